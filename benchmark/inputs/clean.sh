@@ -0,0 +1,7 @@
+#!/bin/sh
+
+set -e
+
+rm -f *.out
+rm -rf .ninja*
+rm -rf .turtle*
@@ -0,0 +1,29 @@
+#!/bin/sh
+
+set -e
+
+input_count=1000
+
+cd $(dirname $0)
+rm -rf tmp
+mkdir -p tmp
+cd tmp
+
+(
+  echo rule cat
+  echo "" command = cat \$in \> \$out
+  echo "" description = run faster
+  printf 'build foo.out: cat'
+
+  for index in $(seq 0 $input_count); do
+    input=$index.in
+
+    touch $input
+    printf " $input"
+  done
+
+  echo
+) >build.ninja
+
+cargo install hyperfine
+hyperfine -w 1 ninja turtle
@@ -167,3 +167,18 @@ Feature: Build statement
     And the exit status should not be 0
     Then I run `turtle`
     And the exit status should not be 0
+
+  Scenario: Use paths with escaped spaces
+    Given a file named "build.ninja" with:
+    """
+    rule cp
+      command = cp $in $out
+
+    build foo$ bar: cp baz
+    build qux: cp foo$ bar
+
+    """
+    And a file named "baz" with "baz"
+    When I successfully run `turtle`
+    Then a file named "foo bar" should contain "baz"
+    And a file named "qux" should contain "baz"
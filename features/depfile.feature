@@ -0,0 +1,72 @@
+Feature: Depfile
+  Scenario: Rebuild an output on update of a dependency in a depfile
+    Given a file named "build.ninja" with:
+    """
+    rule cc
+      command = echo hello && cp $in $out && echo "$out: $in bar.h" > $out.d
+      depfile = $out.d
+
+    build foo: cc bar.c
+
+    """
+    And a file named "bar.c" with ""
+    And a file named "bar.h" with ""
+    When I successfully run `turtle`
+    And a file named "bar.h" with "bar"
+    And I successfully run `turtle`
+    Then the stdout should contain exactly:
+    """
+    hello
+    hello
+    """
+
+  Scenario: Do not rebuild an output without update of dependencies in a depfile
+    Given a file named "build.ninja" with:
+    """
+    rule cc
+      command = echo hello && cp $in $out && echo "$out: $in bar.h" > $out.d
+      depfile = $out.d
+
+    build foo: cc bar.c
+
+    """
+    And a file named "bar.c" with ""
+    And a file named "bar.h" with ""
+    When I successfully run `turtle`
+    And I successfully run `turtle`
+    Then the stdout should contain exactly "hello"
+
+  Scenario: Run a rule without a depfile generated
+    Given a file named "build.ninja" with:
+    """
+    rule cc
+      command = cp $in $out
+      depfile = $out.d
+
+    build foo: cc bar.c
+
+    """
+    And a file named "bar.c" with ""
+    When I successfully run `turtle`
+    Then the exit status should be 0
+
+  Scenario: Rebuild on an update of a dependency in MSVC format
+    Given a file named "build.ninja" with:
+    """
+    rule cc
+      command = cp $in $out && echo "Note: including file: bar.h" && echo hello
+      deps = msvc
+
+    build foo: cc bar.c
+
+    """
+    And a file named "bar.c" with ""
+    And a file named "bar.h" with ""
+    And I successfully run `turtle`
+    And a file named "bar.h" with "bar"
+    When I successfully run `turtle`
+    Then the stdout should contain exactly:
+    """
+    hello
+    hello
+    """
@@ -4,6 +4,12 @@ Feature: Error
     Then the exit status should not be 0
     And the stderr should contain "build.ninja"
 
+  @turtle
+  Scenario: Suggest options on a missing root build file
+    When I run `turtle`
+    Then the exit status should be 3
+    And the stderr should contain "use -f to specify a build file or -C to change a directory"
+
   Scenario: Fail to read a included build file
     Given a file named "build.ninja" with:
     """
@@ -23,3 +29,183 @@ Feature: Error
     When I run `turtle`
     Then the exit status should not be 0
     And the stderr should contain "foo.ninja"
+
+  @turtle
+  Scenario: Fail on mutually including build files
+    Given a file named "build.ninja" with:
+    """
+    include foo.ninja
+
+    """
+    And a file named "foo.ninja" with:
+    """
+    include build.ninja
+
+    """
+    When I run `turtle`
+    Then the exit status should not be 0
+    And the stderr should contain "build file dependency cycle detected"
+    And the stderr should contain "foo.ninja -> "
+
+  @turtle
+  Scenario: Fail to compile duplicate outputs
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+
+    build foo: touch
+    build foo: touch
+
+    """
+    When I run `turtle`
+    Then the exit status should not be 0
+    And the stderr should contain "multiple builds generate output \"foo\""
+
+  @turtle
+  Scenario: Warn duplicate outputs
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+
+    build foo: touch
+    build foo: touch
+
+    """
+    When I successfully run `turtle -w dupbuild=warn`
+    Then the stderr should contain "warning: multiple builds generate output \"foo\""
+    And a file named "foo" should exist
+
+  @turtle
+  Scenario: Ignore duplicate outputs
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+
+    build foo: touch
+    build foo: touch
+
+    """
+    When I successfully run `turtle -w dupbuild=off`
+    Then the stderr should not contain "multiple builds"
+    And a file named "foo" should exist
+
+  @turtle
+  Scenario: Fail on an unknown warning
+    Given a file named "build.ninja" with:
+    """
+    """
+    When I run `turtle -w foo=warn`
+    Then the exit status should not be 0
+    And the stderr should contain "valid warnings: dead-output, dupbuild, emptycmd, undefvar"
+
+  @turtle
+  Scenario: Report a position of a syntax error
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+
+    build foo touch
+
+    """
+    When I run `turtle`
+    Then the exit status should be 3
+    And the stderr should contain "build.ninja:4:1: unexpected \"build foo touch\""
+
+  @turtle
+  Scenario: Report missing inputs before running builds
+    Given a file named "build.ninja" with:
+    """
+    rule cp
+      command = cp $in $out
+
+    build foo: cp bar
+    build baz: cp qux
+    build all: phony foo baz
+
+    """
+    When I run `turtle --check`
+    Then the exit status should be 1
+    And the stderr should contain "files \"bar\", \"qux\" not found"
+
+  @turtle
+  Scenario: Warn undefined variables in commands
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out $outt
+
+    build foo: touch
+
+    """
+    When I successfully run `turtle`
+    Then the stderr should contain "warning: undefined variable \"outt\" in rule \"touch\" of build \"foo\""
+
+  @turtle
+  Scenario: Fail on undefined variables in commands
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out $outt
+
+    build foo: touch
+
+    """
+    When I run `turtle -w undefvar=err`
+    Then the exit status should be 3
+    And the stderr should contain "undefined variable \"outt\" in rule \"touch\" of build \"foo\""
+
+  @turtle
+  Scenario: Warn about empty commands
+    Given a file named "build.ninja" with:
+    """
+    rule echo
+      command = $cmd
+
+    build foo: echo
+
+    """
+    When I successfully run `turtle -w undefvar=off`
+    Then the stderr should contain "empty command in rule \"echo\" of build \"foo\""
+
+  @turtle
+  Scenario: Fail on empty commands
+    Given a file named "build.ninja" with:
+    """
+    rule echo
+      command = $cmd
+
+    build foo: echo
+
+    """
+    When I run `turtle -w emptycmd=err`
+    Then the exit status should be 3
+    And the stderr should contain "empty command in rule \"echo\" of build \"foo\""
+
+  @turtle
+  Scenario: Print errors in JSON
+    Given a file named "build.ninja" with:
+    """
+    build foo: bar
+
+    """
+    When I run `turtle --error-format json`
+    Then the exit status should be 3
+    And the stderr should contain exactly:
+    """
+    {"kind":"compile","message":"rule \"bar\" not found"}
+    """
+
+  @turtle
+  Scenario: Exit with a distinct code on dependency cycles
+    Given a file named "build.ninja" with:
+    """
+    build foo: phony bar
+    build bar: phony foo
+
+    """
+    When I run `turtle`
+    Then the exit status should be 4
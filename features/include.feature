@@ -34,3 +34,26 @@ Feature: Include statement
     """
     When I successfully run `turtle`
     Then the stdout should contain exactly "hello"
+
+  @turtle
+  Scenario: Include a build file relative to a nested build file
+    Given a file named "build.ninja" with:
+    """
+    include foo/foo.ninja
+
+    build foo: hello
+
+    """
+    And a file named "foo/foo.ninja" with:
+    """
+    include bar/bar.ninja
+
+    """
+    And a file named "foo/bar/bar.ninja" with:
+    """
+    rule hello
+      command = echo hello
+
+    """
+    When I successfully run `turtle`
+    Then the stdout should contain exactly "hello"
@@ -21,3 +21,470 @@ Feature: Command line options
     When I run `turtle --log-prefix tomato`
     Then the exit status should not be 0
     And the stderr should contain "tomato"
+
+  @turtle
+  Scenario: Build with a symbolic link to a build file without canonicalization
+    Given a file named "foo/build.ninja" with:
+    """
+    rule echo
+      command = echo hello
+
+    build foo: echo
+
+    """
+    And I successfully run `ln -s foo/build.ninja build.ninja`
+    When I successfully run `turtle --no-canonicalize`
+    Then the stdout should contain exactly "hello"
+
+  @turtle
+  Scenario: Warn about dead outputs
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+
+    build foo: touch
+    build bar: touch
+
+    default foo
+
+    """
+    When I successfully run `turtle -w dead-output`
+    Then the stderr should contain "warning: outputs never used: bar"
+
+  @turtle
+  Scenario: Print commands without running them
+    Given a file named "build.ninja" with:
+    """
+    rule cp
+      command = cp $in $out
+
+    build bar: cp baz
+    build foo: cp bar
+
+    """
+    And a file named "baz" with ""
+    When I successfully run `turtle -n`
+    Then the stdout should contain exactly:
+    """
+    cp baz bar
+    cp bar foo
+    """
+    And a file named "foo" should not exist
+
+  @turtle
+  Scenario: Keep going after failures of builds
+    Given a file named "build.ninja" with:
+    """
+    rule fail
+      command = exit 1
+    rule touch
+      command = touch $out
+    rule cp
+      command = cp $in $out
+
+    build foo: fail
+    build bar: fail
+    build baz: touch
+    build qux: cp foo baz
+
+    """
+    When I run `turtle -k 0`
+    Then the exit status should not be 0
+    And the stderr should contain "2 builds failed"
+    And a file named "baz" should exist
+    And a file named "qux" should not exist
+
+  @turtle
+  Scenario: Stop running commands at a failure limit
+    Given a file named "build.ninja" with:
+    """
+    rule fail
+      command = touch $out.log && false
+
+    build foo: fail
+    build bar: fail
+    build baz: fail
+
+    """
+    When I run `turtle -j 1 --max-fail 2`
+    Then the exit status should not be 0
+    And the stderr should contain "2 builds failed"
+
+  @turtle
+  Scenario: Build downstream outputs
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+
+    build foo: touch
+    build bar: touch foo
+    build baz: touch bar
+    build qux: touch
+
+    """
+    When I successfully run `turtle --downstream bar`
+    Then a file named "foo" should exist
+    And a file named "bar" should exist
+    And a file named "baz" should exist
+    And a file named "qux" should not exist
+
+  @turtle
+  Scenario: Verify outputs
+    Given a file named "build.ninja" with:
+    """
+    rule true
+      command = true
+
+    build foo: true
+
+    """
+    When I run `turtle --verify-outputs`
+    Then the exit status should be 1
+    And the stderr should contain "outputs \"foo\" missing after builds"
+
+  @turtle
+  Scenario: Protect source files
+    Given a file named "build.ninja" with:
+    """
+    rule echo
+      command = echo built > $out
+
+    rule cp
+      command = cp $in $out
+
+    build foo: echo
+    build bar: cp foo
+
+    """
+    And a file named "foo" with "source"
+    When I run `turtle --protect-sources`
+    Then the exit status should be 1
+    And the stderr should contain "outputs \"foo\" would overwrite source files"
+    And a file named "foo" should contain "source"
+
+  Scenario: Show commands
+    Given a file named "build.ninja" with:
+    """
+    rule echo
+      command = echo hello
+      description = world
+
+    build foo: echo
+
+    """
+    When I successfully run `turtle -v`
+    Then the stderr should contain "echo hello"
+
+  @turtle
+  Scenario: Show progress
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+      description = touch $out
+
+    build foo: touch
+    build bar: touch foo
+
+    """
+    When I successfully run `turtle`
+    Then the stderr should contain "[1/2] touch foo"
+    And the stderr should contain "[2/2] touch bar"
+
+  @turtle
+  Scenario: Hide progress
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+      description = touch $out
+
+    build foo: touch
+
+    """
+    When I successfully run `turtle --quiet`
+    Then the stderr should not contain "touch foo"
+    And the stderr should contain "built 1 target in"
+
+  @turtle
+  Scenario: Show nothing
+    Given a file named "build.ninja" with:
+    """
+    rule echo
+      command = echo hello && false
+      description = echo $out
+
+    build foo: echo
+
+    """
+    When I run `turtle --silent`
+    Then the exit status should be 1
+    And the stdout should not contain "hello"
+    And the stderr should not contain "echo foo"
+    And the stderr should not contain "build failed"
+
+  @turtle
+  Scenario: Show outputs of jobs contiguously
+    Given a file named "build.ninja" with:
+    """
+    rule echo
+      command = sleep $delay && echo $out >&2
+      description = $out
+
+    build foo: echo
+      delay = 1
+    build bar: echo
+      delay = 0
+
+    """
+    When I successfully run `turtle -j 2 foo bar`
+    Then the stderr should contain:
+    """
+    [1/2] bar
+    bar
+    [2/2] foo
+    foo
+    """
+
+  @turtle
+  Scenario: Time out a command
+    Given a file named "build.ninja" with:
+    """
+    rule slow
+      command = sleep 10 && touch $out
+
+    build foo: slow
+
+    """
+    When I run `turtle --timeout 1`
+    Then the exit status should be 1
+    And the stderr should contain "command timed out: sleep 10 && touch foo"
+    And a file named "foo" should not exist
+
+  Scenario: Limit a load average
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+
+    build foo: touch
+
+    """
+    When I successfully run `turtle -l 1000`
+    Then a file named "foo" should exist
+
+  @turtle
+  Scenario: Rerun builds on switch of hash algorithms
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+      description = touch $out
+
+    build foo: touch
+
+    """
+    And I successfully run `turtle`
+    When I successfully run `turtle --hash std`
+    Then the stderr should contain "touch foo"
+
+  @turtle
+  Scenario: Emit a build configuration in JSON
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+
+    build foo: touch
+
+    """
+    When I successfully run `turtle --emit-json`
+    Then the stdout should contain "\"command\": \"touch foo\""
+    And a file named "foo" should not exist
+
+  @turtle
+  Scenario: Write profile timings in JSON
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+
+    build foo: touch
+
+    """
+    When I successfully run `turtle --profile-json profile.json`
+    Then a file named "profile.json" should contain "\"output\": \"foo\""
+
+  @turtle
+  Scenario: Write a trace of builds
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+
+    build foo: touch
+
+    """
+    When I successfully run `turtle --trace trace.json`
+    Then a file named "trace.json" should contain "\"name\": \"foo\""
+
+  @turtle
+  Scenario: Color diagnostics
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+
+    build foo: touch
+
+    """
+    When I successfully run `turtle --color always`
+    Then the stderr should contain "[32mbuilt 1 target in"
+
+  @turtle
+  Scenario: Disable colors of diagnostics
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+
+    build foo: touch
+
+    """
+    When I successfully run `turtle --color never`
+    Then the stderr should not contain "[32m"
+
+  @turtle
+  Scenario: Format status lines
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+      description = touch $out
+
+    build foo: touch
+
+    """
+    When I successfully run `turtle --status "(%f of %t) "`
+    Then the stderr should contain "(1 of 1) touch foo"
+
+  @turtle
+  Scenario: Set a build directory
+    Given a file named "build.ninja" with:
+    """
+    builddir = foo
+
+    rule touch
+      command = touch $out
+
+    build bar: touch
+
+    """
+    When I successfully run `turtle --build-dir baz`
+    Then a directory named "baz/.turtle" should exist
+    And a directory named "foo" should not exist
+
+  @turtle
+  Scenario: Rebuild outputs without a cache
+    Given a file named "build.ninja" with:
+    """
+    rule echo
+      command = echo hello > $out
+
+    build foo: echo
+
+    """
+    And I successfully run `turtle`
+    When I successfully run `turtle --no-cache`
+    Then the stderr should contain "built 1 target in"
+
+  @turtle
+  Scenario: Force builds
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+
+    build foo: touch
+
+    """
+    And I successfully run `turtle`
+    When I successfully run `turtle -B -v`
+    Then the stderr should contain "touch foo"
+
+  @turtle
+  Scenario: Print commands of forced builds
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+
+    build foo: touch
+
+    """
+    And I successfully run `turtle`
+    When I successfully run `turtle -B -n`
+    Then the stdout should contain "touch foo"
+
+  @turtle
+  Scenario: Explain builds
+    Given a file named "build.ninja" with:
+    """
+    rule cp
+      command = cp $in $out
+
+    build foo: cp bar
+
+    """
+    And a file named "bar" with ""
+    When I successfully run `turtle --explain`
+    Then the stderr should contain "explain: foo: output foo missing"
+
+  @turtle
+  Scenario: Explain builds with changed commands
+    Given a file named "build.ninja" with:
+    """
+    rule cp
+      command = cp $in $out
+
+    build foo: cp bar
+
+    """
+    And a file named "bar" with ""
+    And I successfully run `turtle`
+    And a file named "build.ninja" with:
+    """
+    rule cp
+      command = cp -p $in $out
+
+    build foo: cp bar
+
+    """
+    When I successfully run `turtle --explain`
+    Then the stderr should contain "explain: foo: command changed"
+
+  @turtle
+  Scenario: Build multiple root build files
+    Given a file named "rules.ninja" with:
+    """
+    rule touch
+      command = touch $out
+
+    """
+    And a file named "foo.ninja" with:
+    """
+    include rules.ninja
+    build foo: touch
+
+    """
+    And a file named "bar.ninja" with:
+    """
+    include rules.ninja
+    build bar: touch
+
+    """
+    When I successfully run `turtle -f foo.ninja -f bar.ninja`
+    Then a file named "foo" should exist
+    And a file named "bar" should exist
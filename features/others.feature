@@ -69,3 +69,71 @@ Feature: Others
     """
     When I successfully run `turtle foo`
     Then the stdout should contain exactly "hello"
+
+  Scenario: Specify an output with a relative path
+    Given a file named "build.ninja" with:
+    """
+    rule echo
+      command = echo hello
+
+    build foo: echo
+    build bar: echo
+
+    """
+    When I successfully run `turtle ./foo`
+    Then the stdout should contain exactly "hello"
+
+  Scenario: Fail to find an output
+    Given a file named "build.ninja" with:
+    """
+    rule echo
+      command = echo hello
+
+    build foo: echo
+
+    """
+    When I run `turtle bar`
+    Then the exit status should not be 0
+    And the stderr should contain "bar"
+
+  @turtle
+  Scenario: Summarize builds
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+
+    build foo: touch
+
+    """
+    When I successfully run `turtle`
+    Then the stderr should contain "built 1 target in"
+
+  @turtle
+  Scenario: Summarize up-to-date builds
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+
+    build foo: touch
+
+    """
+    And I successfully run `turtle`
+    When I successfully run `turtle`
+    Then the stderr should contain "built 0 targets in"
+
+  @turtle
+  Scenario: Override a variable on the command line
+    Given a file named "build.ninja" with:
+    """
+    message = foo
+
+    rule echo
+      command = echo $message > $out
+
+    build foo: echo
+
+    """
+    When I successfully run `turtle message=bar`
+    Then a file named "foo" should contain "bar"
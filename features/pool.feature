@@ -0,0 +1,52 @@
+Feature: Pool statement
+  Scenario: Run jobs in a pool serially
+    Given a file named "build.ninja" with:
+    """
+    pool serial
+      depth = 1
+
+    rule lock
+      command = mkdir lock && sleep 0.1 && rmdir lock && touch $out
+      pool = serial
+
+    build foo: lock
+    build bar: lock
+    build baz: lock
+
+    """
+    When I successfully run `turtle -j 4`
+    Then a file named "foo" should exist
+    And a file named "bar" should exist
+    And a file named "baz" should exist
+
+  Scenario: Set a pool in a build statement
+    Given a file named "build.ninja" with:
+    """
+    pool serial
+      depth = 1
+
+    rule lock
+      command = mkdir lock && sleep 0.1 && rmdir lock && touch $out
+
+    build foo: lock
+      pool = serial
+    build bar: lock
+      pool = serial
+
+    """
+    When I successfully run `turtle -j 4`
+    Then a file named "foo" should exist
+    And a file named "bar" should exist
+
+  Scenario: Run a job in a console pool
+    Given a file named "build.ninja" with:
+    """
+    rule echo
+      command = echo hello
+      pool = console
+
+    build foo: echo
+
+    """
+    When I successfully run `turtle`
+    Then the stdout should contain exactly "hello"
@@ -122,7 +122,8 @@ Feature: Rule statement
     When I successfully run `turtle`
     Then the file named "foo" should exist
 
-  Scenario: Rebuild for a phony dependency
+  @turtle
+  Scenario: Do not rebuild for a phony dependency without inputs
     Given a file named "build.ninja" with:
     """
     rule hello
@@ -136,8 +137,256 @@ Feature: Rule statement
     """
     When I successfully run `turtle`
     And I successfully run `turtle`
+    Then the stdout should contain exactly "hello"
+
+  Scenario: Rebuild for an updated input of a phony dependency
+    Given a file named "build.ninja" with:
+    """
+    rule hello
+      command = echo hello && touch $out
+
+    build foo: phony bar
+    build baz: hello foo
+
+    default baz
+
+    """
+    And a file named "bar" with ""
+    When I successfully run `turtle`
+    And a file named "bar" with "bar"
+    And I successfully run `turtle`
     Then the stdout should contain exactly:
     """
     hello
     hello
     """
+
+  Scenario: Use comments and blank lines in a rule
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      # Create an output.
+      command = touch $out
+
+      # Describe a job.
+      description = creating $out
+
+    build foo: touch
+
+    """
+    When I successfully run `turtle`
+    Then the file named "foo" should exist
+    And the stderr should contain "creating foo"
+
+  Scenario: Use a variable with braces
+    Given a file named "build.ninja" with:
+    """
+    x = foo
+
+    rule echo
+      command = echo ${x}bar
+
+    build baz: echo
+
+    """
+    When I successfully run `turtle`
+    Then the stdout should contain exactly "foobar"
+
+  Scenario: Do not rebuild dependents of a restat rule with outputs unchanged
+    Given a file named "build.ninja" with:
+    """
+    rule gen
+      command = cut -c 1 $in > $out.tmp && (cmp -s $out.tmp $out || cp $out.tmp $out)
+      restat = 1
+
+    rule cp
+      command = echo hello && cp $in $out
+
+    build foo: gen bar
+    build baz: cp foo
+
+    """
+    And a file named "bar" with "a"
+    When I successfully run `turtle`
+    And a file named "bar" with "ab"
+    And I successfully run `turtle`
+    Then the stdout should contain exactly "hello"
+
+  Scenario: Regenerate a build file
+    Given a file named "build.ninja" with:
+    """
+    rule regenerate
+      command = cp $in $out
+      generator = 1
+
+    rule echo
+      command = echo $message
+
+    build build.ninja: regenerate build.ninja.in
+    build foo: echo
+      message = foo
+
+    """
+    And a file named "build.ninja.in" with:
+    """
+    rule regenerate
+      command = cp $in $out
+      generator = 1
+
+    rule echo
+      command = echo $message
+
+    build build.ninja: regenerate build.ninja.in
+    build foo: echo
+      message = bar
+
+    """
+    When I successfully run `turtle foo`
+    Then the stdout should contain exactly "bar"
+
+  @turtle
+  Scenario: Show regeneration of a build file in dry run
+    Given a file named "build.ninja" with:
+    """
+    rule regenerate
+      command = cp $in $out
+      generator = 1
+
+    rule echo
+      command = echo $message
+
+    build build.ninja: regenerate build.ninja.in
+    build foo: echo
+      message = foo
+
+    """
+    And a file named "build.ninja.in" with:
+    """
+    """
+    When I successfully run `turtle -n foo`
+    Then the stdout should contain "cp build.ninja.in build.ninja"
+    And the stdout should contain "echo foo"
+
+  Scenario: Use a response file
+    Given a file named "build.ninja" with:
+    """
+    rule cat
+      command = cat $rspfile > $out
+      rspfile = $out.rsp
+      rspfile_content = $in
+
+    build foo: cat bar baz
+
+    """
+    And a file named "bar" with ""
+    And a file named "baz" with ""
+    When I successfully run `turtle`
+    Then a file named "foo" should contain "bar baz"
+    And a file named "foo.rsp" should not exist
+
+  Scenario: Use a response file with newline-separated inputs
+    Given a file named "build.ninja" with:
+    """
+    rule cat
+      command = cat $rspfile > $out
+      rspfile = $out.rsp
+      rspfile_content = $in_newline
+
+    build foo: cat bar baz
+
+    """
+    And a file named "bar" with ""
+    And a file named "baz" with ""
+    When I successfully run `turtle`
+    Then a file named "foo" should contain:
+    """
+    bar
+    baz
+    """
+
+  @turtle
+  Scenario: Use hash signs in commands
+    Given a file named "build.ninja" with:
+    """
+    rule echo
+      command = echo "$#1 x#y"
+
+    build foo: echo
+
+    """
+    When I successfully run `turtle`
+    Then the stdout should contain exactly "#1 x#y"
+
+  Scenario: Continue lines in commands
+    Given a file named "build.ninja" with:
+    """
+    rule echo
+      command = echo $
+        hello
+
+    build foo: echo
+
+    """
+    When I successfully run `turtle`
+    Then the stdout should contain exactly "hello"
+
+  @turtle
+  Scenario: Run a command in a directory
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch bar
+      chdir = foo
+
+    build foo/bar: touch
+
+    """
+    And a directory named "foo"
+    When I successfully run `turtle`
+    Then a file named "foo/bar" should exist
+
+  @turtle
+  Scenario: Run a command in a directory relative to a build directory
+    Given a file named "foo/build.ninja" with:
+    """
+    builddir = foo
+
+    rule touch
+      command = touch bar
+      chdir = baz
+
+    build foo/baz/bar: touch
+
+    """
+    And a directory named "foo/baz"
+    When I successfully run `turtle -f foo/build.ninja`
+    Then a file named "foo/baz/bar" should exist
+
+  @turtle
+  Scenario: Run a command with environment variables
+    Given a file named "build.ninja" with:
+    """
+    rule echo
+      command = echo $$FOO > $out
+      environment = FOO=bar
+
+    build foo: echo
+
+    """
+    When I successfully run `turtle`
+    Then a file named "foo" should contain "bar"
+
+  @turtle
+  Scenario: Use a first input
+    Given a file named "build.ninja" with:
+    """
+    rule cp
+      command = cp $in_first $out
+
+    build foo: cp bar baz
+
+    """
+    And a file named "bar" with "bar"
+    And a file named "baz" with "baz"
+    When I successfully run `turtle`
+    Then a file named "foo" should contain "bar"
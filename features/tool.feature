@@ -1,4 +1,68 @@
 Feature: Tool
+  @turtle
+  Scenario: Clean outputs
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+
+    build foo: touch
+
+    """
+    And I successfully run `turtle`
+    When I successfully run `turtle -t clean`
+    Then the file "foo" should not exist
+    And the stdout should contain "1 file removed"
+
+  @turtle
+  Scenario: Do not clean ignored outputs
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+
+    build foo.o: touch
+    build bar: touch
+
+    """
+    And a file named ".turtleignore" with:
+    """
+    *.o
+    """
+    And I successfully run `turtle`
+    When I successfully run `turtle -t clean`
+    Then the stdout should contain "foo.o skipped (ignored)"
+    And a file named "foo.o" should exist
+    And a file named "bar" should not exist
+
+  @turtle
+  Scenario: Do not clean outputs on dry run
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+
+    build foo: touch
+
+    """
+    And I successfully run `turtle`
+    When I successfully run `turtle -t clean -n`
+    Then the file "foo" should exist
+
+  Scenario: Do not clean outputs of generator rules
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+      generator = 1
+
+    build foo: touch
+
+    """
+    And I successfully run `turtle`
+    When I successfully run `turtle -t clean`
+    Then the file "foo" should exist
+
   Scenario: Clean dead outputs
     Given a file named "build.ninja" with:
     """
@@ -28,3 +92,208 @@ Feature: Tool
     And I successfully run `turtle`
     When I successfully run `turtle -t cleandead`
     Then the file "foo" should exist
+
+  @turtle
+  Scenario: Print a dependency graph
+    Given a file named "build.ninja" with:
+    """
+    rule cp
+      command = cp $in $out
+
+    build foo: cp bar
+
+    """
+    When I successfully run `turtle -t graph`
+    Then the stdout should contain "digraph"
+    And the stdout should contain:
+    """
+    "bar" -> "foo"
+    """
+
+  @turtle
+  Scenario: List targets
+    Given a file named "build.ninja" with:
+    """
+    rule cp
+      command = cp $in $out
+
+    build foo: cp bar
+    build baz: phony foo
+
+    """
+    When I successfully run `turtle -t targets all`
+    Then the stdout should contain exactly:
+    """
+    baz: phony
+    foo: cp
+    """
+
+  @turtle
+  Scenario: List default targets
+    Given a file named "build.ninja" with:
+    """
+    rule cp
+      command = cp $in $out
+
+    build foo: cp bar
+    build baz: cp bar
+
+    default foo
+
+    """
+    When I successfully run `turtle -t targets default`
+    Then the stdout should contain exactly "foo"
+
+  Scenario: Print commands
+    Given a file named "build.ninja" with:
+    """
+    rule cp
+      command = cp $in $out
+
+    build foo: cp bar
+    build bar: cp baz
+
+    """
+    When I successfully run `turtle -t commands foo`
+    Then the stdout should contain exactly:
+    """
+    cp baz bar
+    cp bar foo
+    """
+
+  Scenario: Print a compilation database
+    Given a file named "build.ninja" with:
+    """
+    rule cc
+      command = cp $in $out
+
+    build foo.o: cc foo.c
+
+    """
+    When I successfully run `turtle -t compdb cc`
+    Then the stdout should contain:
+    """
+    "command": "cp foo.c foo.o"
+    """
+
+  @turtle
+  Scenario: Do not clean dead outputs used as sources
+    Given a file named "build.ninja" with:
+    """
+    rule touch
+      command = touch $out
+
+    build foo: touch
+
+    """
+    And I successfully run `turtle`
+    When a file named "build.ninja" with:
+    """
+    rule cp
+      command = cp $in $out
+
+    build bar: cp foo
+
+    """
+    And I successfully run `turtle -t cleandead`
+    Then the file "foo" should exist
+
+  @turtle
+  Scenario: Query an output
+    Given a file named "build.ninja" with:
+    """
+    rule cp
+      command = cp $in $out
+
+    build foo: cp bar
+    build baz: cp foo
+
+    """
+    When I successfully run `turtle -t query foo`
+    Then the stdout should contain exactly:
+    """
+    foo:
+      input: cp
+        bar
+      outputs:
+        baz
+    """
+
+  @turtle
+  Scenario: List rules
+    Given a file named "build.ninja" with:
+    """
+    rule cp
+      command = cp $in $out
+
+    rule cc
+      command = cc -o $out $in
+
+    """
+    When I successfully run `turtle -t rules -- -a`
+    Then the stdout should contain exactly:
+    """
+    cc: cc -o $out $in
+    cp: cp $in $out
+    """
+
+  @turtle
+  Scenario: Print a dependency path
+    Given a file named "build.ninja" with:
+    """
+    rule cp
+      command = cp $in $out
+
+    build foo: cp bar
+    build bar: cp baz
+
+    """
+    When I successfully run `turtle -t path foo baz`
+    Then the stdout should contain exactly:
+    """
+    foo
+    bar
+    baz
+    """
+
+  @turtle
+  Scenario: Fail to find a dependency path
+    Given a file named "build.ninja" with:
+    """
+    rule cp
+      command = cp $in $out
+
+    build foo: cp bar
+    build baz: cp bar
+
+    """
+    When I run `turtle -t path foo baz`
+    Then the exit status should not be 0
+    And the stderr should contain "no dependency path from \"foo\" to \"baz\""
+
+  @turtle
+  Scenario: Show dependencies discovered via depfiles
+    Given a file named "build.ninja" with:
+    """
+    rule cc
+      command = cp $in $out && echo "$out: $in foo.h" > $out.d
+      depfile = $out.d
+
+    build foo.o: cc foo.c
+
+    """
+    And a file named "foo.c" with ""
+    And a file named "foo.h" with ""
+    And I successfully run `turtle`
+    When I successfully run `turtle -t deps foo.o`
+    Then the stdout should contain exactly:
+    """
+    foo.o: #deps 2
+        foo.c
+        foo.h
+    """
+
+  Scenario: List tools without build files
+    When I successfully run `turtle -t list`
+    Then the stdout should contain "clean "
+    And the stdout should contain "targets "
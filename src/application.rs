@@ -0,0 +1,1974 @@
+use crate::{
+    arguments::{Arguments, ColorMode, ErrorFormat, Hash, Tool},
+    ast::{Module, Statement},
+    color::{self, Color},
+    compile::{self, compile},
+    configuration_json,
+    context::Context,
+    dead_output, downstream,
+    error::ApplicationError,
+    error_json,
+    hash_algorithm::HashAlgorithm,
+    ignore::IgnoreList,
+    infrastructure::{self, OsConsole, OsFileSystem},
+    ir::Configuration,
+    module_dependency::{self, ModuleDependencyMap},
+    parse::parse,
+    run, tool,
+    verbosity::Verbosity,
+    warning::WarningLevel,
+    watch,
+};
+use clap::Parser;
+use futures::future::try_join_all;
+use itertools::Itertools;
+use std::{
+    collections::{HashMap, HashSet},
+    env::{self, set_current_dir},
+    future::pending,
+    io::{self, stderr, IsTerminal},
+    path::{Path, PathBuf},
+    process::exit,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::{select, signal::ctrl_c, time::sleep};
+
+const DEFAULT_BUILD_FILE: &str = "build.ninja";
+const IGNORE_FILE: &str = ".turtleignore";
+const DATABASE_DIRECTORY: &str = ".turtle";
+const OPEN_FILE_LIMIT: usize = if cfg!(target_os = "macos") { 256 } else { 1024 };
+const DEFAULT_FILE_COUNT_PER_PROCESS: usize = 3; // stdin, stdout, and stderr
+const INITIAL_RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+// Runs Turtle as a command with arguments of a current process.
+pub async fn main() {
+    let arguments = Arguments::parse();
+    let job_limit = arguments.job_limit.unwrap_or_else(num_cpus::get);
+    let context = Context::new(
+        OsConsole::new(),
+        OsFileSystem::new(
+            OPEN_FILE_LIMIT
+                .saturating_sub(DEFAULT_FILE_COUNT_PER_PROCESS * (job_limit + 1))
+                .max(1),
+            !arguments.no_canonicalize,
+        ),
+    )
+    .into();
+
+    if let Err(error) = execute_root(&context, &arguments).await {
+        report_error(&context, &arguments, &error).await.unwrap();
+
+        // Snapshots of databases are not removed on exit as their destructors
+        // never run.
+        let _ = context.database().remove_snapshot();
+
+        // Delay for the error message to be written completely hopefully.
+        sleep(Duration::from_millis(1)).await;
+
+        exit(error.exit_code())
+    }
+}
+
+async fn execute_root(
+    context: &Arc<Context>,
+    arguments: &Arguments,
+) -> Result<(), ApplicationError> {
+    if let Some(directory) = &arguments.directory {
+        set_current_dir(directory)?;
+    }
+
+    if arguments.watch {
+        execute_with_watch(context, arguments).await
+    } else {
+        execute_with_retry(context, arguments).await
+    }
+}
+
+// Reruns builds on every change of build files, inputs, or outputs until
+// interruption. Build errors are reported but do not stop watching.
+async fn execute_with_watch(
+    context: &Arc<Context>,
+    arguments: &Arguments,
+) -> Result<(), ApplicationError> {
+    loop {
+        match execute_with_retry(context, arguments).await {
+            Ok(()) => {}
+            Err(ApplicationError::Interrupted) => return Err(ApplicationError::Interrupted),
+            Err(error) => report_error(context, arguments, &error).await?,
+        }
+
+        let paths = watched_paths(context, arguments).await;
+
+        select! {
+            result = watch::wait_changes(&paths) => result?,
+            result = ctrl_c() => return Ok(result?),
+        }
+    }
+}
+
+// Builds are interrupted by a signal only in the application so that libraries
+// do not take over signal handling of their users.
+async fn interrupt_signal() {
+    if ctrl_c().await.is_err() {
+        pending::<()>().await;
+    }
+}
+
+// Collects paths of build files, inputs, and outputs. As build files can be
+// broken while watching, only valid ones are collected.
+async fn watched_paths(context: &Context, arguments: &Arguments) -> Vec<PathBuf> {
+    let Ok(root_module_paths) = root_module_paths(context, arguments).await else {
+        return root_module_files(arguments)
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+    };
+    let Ok((modules, dependencies)) = parse_modules(context, &root_module_paths).await else {
+        return root_module_paths;
+    };
+    let mut paths = modules.keys().cloned().collect::<Vec<_>>();
+
+    if module_dependency::validate(&dependencies).is_ok() {
+        if let Ok((configuration, _)) = compile(
+            &modules,
+            &dependencies,
+            &root_module_paths,
+            &compile_options(arguments),
+        ) {
+            paths.extend(
+                configuration
+                    .outputs()
+                    .values()
+                    .flat_map(|build| {
+                        build
+                            .outputs()
+                            .iter()
+                            .chain(build.implicit_outputs())
+                            .chain(build.inputs())
+                    })
+                    .map(|path| PathBuf::from(path.as_ref())),
+            );
+        }
+    }
+
+    paths.sort();
+    paths.dedup();
+
+    paths
+}
+
+async fn execute_with_retry(
+    context: &Arc<Context>,
+    arguments: &Arguments,
+) -> Result<(), ApplicationError> {
+    let mut interval = INITIAL_RETRY_INTERVAL;
+
+    for _ in 0..arguments.retry_infra {
+        match execute(context, arguments).await {
+            Err(error) if error.is_transient() => {
+                sleep(interval).await;
+                interval *= 2;
+            }
+            result => return result,
+        }
+    }
+
+    execute(context, arguments).await
+}
+
+async fn execute(context: &Arc<Context>, arguments: &Arguments) -> Result<(), ApplicationError> {
+    // Tools are listed without any build file.
+    if let Some(Tool::List) = arguments.tool {
+        return tool::list(context).await;
+    }
+
+    let root_module_paths = root_module_paths(context, arguments).await?;
+    let configuration = load_configuration(context, arguments, &root_module_paths).await?;
+
+    let level = arguments.warning_configuration().dead_output;
+
+    if level != WarningLevel::Off {
+        let outputs = dead_output::find(&configuration);
+
+        if !outputs.is_empty() {
+            let error = ApplicationError::DeadOutputs(outputs);
+
+            if level == WarningLevel::Err {
+                return Err(error);
+            }
+
+            warn(context, arguments, &error.to_string()).await?;
+        }
+    }
+
+    if arguments.emit_json {
+        return configuration_json::emit(context, &configuration).await;
+    }
+
+    let build_directory = build_directory(arguments, &configuration, &root_module_paths[0]);
+
+    initialize_database(
+        context,
+        arguments,
+        &build_directory
+            .join(DATABASE_DIRECTORY)
+            .join(env!("CARGO_PKG_VERSION").replace('.', "_")),
+    )
+    .await?;
+
+    if let Some(tool) = &arguments.tool {
+        match tool {
+            Tool::Clean => {
+                tool::clean(
+                    context,
+                    &configuration,
+                    &read_ignore_list(context, build_directory).await?,
+                    arguments.dry_run,
+                )
+                .await?
+            }
+            Tool::CleanDead => {
+                tool::clean_dead(
+                    context,
+                    &configuration,
+                    &read_ignore_list(context, build_directory).await?,
+                )
+                .await?
+            }
+            Tool::Commands => tool::commands(context, &configuration, &arguments.outputs()).await?,
+            Tool::CompDb => {
+                tool::compilation_database(context, &configuration, &arguments.outputs()).await?
+            }
+            Tool::Deps => tool::deps(context, &configuration, &arguments.outputs()).await?,
+            Tool::Graph => tool::graph(context, &configuration, &arguments.outputs()).await?,
+            Tool::List => unreachable!(),
+            Tool::Path => tool::path(context, &configuration, &arguments.outputs()).await?,
+            Tool::Query => tool::query(context, &configuration, &arguments.outputs()).await?,
+            Tool::Rules => {
+                let (modules, _) = parse_modules(context, &root_module_paths).await?;
+
+                // Rules are listed relatively to the first root build file.
+                tool::rules(
+                    context,
+                    &modules,
+                    &root_module_paths[0],
+                    &arguments.outputs(),
+                )
+                .await?
+            }
+            Tool::Targets => tool::targets(context, &configuration, &arguments.outputs()).await?,
+        }
+    } else {
+        let load_limit =
+            if arguments.load_limit.is_some() && infrastructure::load_average().await.is_none() {
+                warn(context, arguments, "load average not available").await?;
+
+                None
+            } else {
+                arguments.load_limit
+            };
+        let options = run::Options {
+            debug: arguments.debug,
+            profile: arguments.profile,
+            profile_file: arguments.profile_json.as_ref().map(PathBuf::from),
+            trace_file: arguments.trace.as_ref().map(PathBuf::from),
+            dry_run: arguments.dry_run,
+            force: arguments.force,
+            explain: arguments.explain,
+            keep_going: arguments.keep_going,
+            job_limit: arguments.job_limit.unwrap_or_else(num_cpus::get),
+            timeout: Some(arguments.timeout)
+                .filter(|&seconds| seconds > 0)
+                .map(Duration::from_secs),
+            load_limit,
+            hash_algorithm: match arguments.hash {
+                Hash::Fnv => HashAlgorithm::Fnv,
+                Hash::Std => HashAlgorithm::Std,
+            },
+            check_inputs: arguments.check,
+            verify_outputs: arguments.verify_outputs,
+            protect_sources: arguments.protect_sources,
+            verbosity: arguments.verbosity(),
+            summary: true,
+            status_format: arguments.status.clone(),
+            color: color_enabled(arguments),
+            verbose: arguments.verbose,
+            log_prefix: arguments.log_prefix.clone(),
+            build_directory: build_directory.into(),
+        };
+        let configuration = if arguments.dry_run {
+            configuration
+        } else {
+            regenerate_configuration(
+                context,
+                arguments,
+                &root_module_paths,
+                configuration,
+                &options,
+            )
+            .await?
+        };
+
+        let outputs = if arguments.downstream.is_empty() {
+            arguments.outputs()
+        } else {
+            arguments
+                .outputs()
+                .into_iter()
+                .chain(downstream::find(&configuration, &arguments.downstream)?)
+                .collect()
+        };
+
+        // Root build files are not regenerated under `-n` but commands to
+        // regenerate them are shown before the other ones.
+        let outputs = if arguments.dry_run {
+            let root_outputs = root_module_outputs(arguments, &configuration);
+
+            if root_outputs.is_empty() {
+                outputs
+            } else if outputs.is_empty() {
+                root_outputs
+                    .into_iter()
+                    .chain(
+                        configuration
+                            .default_outputs()
+                            .iter()
+                            .sorted()
+                            .map(|output| output.to_string()),
+                    )
+                    .collect()
+            } else {
+                root_outputs.into_iter().chain(outputs).collect()
+            }
+        } else {
+            outputs
+        };
+
+        run::run(
+            context,
+            configuration,
+            &outputs,
+            options,
+            interrupt_signal(),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+// Build files given on a command line are independent roots of build
+// configurations.
+fn root_module_files(arguments: &Arguments) -> Vec<&str> {
+    if arguments.files.is_empty() {
+        vec![DEFAULT_BUILD_FILE]
+    } else {
+        arguments.files.iter().map(String::as_str).collect()
+    }
+}
+
+async fn root_module_paths(
+    context: &Context,
+    arguments: &Arguments,
+) -> Result<Vec<PathBuf>, ApplicationError> {
+    let mut paths = vec![];
+
+    for file in root_module_files(arguments) {
+        // Missing build files are reported before canonicalization as it can be
+        // disabled.
+        if let Err(error) = context.file_system().metadata(file.as_ref()).await {
+            return Err(match error.into() {
+                ApplicationError::Io(io::ErrorKind::NotFound, _) => {
+                    ApplicationError::ManifestNotFound(file.into())
+                }
+                error => error,
+            });
+        }
+
+        paths.push(
+            context
+                .file_system()
+                .canonicalize_path(file.as_ref())
+                .await?,
+        );
+    }
+
+    Ok(paths)
+}
+
+async fn load_configuration(
+    context: &Context,
+    arguments: &Arguments,
+    root_module_paths: &[PathBuf],
+) -> Result<Arc<Configuration>, ApplicationError> {
+    let (modules, dependencies) = parse_modules(context, root_module_paths).await?;
+
+    module_dependency::validate(&dependencies)?;
+
+    let (configuration, warnings) = compile(
+        &modules,
+        &dependencies,
+        root_module_paths,
+        &compile_options(arguments),
+    )?;
+
+    for warning in warnings {
+        warn(context, arguments, &warning.to_string()).await?;
+    }
+
+    Ok(configuration.into())
+}
+
+// A temporary database is used without caching so that everything is rebuilt
+// without touching a persistent one.
+async fn initialize_database(
+    context: &Context,
+    arguments: &Arguments,
+    path: &Path,
+) -> Result<(), ApplicationError> {
+    if arguments.no_cache {
+        return Ok(context.database().initialize_temporary()?);
+    } else if arguments.tool.as_ref().is_some_and(Tool::is_read_only) {
+        return Ok(context.database().initialize_read_only(path)?);
+    }
+
+    async {
+        context.file_system().create_directory(path).await?;
+        context.database().initialize(path)?;
+
+        Ok(())
+    }
+    .await
+    .map_err(|error: ApplicationError| ApplicationError::Database(path.into(), error.into()))
+}
+
+// A build directory given on a command line takes precedence over the one in
+// build files. Relative paths are resolved from a current directory.
+fn build_directory<'a>(
+    arguments: &'a Arguments,
+    configuration: &'a Configuration,
+    root_module_path: &'a Path,
+) -> &'a Path {
+    arguments
+        .build_dir
+        .as_deref()
+        .or(configuration
+            .build_directory()
+            .map(|string| string.as_ref()))
+        .map(Path::new)
+        .unwrap_or_else(|| root_module_path.parent().unwrap())
+}
+
+// A missing ignore file means no file is ignored.
+async fn read_ignore_list(
+    context: &Context,
+    build_directory: &Path,
+) -> Result<IgnoreList, ApplicationError> {
+    let path = build_directory.join(IGNORE_FILE);
+
+    if context.file_system().metadata(&path).await.is_err() {
+        return Ok(Default::default());
+    }
+
+    let mut source = String::new();
+
+    context
+        .file_system()
+        .read_file_to_string(&path, &mut source)
+        .await?;
+
+    Ok(IgnoreList::parse(&source))
+}
+
+fn compile_options(arguments: &Arguments) -> compile::Options {
+    compile::Options {
+        warnings: arguments.warning_configuration(),
+        variables: arguments.variables(),
+        ..Default::default()
+    }
+}
+
+// Builds root build files first if they are outputs of any builds, and reloads
+// them if any of them are updated.
+async fn regenerate_configuration(
+    context: &Arc<Context>,
+    arguments: &Arguments,
+    root_module_paths: &[PathBuf],
+    configuration: Arc<Configuration>,
+    options: &run::Options,
+) -> Result<Arc<Configuration>, ApplicationError> {
+    let (outputs, paths): (Vec<String>, Vec<&PathBuf>) = root_module_files(arguments)
+        .into_iter()
+        .zip(root_module_paths)
+        .filter(|(output, _)| configuration.outputs().contains_key(*output))
+        .map(|(output, path)| (output.into(), path))
+        .unzip();
+
+    if outputs.is_empty() {
+        return Ok(configuration);
+    }
+
+    let times = modified_times(context, &paths).await?;
+
+    run::run(
+        context,
+        configuration.clone(),
+        &outputs,
+        run::Options {
+            summary: false,
+            ..options.clone()
+        },
+        interrupt_signal(),
+    )
+    .await?;
+
+    if modified_times(context, &paths).await? == times {
+        Ok(configuration)
+    } else {
+        load_configuration(context, arguments, root_module_paths).await
+    }
+}
+
+fn root_module_outputs(arguments: &Arguments, configuration: &Configuration) -> Vec<String> {
+    root_module_files(arguments)
+        .into_iter()
+        .filter(|output| configuration.outputs().contains_key(*output))
+        .map(From::from)
+        .collect()
+}
+
+async fn modified_times(
+    context: &Context,
+    paths: &[&PathBuf],
+) -> Result<Vec<SystemTime>, ApplicationError> {
+    let mut times = vec![];
+
+    for path in paths {
+        times.push(context.file_system().metadata(path).await?.modified_time());
+    }
+
+    Ok(times)
+}
+
+async fn report_error(
+    context: &Context,
+    arguments: &Arguments,
+    error: &ApplicationError,
+) -> Result<(), ApplicationError> {
+    if arguments.verbosity() == Verbosity::Silent {
+        return Ok(());
+    }
+
+    // JSON errors are never prefixed nor colored so that they are parsable.
+    let message = match arguments.error_format {
+        ErrorFormat::Human => format!(
+            "{}{}\n",
+            arguments.log_prefix.as_deref().unwrap_or_default(),
+            color::paint(&error.to_string(), Color::Red, color_enabled(arguments))
+        ),
+        ErrorFormat::Json => error_json::render(error),
+    };
+
+    context
+        .console()
+        .lock()
+        .await
+        .write_stderr(message.as_bytes())
+        .await?;
+
+    Ok(())
+}
+
+async fn warn(
+    context: &Context,
+    arguments: &Arguments,
+    message: &str,
+) -> Result<(), ApplicationError> {
+    if arguments.verbosity() == Verbosity::Silent {
+        return Ok(());
+    }
+
+    context
+        .console()
+        .lock()
+        .await
+        .write_stderr(
+            format!(
+                "{}{}\n",
+                arguments.log_prefix.as_deref().unwrap_or_default(),
+                color::paint(
+                    &format!("warning: {}", message),
+                    Color::Yellow,
+                    color_enabled(arguments)
+                )
+            )
+            .as_bytes(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+// Colors are enabled automatically only on terminals unless the `NO_COLOR`
+// environment variable is set.
+fn color_enabled(arguments: &Arguments) -> bool {
+    match arguments.color {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => {
+            env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()) && stderr().is_terminal()
+        }
+    }
+}
+
+// Parses a root build file and all the others referenced by it.
+pub async fn parse_modules(
+    context: &Context,
+    root_paths: &[PathBuf],
+) -> Result<(HashMap<PathBuf, Module>, ModuleDependencyMap), ApplicationError> {
+    let mut paths = vec![];
+    let mut visited_paths = HashSet::new();
+
+    for path in root_paths {
+        let path = context.file_system().canonicalize_path(path).await?;
+
+        if visited_paths.insert(path.clone()) {
+            paths.push(path);
+        }
+    }
+
+    let mut modules = HashMap::new();
+    let mut dependencies = HashMap::new();
+
+    // Modules discovered at the same time are read concurrently. Each module is
+    // read only once even if it is referenced by multiple modules.
+    while !paths.is_empty() {
+        for (path, module, submodule_paths) in
+            try_join_all(paths.drain(..).map(|path| parse_module(context, path))).await?
+        {
+            paths.extend(
+                submodule_paths
+                    .values()
+                    .filter(|&path| visited_paths.insert(path.clone()))
+                    .cloned(),
+            );
+
+            modules.insert(path.clone(), module);
+            dependencies.insert(path, submodule_paths);
+        }
+    }
+
+    Ok((modules, dependencies))
+}
+
+async fn parse_module(
+    context: &Context,
+    path: PathBuf,
+) -> Result<(PathBuf, Module, HashMap<String, PathBuf>), ApplicationError> {
+    let mut source = String::new();
+
+    context
+        .file_system()
+        .read_file_to_string(&path, &mut source)
+        .await?;
+
+    let module = parse(&source).map_err(|error| error.with_path(&path))?;
+
+    let submodule_paths = try_join_all(
+        module
+            .statements()
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::Include(include) => Some(include.path()),
+                Statement::Submodule(submodule) => Some(submodule.path()),
+                _ => None,
+            })
+            .map(|submodule_path| resolve_submodule_path(context, &path, submodule_path))
+            .collect::<Vec<_>>(),
+    )
+    .await?
+    .into_iter()
+    .collect::<HashMap<_, _>>();
+
+    Ok((path, module, submodule_paths))
+}
+
+// Paths of both included and child modules are resolved from directories of
+// modules referencing them rather than a working or build directory.
+async fn resolve_submodule_path(
+    context: &Context,
+    module_path: &Path,
+    submodule_path: &str,
+) -> Result<(String, PathBuf), ApplicationError> {
+    Ok((
+        submodule_path.into(),
+        context
+            .file_system()
+            .canonicalize_path(&module_path.parent().unwrap().join(submodule_path))
+            .await?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hash_type::HashType,
+        ir::{Build, Environment},
+    };
+    use async_trait::async_trait;
+    use futures::future::pending;
+    use infrastructure::{BufferConsole, CommandRunner, FileSystem, InMemoryFileSystem, Metadata};
+    use std::{
+        error::Error,
+        io,
+        os::unix::process::ExitStatusExt,
+        process::{ExitStatus, Output},
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Mutex,
+        },
+    };
+    use tempfile::{tempdir, TempDir};
+
+    #[derive(Default)]
+    struct RecordingCommandRunner {
+        commands: Arc<Mutex<Vec<String>>>,
+        environments: Arc<Mutex<Vec<Environment>>>,
+    }
+
+    #[async_trait]
+    impl CommandRunner for RecordingCommandRunner {
+        async fn run(
+            &self,
+            command: &str,
+            _directory: Option<&Path>,
+            environment: &Environment,
+        ) -> Result<Output, Box<dyn Error>> {
+            self.commands.lock().unwrap().push(command.into());
+            self.environments.lock().unwrap().push(environment.clone());
+
+            Ok(Output {
+                status: ExitStatus::default(),
+                stdout: vec![],
+                stderr: vec![],
+            })
+        }
+
+        async fn run_console(
+            &self,
+            command: &str,
+            _directory: Option<&Path>,
+            environment: &Environment,
+        ) -> Result<ExitStatus, Box<dyn Error>> {
+            self.commands.lock().unwrap().push(command.into());
+            self.environments.lock().unwrap().push(environment.clone());
+
+            Ok(ExitStatus::default())
+        }
+    }
+
+    // A command runner measuring the maximum number of commands running at once
+    // A command runner blocking a first command of `slow` until interruption
+    // and failing ones of `false`.
+    #[derive(Default)]
+    struct InterruptedCommandRunner {
+        commands: Arc<Mutex<Vec<String>>>,
+        blocked: AtomicBool,
+    }
+
+    #[async_trait]
+    impl CommandRunner for InterruptedCommandRunner {
+        async fn run(
+            &self,
+            command: &str,
+            _directory: Option<&Path>,
+            _environment: &Environment,
+        ) -> Result<Output, Box<dyn Error>> {
+            self.commands.lock().unwrap().push(command.into());
+
+            let status = match command {
+                "slow" if !self.blocked.swap(true, Ordering::SeqCst) => {
+                    pending::<()>().await;
+                    unreachable!()
+                }
+                // Let the other commands start before a failure.
+                "false" => {
+                    sleep(Duration::from_millis(10)).await;
+                    ExitStatus::from_raw(1 << 8)
+                }
+                _ => ExitStatus::default(),
+            };
+
+            Ok(Output {
+                status,
+                stdout: vec![],
+                stderr: vec![],
+            })
+        }
+
+        async fn run_console(
+            &self,
+            _command: &str,
+            _directory: Option<&Path>,
+            _environment: &Environment,
+        ) -> Result<ExitStatus, Box<dyn Error>> {
+            unreachable!()
+        }
+    }
+
+    #[derive(Default)]
+    struct ConcurrencyCommandRunner {
+        running_count: AtomicUsize,
+        max_running_count: Arc<AtomicUsize>,
+    }
+
+    impl ConcurrencyCommandRunner {
+        async fn wait(&self) {
+            let count = self.running_count.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_running_count.fetch_max(count, Ordering::SeqCst);
+            sleep(Duration::from_millis(10)).await;
+            self.running_count.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    #[async_trait]
+    impl CommandRunner for ConcurrencyCommandRunner {
+        async fn run(
+            &self,
+            _command: &str,
+            _directory: Option<&Path>,
+            _environment: &Environment,
+        ) -> Result<Output, Box<dyn Error>> {
+            self.wait().await;
+
+            Ok(Output {
+                status: ExitStatus::default(),
+                stdout: vec![],
+                stderr: vec![],
+            })
+        }
+
+        async fn run_console(
+            &self,
+            _command: &str,
+            _directory: Option<&Path>,
+            _environment: &Environment,
+        ) -> Result<ExitStatus, Box<dyn Error>> {
+            self.wait().await;
+
+            Ok(ExitStatus::default())
+        }
+    }
+
+    struct FlakyFileSystem {
+        file_system: OsFileSystem,
+        failed: AtomicBool,
+    }
+
+    impl FlakyFileSystem {
+        fn new() -> Self {
+            Self {
+                file_system: OsFileSystem::new(OPEN_FILE_LIMIT, true),
+                failed: false.into(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl FileSystem for FlakyFileSystem {
+        async fn read_file_chunks(
+            &self,
+            path: &Path,
+            buffer: &mut [u8],
+            consume: &mut (dyn for<'a> FnMut(&'a [u8]) + Send),
+        ) -> Result<(), Box<dyn Error>> {
+            self.file_system
+                .read_file_chunks(path, buffer, consume)
+                .await
+        }
+
+        async fn read_file_to_string(
+            &self,
+            path: &Path,
+            buffer: &mut String,
+        ) -> Result<(), Box<dyn Error>> {
+            if self.failed.swap(true, Ordering::SeqCst) {
+                self.file_system.read_file_to_string(path, buffer).await
+            } else {
+                Err(io::Error::new(io::ErrorKind::Interrupted, "interrupted").into())
+            }
+        }
+
+        async fn metadata(&self, path: &Path) -> Result<Metadata, Box<dyn Error>> {
+            self.file_system.metadata(path).await
+        }
+
+        async fn write_file(&self, path: &Path, content: &[u8]) -> Result<(), Box<dyn Error>> {
+            self.file_system.write_file(path, content).await
+        }
+
+        async fn remove_file(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+            self.file_system.remove_file(path).await
+        }
+
+        async fn create_directory(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+            self.file_system.create_directory(path).await
+        }
+
+        async fn canonicalize_path(&self, path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+            self.file_system.canonicalize_path(path).await
+        }
+    }
+
+    fn create_context() -> Arc<Context> {
+        Context::new(OsConsole::new(), FlakyFileSystem::new()).into()
+    }
+
+    // A temporary directory on an operating system for tests which run real
+    // commands or databases.
+    struct TestDirectory {
+        directory: TempDir,
+    }
+
+    impl TestDirectory {
+        fn new() -> Self {
+            Self {
+                directory: tempdir().unwrap(),
+            }
+        }
+
+        fn path(&self, name: &str) -> String {
+            self.directory.path().join(name).display().to_string()
+        }
+
+        async fn write_file(&self, name: &str, content: &str) {
+            tokio::fs::write(self.path(name), content).await.unwrap();
+        }
+
+        async fn write_build_file(&self, content: &str) {
+            self.write_file(DEFAULT_BUILD_FILE, content).await;
+        }
+
+        fn arguments(&self, options: &[&str]) -> Arguments {
+            Arguments::parse_from(
+                ["turtle".into(), "-f".into(), self.path(DEFAULT_BUILD_FILE)]
+                    .into_iter()
+                    .chain(options.iter().map(|&option| option.into())),
+            )
+        }
+    }
+
+    fn create_os_context(console: BufferConsole) -> Context {
+        Context::new(console, OsFileSystem::new(OPEN_FILE_LIMIT, true))
+    }
+
+    #[tokio::test]
+    async fn retry_on_transient_error() {
+        let directory = TestDirectory::new();
+        directory.write_build_file("").await;
+
+        execute_with_retry(
+            &create_context(),
+            &directory.arguments(&["--retry-infra", "1"]),
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn fail_on_transient_error_without_retry() {
+        let directory = TestDirectory::new();
+        directory.write_build_file("").await;
+
+        assert!(matches!(
+            execute_with_retry(
+                &create_context(),
+                &directory.arguments(&["--retry-infra", "0"])
+            )
+            .await,
+            Err(ApplicationError::Io(io::ErrorKind::Interrupted, _))
+        ));
+    }
+
+    #[tokio::test]
+    async fn do_not_retry_on_non_transient_error() {
+        let directory = TestDirectory::new();
+
+        assert!(matches!(
+            execute_with_retry(
+                &create_context(),
+                &directory.arguments(&["--retry-infra", "1"])
+            )
+            .await,
+            Err(ApplicationError::ManifestNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn fail_to_find_manifest() {
+        let error = execute(
+            &Context::new(BufferConsole::new(), InMemoryFileSystem::new()).into(),
+            &Arguments::parse_from(["turtle", "--no-cache"]),
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(
+            error,
+            ApplicationError::ManifestNotFound(DEFAULT_BUILD_FILE.into())
+        );
+        assert_eq!(
+            error.to_string(),
+            "build file \"build.ninja\" not found (use -f to specify a build file or -C to change a directory)"
+        );
+    }
+
+    #[tokio::test]
+    async fn run_commands_with_custom_command_runner() {
+        let directory = TestDirectory::new();
+        let foo = directory.path("foo");
+        let bar = directory.path("bar");
+
+        // Outputs exist in advance as the runner does not run commands.
+        for name in ["foo", "bar"] {
+            directory.write_file(name, "").await;
+        }
+
+        directory
+            .write_build_file(&format!(
+                "rule echo\n  command = echo $out\nbuild {foo}: echo\nbuild {bar}: echo {foo}\n"
+            ))
+            .await;
+        let runner = RecordingCommandRunner::default();
+        let commands = runner.commands.clone();
+
+        execute(
+            &create_os_context(BufferConsole::new())
+                .with_command_runner(runner)
+                .into(),
+            &directory.arguments(&[]),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            *commands.lock().unwrap(),
+            [format!("echo {foo}"), format!("echo {bar}")]
+        );
+    }
+
+    // Returns commands run after an output is regenerated identically and
+    // whether a timestamp hash of its dependent is updated.
+    async fn regenerate_output_identically(restat: bool) -> (Vec<String>, bool) {
+        let directory = TestDirectory::new();
+        let path = |name| directory.path(name);
+
+        // Outputs exist in advance as the runner does not run commands.
+        for name in ["foo", "bar", "baz"] {
+            directory.write_file(name, "").await;
+        }
+
+        directory
+            .write_build_file(&format!(
+                "rule gen\n  command = gen $in $out\n{}rule cp\n  command = cp $in $out\nbuild {}: gen {}\nbuild {}: cp {}\n",
+                if restat { "  restat = 1\n" } else { "" },
+                path("foo"),
+                path("bar"),
+                path("baz"),
+                path("foo"),
+            ))
+            .await;
+        let runner = RecordingCommandRunner::default();
+        let commands = runner.commands.clone();
+        let context = Arc::new(create_os_context(BufferConsole::new()).with_command_runner(runner));
+        let arguments = directory.arguments(&[]);
+        let id = Build::new(vec![path("baz").into()], vec![], None, vec![], vec![], None).id();
+        let timestamp_hash = || {
+            context
+                .database()
+                .get_hash(HashType::Timestamp, id)
+                .unwrap()
+        };
+
+        execute(&context, &arguments).await.unwrap();
+        commands.lock().unwrap().clear();
+        let old_hash = timestamp_hash();
+
+        directory.write_file("bar", "bar").await;
+        directory.write_file("foo", "").await;
+        execute(&context, &arguments).await.unwrap();
+
+        let commands = commands.lock().unwrap().clone();
+
+        (commands, timestamp_hash() != old_hash)
+    }
+
+    #[tokio::test]
+    async fn do_not_rerun_dependent_of_output_regenerated_identically() {
+        for restat in [false, true] {
+            let (commands, _) = regenerate_output_identically(restat).await;
+
+            assert_eq!(commands.len(), 1);
+            assert!(commands[0].starts_with("gen "));
+        }
+    }
+
+    #[tokio::test]
+    async fn update_timestamp_hash_of_dependent_of_restat_output() {
+        assert!(!regenerate_output_identically(false).await.1);
+        assert!(regenerate_output_identically(true).await.1);
+    }
+
+    #[tokio::test]
+    async fn remove_records_of_dead_builds() {
+        let directory = TestDirectory::new();
+        let path = |name| directory.path(name);
+
+        for name in ["foo", "bar"] {
+            directory.write_file(name, "").await;
+        }
+
+        let context = Arc::new(
+            create_os_context(BufferConsole::new())
+                .with_command_runner(RecordingCommandRunner::default()),
+        );
+
+        directory
+            .write_build_file(&format!(
+                "rule touch\n  command = touch $out\nbuild {}: touch\nbuild {}: touch\n",
+                path("foo"),
+                path("bar"),
+            ))
+            .await;
+
+        execute(&context, &directory.arguments(&[])).await.unwrap();
+
+        let ids = context.database().get_build_ids().unwrap();
+
+        assert_eq!(ids.len(), 2);
+
+        for &id in &ids {
+            context.database().set_dependencies(id, &[]).unwrap();
+        }
+
+        directory
+            .write_build_file(&format!(
+                "rule touch\n  command = touch $out\nbuild {}: touch\n",
+                path("foo")
+            ))
+            .await;
+
+        execute(&context, &directory.arguments(&["-t", "cleandead"]))
+            .await
+            .unwrap();
+
+        let [id] = context.database().get_build_ids().unwrap()[..] else {
+            panic!("unexpected build IDs");
+        };
+
+        for id in ids.into_iter().filter(|&other| other != id) {
+            for r#type in HashType::ALL {
+                assert_eq!(context.database().get_hash(r#type, id).unwrap(), None);
+            }
+            assert_eq!(context.database().get_dependencies(id).unwrap(), None);
+        }
+    }
+
+    #[tokio::test]
+    async fn do_not_rerun_build_order_only_depending_on_rebuilt_phony_build() {
+        let directory = TestDirectory::new();
+        let path = |name| directory.path(name);
+
+        // Outputs exist in advance as the runner does not run commands.
+        for name in ["foo", "bar", "baz", "qux", "quux"] {
+            directory.write_file(name, "").await;
+        }
+
+        directory
+            .write_build_file(&format!(
+                "rule cp\n  command = cp $in $out\nbuild {}: cp {}\nbuild barrier: phony {}\nbuild {}: cp {} || barrier\nbuild {}: cp {} | barrier\n",
+                path("bar"),
+                path("baz"),
+                path("bar"),
+                path("foo"),
+                path("qux"),
+                path("quux"),
+                path("qux"),
+            ))
+            .await;
+        let runner = RecordingCommandRunner::default();
+        let commands = runner.commands.clone();
+        let context = Arc::new(create_os_context(BufferConsole::new()).with_command_runner(runner));
+        let arguments = directory.arguments(&[]);
+
+        execute(&context, &arguments).await.unwrap();
+        commands.lock().unwrap().clear();
+
+        // An output is updated in advance as well so that a hash of the phony
+        // build changes.
+        for name in ["baz", "bar"] {
+            directory.write_file(name, "baz").await;
+        }
+
+        execute(&context, &arguments).await.unwrap();
+
+        let mut commands = commands.lock().unwrap().clone();
+        commands.sort();
+
+        // Only a build depending on the phony build via an implicit input is rerun.
+        assert_eq!(
+            commands,
+            [
+                format!("cp {} {}", path("baz"), path("bar")),
+                format!("cp {} {}", path("qux"), path("quux")),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn run_read_only_tools_while_database_is_held() {
+        let directory = TestDirectory::new();
+        directory
+            .write_build_file(&format!(
+                "rule touch\n  command = touch $out\nbuild {}: touch\n",
+                directory.path("foo")
+            ))
+            .await;
+        let create_context = || Arc::new(create_os_context(BufferConsole::new()));
+        let build_context = create_context();
+
+        execute(&build_context, &directory.arguments(&[]))
+            .await
+            .unwrap();
+
+        for tool in ["deps", "query", "targets"] {
+            execute(&create_context(), &directory.arguments(&["-t", tool]))
+                .await
+                .unwrap();
+        }
+
+        assert!(matches!(
+            execute(&create_context(), &directory.arguments(&["-t", "clean"])).await,
+            Err(ApplicationError::Database(_, _))
+        ));
+    }
+
+    #[tokio::test]
+    async fn run_command_in_directory_relative_to_build_directory() {
+        let directory = TestDirectory::new();
+        tokio::fs::create_dir(directory.path("foo")).await.unwrap();
+
+        directory
+            .write_build_file(&format!(
+                "rule touch\n  command = touch bar\n  chdir = foo\nbuild {}: touch\n",
+                directory.path("foo/bar")
+            ))
+            .await;
+
+        execute(
+            &create_os_context(BufferConsole::new()).into(),
+            &directory.arguments(&[]),
+        )
+        .await
+        .unwrap();
+
+        assert!(Path::new(&directory.path("foo/bar")).exists());
+    }
+
+    #[tokio::test]
+    async fn verify_outputs() {
+        let directory = TestDirectory::new();
+        let foo = directory.path("foo");
+        let bar = directory.path("bar");
+
+        directory
+            .write_build_file(&format!(
+                "rule touch\n  command = touch $out\nrule lie\n  command = true\nbuild {foo}: touch\nbuild {bar}: lie\n"
+            ))
+            .await;
+
+        assert_eq!(
+            execute(
+                &create_os_context(BufferConsole::new()).into(),
+                &directory.arguments(&["--no-cache", "--verify-outputs"]),
+            )
+            .await,
+            Err(ApplicationError::OutputsMissing(vec![bar]))
+        );
+    }
+
+    #[tokio::test]
+    async fn protect_sources() {
+        let directory = TestDirectory::new();
+        let path = |name| directory.path(name);
+
+        directory
+            .write_build_file(&format!(
+                "rule echo\n  command = echo built > $out\nrule cp\n  command = cp $in $out\nbuild {}: echo\nbuild {}: cp {}\n",
+                path("foo"),
+                path("bar"),
+                path("foo"),
+            ))
+            .await;
+        directory.write_file("foo", "source").await;
+
+        let context = Arc::new(create_os_context(BufferConsole::new()));
+
+        assert_eq!(
+            execute(&context, &directory.arguments(&["--protect-sources"])).await,
+            Err(ApplicationError::SourcesOverwritten(vec![path("foo")]))
+        );
+        assert_eq!(
+            tokio::fs::read_to_string(path("foo")).await.unwrap(),
+            "source"
+        );
+
+        // Outputs built before are not protected.
+        tokio::fs::remove_file(path("foo")).await.unwrap();
+        execute(&context, &directory.arguments(&[])).await.unwrap();
+        execute(&context, &directory.arguments(&["--protect-sources", "-B"]))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            tokio::fs::read_to_string(path("bar")).await.unwrap(),
+            "built\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn time_out_commands_without_waits_for_job_slots() {
+        let directory = TestDirectory::new();
+        let outputs = ["foo", "bar"].map(|name| directory.path(name));
+
+        directory
+            .write_build_file(&format!(
+                "rule slow\n  command = sleep 0.6 && touch $out\n{}",
+                outputs
+                    .iter()
+                    .map(|output| format!("build {output}: slow\n"))
+                    .collect::<String>()
+            ))
+            .await;
+
+        execute(
+            &create_os_context(BufferConsole::new()).into(),
+            &directory.arguments(&["-j", "1", "--timeout", "1"]),
+        )
+        .await
+        .unwrap();
+
+        assert!(outputs.iter().all(|output| Path::new(output).exists()));
+    }
+
+    #[tokio::test]
+    async fn stop_running_commands_at_failure_limit() {
+        let directory = TestDirectory::new();
+        let outputs = ["foo", "bar", "baz", "qux"].map(|name| directory.path(name));
+
+        directory
+            .write_build_file(&format!(
+                "rule fail\n  command = touch $out.log && false\n{}",
+                outputs
+                    .iter()
+                    .map(|output| format!("build {output}: fail\n"))
+                    .collect::<String>()
+            ))
+            .await;
+
+        assert!(matches!(
+            execute(
+                &create_os_context(BufferConsole::new()).into(),
+                &directory.arguments(&["-j", "1", "--max-fail", "2"]),
+            )
+            .await,
+            Err(ApplicationError::Builds(2))
+        ));
+        assert_eq!(
+            outputs
+                .iter()
+                .filter(|output| Path::new(&format!("{output}.log")).exists())
+                .count(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn run_command_with_environment_variables() {
+        let runner = RecordingCommandRunner::default();
+        let environments = runner.environments.clone();
+
+        execute(
+            &Context::new(
+                BufferConsole::new(),
+                InMemoryFileSystem::new().with_file(
+                    DEFAULT_BUILD_FILE,
+                    "rule echo\n  command = echo $out\n  environment = FOO=$out\n  env_clear = 1\nbuild foo: echo\n",
+                ),
+            )
+            .with_command_runner(runner)
+            .into(),
+            &Arguments::parse_from(["turtle", "--no-cache"]),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            *environments.lock().unwrap(),
+            [Environment::new(vec![("FOO".into(), "foo".into())], true)]
+        );
+    }
+
+    #[tokio::test]
+    async fn emit_json() {
+        let console = BufferConsole::new();
+
+        execute(
+            &Context::new(
+                console.clone(),
+                InMemoryFileSystem::new().with_file(
+                    DEFAULT_BUILD_FILE,
+                    "rule echo\n  command = echo $out\nbuild foo: echo\n",
+                ),
+            )
+            .into(),
+            &Arguments::parse_from(["turtle", "--no-cache", "--emit-json"]),
+        )
+        .await
+        .unwrap();
+
+        assert!(console.stdout().contains(r#""command": "echo foo""#));
+        assert_eq!(console.stderr(), "");
+    }
+
+    async fn run_with_verbosity(options: &[&str]) -> BufferConsole {
+        let directory = TestDirectory::new();
+        directory
+            .write_build_file(&format!(
+                "rule echo\n  command = echo hello\n  description = echo $out\nbuild {}: echo\n",
+                directory.path("foo")
+            ))
+            .await;
+        let console = BufferConsole::new();
+        let context = Arc::new(create_os_context(console.clone()));
+        let arguments = directory.arguments(
+            &["--no-cache", "--color", "never"]
+                .into_iter()
+                .chain(options.iter().copied())
+                .collect::<Vec<_>>(),
+        );
+
+        execute(&context, &arguments).await.unwrap();
+        report_error(&context, &arguments, &ApplicationError::Build)
+            .await
+            .unwrap();
+
+        console
+    }
+
+    #[tokio::test]
+    async fn run_with_normal_verbosity() {
+        let console = run_with_verbosity(&[]).await;
+
+        assert_eq!(console.stdout(), "hello\n");
+        assert!(console.stderr().contains("echo "));
+        assert!(console.stderr().contains("built 1 target in"));
+        assert!(console.stderr().contains("build failed"));
+    }
+
+    #[tokio::test]
+    async fn run_with_quiet_verbosity() {
+        let console = run_with_verbosity(&["--quiet"]).await;
+
+        assert_eq!(console.stdout(), "hello\n");
+        assert!(!console.stderr().contains("echo "));
+        assert!(console.stderr().contains("built 1 target in"));
+        assert!(console.stderr().contains("build failed"));
+    }
+
+    #[tokio::test]
+    async fn run_with_silent_verbosity() {
+        for options in [&["-qq"][..], &["--silent"]] {
+            let console = run_with_verbosity(options).await;
+
+            assert_eq!(console.stdout(), "");
+            assert_eq!(console.stderr(), "");
+        }
+    }
+
+    #[tokio::test]
+    async fn rerun_build_with_one_of_outputs_removed() {
+        let runner = RecordingCommandRunner::default();
+        let commands = runner.commands.clone();
+        let context = Arc::new(
+            Context::new(
+                BufferConsole::new(),
+                InMemoryFileSystem::new()
+                    .with_file(
+                        DEFAULT_BUILD_FILE,
+                        "rule cp\n  command = cp $in $out\nbuild foo bar: cp baz\n",
+                    )
+                    .with_file("foo", "")
+                    .with_file("bar", "")
+                    .with_file("baz", ""),
+            )
+            .with_command_runner(runner),
+        );
+        let arguments = Arguments::parse_from(["turtle", "--no-cache"]);
+
+        for _ in 0..2 {
+            execute(&context, &arguments).await.unwrap();
+        }
+
+        assert_eq!(commands.lock().unwrap().len(), 1);
+
+        context
+            .file_system()
+            .remove_file(Path::new("bar"))
+            .await
+            .unwrap();
+        execute(&context, &arguments).await.unwrap();
+
+        assert_eq!(commands.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn skip_build_with_many_inputs() {
+        let inputs = (0..100).map(|index| index.to_string()).collect::<Vec<_>>();
+        let mut file_system = InMemoryFileSystem::new()
+            .with_file(
+                DEFAULT_BUILD_FILE,
+                format!(
+                    "rule cat\n  command = cat $in > $out\nbuild foo: cat {}\n",
+                    inputs.join(" ")
+                ),
+            )
+            .with_file("foo", "");
+
+        // Inputs have distinct timestamps so that their hash depends on their order.
+        for input in &inputs {
+            file_system.advance_time(Duration::from_secs(1));
+            file_system = file_system.with_file(input, "");
+        }
+
+        let runner = RecordingCommandRunner::default();
+        let commands = runner.commands.clone();
+        let context =
+            Arc::new(Context::new(BufferConsole::new(), file_system).with_command_runner(runner));
+        let arguments = Arguments::parse_from(["turtle", "--no-cache"]);
+
+        for _ in 0..2 {
+            execute(&context, &arguments).await.unwrap();
+        }
+
+        assert_eq!(commands.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn run_default_builds_in_order() {
+        let outputs = [
+            "qux", "foo", "quux", "bar", "baz", "corge", "grault", "garply",
+        ];
+        let runner = RecordingCommandRunner::default();
+        let commands = runner.commands.clone();
+
+        execute(
+            &Context::new(
+                BufferConsole::new(),
+                InMemoryFileSystem::new().with_file(
+                    DEFAULT_BUILD_FILE,
+                    format!(
+                        "rule echo\n  command = echo $out\n{}",
+                        outputs
+                            .iter()
+                            .map(|output| format!("build {output}: echo\n"))
+                            .collect::<String>()
+                    ),
+                ),
+            )
+            .with_command_runner(runner)
+            .into(),
+            &Arguments::parse_from(["turtle", "--no-cache"]),
+        )
+        .await
+        .unwrap();
+
+        let mut outputs = outputs.map(|output| format!("echo {output}"));
+        outputs.sort();
+
+        assert_eq!(*commands.lock().unwrap(), outputs);
+    }
+
+    async fn run_builds_concurrently(pool: &str, job_limit: usize) -> usize {
+        let runner = ConcurrencyCommandRunner::default();
+        let count = runner.max_running_count.clone();
+
+        execute(
+            &Context::new(
+                BufferConsole::new(),
+                InMemoryFileSystem::new().with_file(
+                    DEFAULT_BUILD_FILE,
+                    format!(
+                        "rule echo\n  command = echo $out\n  pool = {pool}\nbuild foo: echo\nbuild bar: echo\nbuild baz: echo\n"
+                    ),
+                ),
+            )
+            .with_command_runner(runner)
+            .into(),
+            &Arguments::parse_from(["turtle", "--no-cache", "-j", &job_limit.to_string()]),
+        )
+        .await
+        .unwrap();
+
+        count.load(Ordering::SeqCst)
+    }
+
+    #[tokio::test]
+    async fn run_console_builds_serially() {
+        assert_eq!(run_builds_concurrently("console", 3).await, 1);
+    }
+
+    #[tokio::test]
+    async fn run_non_console_builds_concurrently() {
+        assert!(run_builds_concurrently("", 3).await > 1);
+    }
+
+    #[tokio::test]
+    async fn limit_concurrent_builds_by_job_limit() {
+        assert_eq!(run_builds_concurrently("", 1).await, 1);
+    }
+
+    #[tokio::test]
+    async fn rerun_interrupted_build() {
+        let runner = InterruptedCommandRunner::default();
+        let commands = runner.commands.clone();
+        let context = Arc::new(
+            Context::new(
+                BufferConsole::new(),
+                InMemoryFileSystem::new()
+                    .with_file(
+                        DEFAULT_BUILD_FILE,
+                        "rule slow\n  command = slow\nrule false\n  command = false\nbuild foo: slow\nbuild bar: false\n",
+                    )
+                    .with_file("foo", ""),
+            )
+            .with_command_runner(runner),
+        );
+
+        assert_eq!(
+            execute(
+                &context,
+                &Arguments::parse_from(["turtle", "--no-cache", "-j", "2", "-k", "1"])
+            )
+            .await,
+            Err(ApplicationError::Build)
+        );
+
+        let arguments = Arguments::parse_from(["turtle", "--no-cache", "foo"]);
+
+        execute(&context, &arguments).await.unwrap();
+        execute(&context, &arguments).await.unwrap();
+
+        let mut commands = commands.lock().unwrap().clone();
+        commands.sort();
+
+        assert_eq!(commands, ["false", "slow", "slow"]);
+    }
+
+    #[tokio::test]
+    async fn interrupt_builds_on_interruption() {
+        let context = Arc::new(
+            Context::new(
+                BufferConsole::new(),
+                InMemoryFileSystem::new().with_file(
+                    DEFAULT_BUILD_FILE,
+                    "rule slow\n  command = slow\nbuild foo: slow\n",
+                ),
+            )
+            .with_command_runner(InterruptedCommandRunner::default()),
+        );
+        let arguments = Arguments::parse_from(["turtle"]);
+
+        context.database().initialize_temporary().unwrap();
+
+        let paths = root_module_paths(&context, &arguments).await.unwrap();
+        let configuration = load_configuration(&context, &arguments, &paths)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            run::run(
+                &context,
+                configuration,
+                &[],
+                Default::default(),
+                sleep(Duration::from_millis(10)),
+            )
+            .await,
+            Err(ApplicationError::Interrupted)
+        );
+    }
+
+    #[tokio::test]
+    async fn count_started_builds_in_job_slots() {
+        let console = BufferConsole::new();
+
+        execute(
+            &Context::new(
+                console.clone(),
+                InMemoryFileSystem::new().with_file(
+                    DEFAULT_BUILD_FILE,
+                    "rule echo\n  command = echo $out\n  description = $out\nbuild foo: echo\nbuild bar: echo\nbuild baz: echo\n",
+                ),
+            )
+            .with_command_runner(ConcurrencyCommandRunner::default())
+            .into(),
+            &Arguments::parse_from(["turtle", "--no-cache", "-j", "1", "--status", "%s/%t "]),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            console
+                .stdout()
+                .lines()
+                .chain(console.stderr().lines())
+                .filter_map(|line| line.split_once(' ').map(|(status, _)| status))
+                .filter(|status| status.ends_with("/3"))
+                .collect::<Vec<_>>(),
+            ["1/3", "2/3", "3/3"]
+        );
+    }
+
+    #[tokio::test]
+    async fn show_regeneration_of_root_build_file_in_dry_run() {
+        let console = BufferConsole::new();
+        let runner = RecordingCommandRunner::default();
+        let commands = runner.commands.clone();
+        let build_file = "rule gen\n  command = gen\n  generator = 1\nrule touch\n  command = touch $out\nbuild build.ninja: gen\nbuild foo: touch\n";
+        let context = Arc::new(
+            Context::new(
+                console.clone(),
+                InMemoryFileSystem::new().with_file(DEFAULT_BUILD_FILE, build_file),
+            )
+            .with_command_runner(runner),
+        );
+
+        execute(
+            &context,
+            &Arguments::parse_from(["turtle", "--no-cache", "-n", "foo"]),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(console.stdout(), "gen\ntouch foo\n");
+        assert!(commands.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn explain_builds() {
+        let console = BufferConsole::new();
+        let context = Arc::new(
+            Context::new(
+                console.clone(),
+                InMemoryFileSystem::new()
+                    .with_file(
+                        DEFAULT_BUILD_FILE,
+                        "rule cp\n  command = cp $in $out\nbuild foo: cp bar\n",
+                    )
+                    .with_file("bar", ""),
+            )
+            .with_command_runner(RecordingCommandRunner::default()),
+        );
+        let arguments = Arguments::parse_from(["turtle", "--no-cache", "--explain"]);
+
+        execute(&context, &arguments).await.unwrap();
+
+        assert!(console
+            .stderr()
+            .contains("turtle: explain: foo: output foo missing\n"));
+
+        context
+            .file_system()
+            .write_file(Path::new("foo"), b"")
+            .await
+            .unwrap();
+        execute(&context, &arguments).await.unwrap();
+
+        assert!(console
+            .stderr()
+            .contains("turtle: explain: foo: up to date\n"));
+    }
+
+    #[tokio::test]
+    async fn clean_outputs_except_ignored_ones() {
+        let console = BufferConsole::new();
+        let context = Arc::new(Context::new(
+            console.clone(),
+            InMemoryFileSystem::new()
+                .with_file(
+                    DEFAULT_BUILD_FILE,
+                    "rule touch\n  command = touch $out\nbuild foo bar: touch\n",
+                )
+                .with_file(IGNORE_FILE, "# outputs to keep\nbar\n")
+                .with_file("foo", "")
+                .with_file("bar", ""),
+        ));
+
+        execute(
+            &context,
+            &Arguments::parse_from(["turtle", "--no-cache", "-t", "clean"]),
+        )
+        .await
+        .unwrap();
+
+        assert!(context
+            .file_system()
+            .metadata(Path::new("foo"))
+            .await
+            .is_err());
+        assert!(context
+            .file_system()
+            .metadata(Path::new("bar"))
+            .await
+            .is_ok());
+        assert_eq!(console.stdout(), "bar skipped (ignored)\n1 file removed\n");
+    }
+
+    #[tokio::test]
+    async fn parse_shared_submodule_once() {
+        let context = Context::new(
+            BufferConsole::new(),
+            InMemoryFileSystem::new()
+                .with_file("build.ninja", "include foo.ninja\nsubninja bar.ninja\n")
+                .with_file("foo.ninja", "subninja baz.ninja\n")
+                .with_file("bar.ninja", "include baz.ninja\n")
+                .with_file("baz.ninja", ""),
+        );
+
+        let (modules, dependencies) = parse_modules(&context, &["build.ninja".into()])
+            .await
+            .unwrap();
+
+        assert_eq!(modules.len(), 4);
+        assert_eq!(
+            dependencies[Path::new("/foo.ninja")],
+            [("baz.ninja".into(), PathBuf::from("/baz.ninja"))]
+                .into_iter()
+                .collect()
+        );
+        assert_eq!(
+            dependencies[Path::new("/bar.ninja")],
+            [("baz.ninja".into(), PathBuf::from("/baz.ninja"))]
+                .into_iter()
+                .collect()
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_nested_submodule_paths_from_referencing_modules() {
+        let context = Context::new(
+            BufferConsole::new(),
+            InMemoryFileSystem::new()
+                .with_file("build.ninja", "include foo/foo.ninja\n")
+                .with_file("foo/foo.ninja", "include bar/bar.ninja\n")
+                .with_file("foo/bar/bar.ninja", "subninja ../baz.ninja\n")
+                .with_file("foo/baz.ninja", "")
+                // Files at the same relative paths from a root module are not loaded.
+                .with_file("bar/bar.ninja", "")
+                .with_file("baz.ninja", ""),
+        );
+
+        let (modules, dependencies) = parse_modules(&context, &["build.ninja".into()])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            modules.keys().cloned().collect::<HashSet<_>>(),
+            [
+                "/build.ninja",
+                "/foo/bar/bar.ninja",
+                "/foo/baz.ninja",
+                "/foo/foo.ninja",
+            ]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect()
+        );
+        assert_eq!(
+            dependencies[Path::new("/foo/foo.ninja")],
+            [("bar/bar.ninja".into(), PathBuf::from("/foo/bar/bar.ninja"))]
+                .into_iter()
+                .collect()
+        );
+        assert_eq!(
+            dependencies[Path::new("/foo/bar/bar.ninja")],
+            [("../baz.ninja".into(), PathBuf::from("/foo/baz.ninja"))]
+                .into_iter()
+                .collect()
+        );
+    }
+
+    #[tokio::test]
+    async fn fail_to_load_mutually_including_modules() {
+        let context = Context::new(
+            BufferConsole::new(),
+            InMemoryFileSystem::new()
+                .with_file("foo.ninja", "include bar.ninja\n")
+                .with_file("bar.ninja", "include foo.ninja\n"),
+        );
+
+        let (_, dependencies) = parse_modules(&context, &["foo.ninja".into()])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            module_dependency::validate(&dependencies),
+            Err(
+                module_dependency::ModuleDependencyError::CircularDependency(vec![
+                    "/bar.ninja".into(),
+                    "/foo.ninja".into()
+                ])
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn fail_to_parse_dynamic_dependency_file() {
+        let directory = TestDirectory::new();
+        let path = |name| directory.path(name);
+
+        directory
+            .write_build_file(&format!(
+                "rule touch\n  command = touch $out\nbuild {}: touch || {}\n  dyndep = {}\n",
+                path("foo"),
+                path("foo.dd"),
+                path("foo.dd")
+            ))
+            .await;
+        directory
+            .write_file("foo.dd", "ninja_dyndep_version = 1\nbuild\n")
+            .await;
+
+        let error = execute(
+            &create_os_context(BufferConsole::new()).into(),
+            &directory.arguments(&[]),
+        )
+        .await
+        .unwrap_err();
+
+        assert!(matches!(
+            &error,
+            ApplicationError::DynamicParse(build, error)
+                if build.outputs() == [path("foo").into()]
+                    && error.path() == Some(path("foo.dd").as_ref())
+        ));
+        assert!(error.to_string().starts_with(&format!(
+            "failed to parse dynamic dependency file for outputs {}: {}:2:1:",
+            path("foo"),
+            path("foo.dd")
+        )));
+    }
+}
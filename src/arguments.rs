@@ -1,30 +1,303 @@
-use clap::{Parser, ValueEnum};
+use crate::{
+    verbosity::Verbosity,
+    warning::{self, WarningConfiguration, WarningKey, WarningLevel},
+};
+use clap::{ArgAction, Parser, ValueEnum};
+use std::collections::HashMap;
 
 #[derive(Parser)]
 #[clap(about = "The Ninja build system clone written in Rust", version)]
 pub struct Arguments {
-    #[clap(help = "Specify outputs")]
-    pub outputs: Vec<String>,
-    #[clap(short, help = "Set a root build file")]
-    pub file: Option<String>,
+    #[clap(
+        value_name = "OUTPUT|NAME=VALUE",
+        help = "Specify outputs or variables overriding ones in build files"
+    )]
+    pub targets: Vec<String>,
+    #[clap(short = 'f', help = "Set a root build file (can be repeated)")]
+    pub files: Vec<String>,
     #[clap(short = 'C', help = "Set a working directory")]
     pub directory: Option<String>,
-    #[clap(short, help = "Set a job limit")]
+    #[clap(long, help = "Set a build directory overriding the builddir variable")]
+    pub build_dir: Option<String>,
+    #[clap(
+        short,
+        env = "TURTLE_JOBS",
+        value_parser = parse_job_limit,
+        help = "Set a job limit"
+    )]
     pub job_limit: Option<usize>,
+    #[clap(
+        short = 'k',
+        long,
+        visible_alias = "max-fail",
+        default_value_t = 1,
+        help = "Keep going until a given number of builds fail (0 means no limit)"
+    )]
+    pub keep_going: usize,
+    #[clap(
+        long,
+        default_value_t = 0,
+        help = "Set a timeout of each command in seconds (0 means no limit)"
+    )]
+    pub timeout: u64,
+    #[clap(
+        short = 'l',
+        long,
+        help = "Start no new job while a load average is above a given limit"
+    )]
+    pub load_limit: Option<f64>,
+    #[clap(long, help = "Use a temporary build database to rebuild everything")]
+    pub no_cache: bool,
+    #[clap(long, help = "Check existence of source inputs before running builds")]
+    pub check: bool,
+    #[clap(long, help = "Check existence of outputs after running builds")]
+    pub verify_outputs: bool,
+    #[clap(long, help = "Refuse to overwrite existing files never built before")]
+    pub protect_sources: bool,
+    #[clap(long, help = "Build outputs depending on a given path transitively")]
+    pub downstream: Vec<String>,
+    #[clap(short = 'n', long, help = "Print commands without running them")]
+    pub dry_run: bool,
+    #[clap(short = 'B', long, help = "Run builds even if they are up to date")]
+    pub force: bool,
+    #[clap(long, help = "Explain why builds are run")]
+    pub explain: bool,
     #[clap(long, help = "Set a log prefix")]
     pub log_prefix: Option<String>,
-    #[clap(long, help = "Show no message on failure of build jobs")]
-    pub quiet: bool,
+    #[clap(
+        short,
+        long,
+        action = ArgAction::Count,
+        help = "Show no progress status of build jobs (twice to show nothing)"
+    )]
+    pub quiet: u8,
+    #[clap(long, help = "Show nothing but exit codes (same as -qq)")]
+    pub silent: bool,
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = ColorMode::Auto,
+        help = "Color diagnostics"
+    )]
+    pub color: ColorMode,
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = ErrorFormat::Human,
+        help = "Set a format of error messages"
+    )]
+    pub error_format: ErrorFormat,
+    #[clap(
+        long,
+        env = "TURTLE_STATUS",
+        default_value = "[%f/%t] ",
+        help = "Set a format of status lines (%s: started, %f: finished, %t: total, %p: percentage, %e: elapsed seconds)"
+    )]
+    pub status: String,
+    #[clap(short, long, help = "Show commands of build jobs")]
+    pub verbose: bool,
     #[clap(long, help = "Show debug logs", env = "TURTLE_DEBUG")]
     pub debug: bool,
     #[clap(long, help = "Show profile timings", env = "TURTLE_PROFILE")]
     pub profile: bool,
+    #[clap(long, help = "Write profile timings of commands into a JSON file")]
+    pub profile_json: Option<String>,
+    #[clap(
+        long,
+        help = "Write timings of commands into a file in Chrome's trace event format"
+    )]
+    pub trace: Option<String>,
+    #[clap(
+        long,
+        help = "Resolve paths lexically without resolving symbolic links"
+    )]
+    pub no_canonicalize: bool,
+    #[clap(
+        long,
+        default_value_t = 0,
+        help = "Retry on transient infrastructure errors up to a given count"
+    )]
+    pub retry_infra: usize,
+    #[clap(long, value_enum, default_value_t = Hash::Fnv, help = "Set a hash algorithm")]
+    pub hash: Hash,
+    #[clap(long, help = "Rebuild outputs on changes of files")]
+    pub watch: bool,
+    #[clap(long, help = "Print a build configuration in JSON without building")]
+    pub emit_json: bool,
     #[clap(short, help = "Use a complementary tool")]
     pub tool: Option<Tool>,
+    #[clap(
+        short = 'w',
+        long = "warn",
+        value_parser = warning::parse_setting,
+        help = "Set a warning level in KEY=warn|err|off"
+    )]
+    pub warnings: Vec<(WarningKey, WarningLevel)>,
+}
+
+impl Arguments {
+    pub fn verbosity(&self) -> Verbosity {
+        match (self.silent, self.quiet) {
+            (true, _) | (_, 2..) => Verbosity::Silent,
+            (false, 1) => Verbosity::Quiet,
+            (false, _) => Verbosity::Normal,
+        }
+    }
+
+    pub fn outputs(&self) -> Vec<String> {
+        self.targets
+            .iter()
+            .filter(|target| parse_variable(target).is_none())
+            .cloned()
+            .collect()
+    }
+
+    // Later definitions override earlier ones of the same names.
+    pub fn variables(&self) -> HashMap<String, String> {
+        self.targets
+            .iter()
+            .filter_map(|target| parse_variable(target))
+            .map(|(name, value)| (name.into(), value.into()))
+            .collect()
+    }
+
+    // Later settings override earlier ones of the same keys.
+    pub fn warning_configuration(&self) -> WarningConfiguration {
+        let mut configuration = WarningConfiguration::default();
+
+        for &(key, level) in &self.warnings {
+            configuration.set_level(key, level);
+        }
+
+        configuration
+    }
+}
+
+// Arguments are variable definitions only if they start with valid variable
+// names followed by `=`. Otherwise, they are outputs.
+fn parse_variable(string: &str) -> Option<(&str, &str)> {
+    let (name, value) = string.split_once('=')?;
+
+    (!name.is_empty()
+        && name
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || matches!(character, '_' | '-')))
+    .then_some((name, value))
+}
+
+fn parse_job_limit(string: &str) -> Result<usize, String> {
+    match string.parse::<usize>() {
+        Ok(0) | Err(_) => Err(format!("job limit must be a positive integer: {string}")),
+        Ok(limit) => Ok(limit),
+    }
 }
 
 #[derive(Clone, ValueEnum)]
 #[clap(rename_all = "lower")]
 pub enum Tool {
+    #[value(help = "Remove built files")]
+    Clean,
+    #[value(help = "Remove files built previously but no longer in build files")]
     CleanDead,
+    #[value(help = "List commands of builds in order")]
+    Commands,
+    #[value(help = "Print a compilation database in JSON")]
+    CompDb,
+    #[value(help = "Print dependencies discovered by builds")]
+    Deps,
+    #[value(help = "Print a dependency graph in the DOT language")]
+    Graph,
+    #[value(help = "List available tools")]
+    List,
+    #[value(help = "Print the shortest dependency path from an output to its input")]
+    Path,
+    #[value(help = "Print inputs and dependents of outputs")]
+    Query,
+    #[value(help = "List rules")]
+    Rules,
+    #[value(help = "List outputs")]
+    Targets,
+}
+
+impl Tool {
+    // Read-only tools never block builds running concurrently.
+    pub fn is_read_only(&self) -> bool {
+        !matches!(self, Self::Clean | Self::CleanDead)
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, ValueEnum)]
+#[clap(rename_all = "lower")]
+pub enum ColorMode {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, ValueEnum)]
+#[clap(rename_all = "lower")]
+pub enum ErrorFormat {
+    Human,
+    Json,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, ValueEnum)]
+#[clap(rename_all = "lower")]
+pub enum Hash {
+    Fnv,
+    Std,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_job_limit() {
+        assert_eq!(
+            Arguments::try_parse_from(["turtle", "-j", "4"])
+                .unwrap()
+                .job_limit,
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn fail_to_parse_non_positive_job_limit() {
+        for limit in ["0", "-1", "foo"] {
+            assert!(Arguments::try_parse_from(["turtle", "-j", limit]).is_err());
+        }
+    }
+
+    #[test]
+    fn parse_outputs_and_variables() {
+        let arguments =
+            Arguments::try_parse_from(["turtle", "foo", "cflags=-O2", "bar", "=baz", "x=1=2"])
+                .unwrap();
+
+        assert_eq!(arguments.outputs(), ["foo", "bar", "=baz"]);
+        assert_eq!(
+            arguments.variables(),
+            [("cflags".into(), "-O2".into()), ("x".into(), "1=2".into())]
+                .into_iter()
+                .collect()
+        );
+    }
+
+    #[test]
+    fn parse_verbosity() {
+        for (arguments, verbosity) in [
+            (&["turtle"][..], Verbosity::Normal),
+            (&["turtle", "--quiet"], Verbosity::Quiet),
+            (&["turtle", "-q"], Verbosity::Quiet),
+            (&["turtle", "-qq"], Verbosity::Silent),
+            (&["turtle", "-q", "--quiet"], Verbosity::Silent),
+            (&["turtle", "--silent"], Verbosity::Silent),
+        ] {
+            assert_eq!(
+                Arguments::try_parse_from(arguments).unwrap().verbosity(),
+                verbosity
+            );
+        }
+    }
 }
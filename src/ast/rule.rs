@@ -1,20 +1,16 @@
+use super::VariableDefinition;
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Rule {
     name: String,
-    command: String,
-    description: Option<String>,
+    variable_definitions: Vec<VariableDefinition>,
 }
 
 impl Rule {
-    pub fn new(
-        name: impl Into<String>,
-        command: impl Into<String>,
-        description: Option<String>,
-    ) -> Self {
+    pub fn new(name: impl Into<String>, variable_definitions: Vec<VariableDefinition>) -> Self {
         Self {
             name: name.into(),
-            command: command.into(),
-            description,
+            variable_definitions,
         }
     }
 
@@ -22,11 +18,7 @@ impl Rule {
         &self.name
     }
 
-    pub fn command(&self) -> &str {
-        &self.command
-    }
-
-    pub fn description(&self) -> Option<&str> {
-        self.description.as_deref()
+    pub fn variable_definitions(&self) -> &[VariableDefinition] {
+        &self.variable_definitions
     }
 }
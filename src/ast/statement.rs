@@ -1,10 +1,11 @@
-use super::{Build, DefaultOutput, Include, Rule, Submodule, VariableDefinition};
+use super::{Build, DefaultOutput, Include, Pool, Rule, Submodule, VariableDefinition};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Statement {
     Build(Build),
     Default(DefaultOutput),
     Include(Include),
+    Pool(Pool),
     Rule(Rule),
     Submodule(Submodule),
     VariableDefinition(VariableDefinition),
@@ -28,6 +29,12 @@ impl From<Include> for Statement {
     }
 }
 
+impl From<Pool> for Statement {
+    fn from(pool: Pool) -> Self {
+        Self::Pool(pool)
+    }
+}
+
 impl From<Rule> for Statement {
     fn from(rule: Rule) -> Self {
         Self::Rule(rule)
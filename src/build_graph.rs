@@ -6,15 +6,25 @@ use petgraph::{
     Graph,
 };
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet, VecDeque},
     error::Error,
     fmt::{self, Display, Formatter},
     sync::Arc,
 };
 
+// A kind of an edge from an output to its input
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EdgeKind {
+    Explicit,
+    Implicit,
+    OrderOnly,
+    // An edge from a secondary output to its primary one
+    Output,
+}
+
 #[derive(Debug)]
 pub struct BuildGraph {
-    graph: Graph<Arc<str>, ()>,
+    graph: Graph<Arc<str>, EdgeKind>,
     nodes: HashMap<Arc<str>, NodeIndex<DefaultIx>>,
     primary_outputs: HashMap<Arc<str>, Arc<str>>,
 }
@@ -22,14 +32,20 @@ pub struct BuildGraph {
 impl BuildGraph {
     pub fn new(outputs: &HashMap<Arc<str>, Arc<Build>>) -> Self {
         let mut this = Self {
-            graph: Graph::<Arc<str>, ()>::new(),
+            graph: Graph::<Arc<str>, EdgeKind>::new(),
             nodes: HashMap::<Arc<str>, NodeIndex<DefaultIx>>::new(),
             primary_outputs: HashMap::new(),
         };
 
         for (output, build) in outputs {
-            for input in build.inputs().iter().chain(build.order_only_inputs()) {
-                this.add_edge(output.clone(), input.clone());
+            for (inputs, kind) in [
+                (build.explicit_inputs(), EdgeKind::Explicit),
+                (build.implicit_inputs(), EdgeKind::Implicit),
+                (build.order_only_inputs(), EdgeKind::OrderOnly),
+            ] {
+                for input in inputs {
+                    this.add_edge(output.clone(), input.clone(), kind);
+                }
             }
 
             // Is this output primary?
@@ -37,7 +53,7 @@ impl BuildGraph {
                 this.primary_outputs.insert(output.clone(), output.clone());
 
                 for secondary in build.outputs().iter().skip(1) {
-                    this.add_edge(secondary.clone(), output.clone());
+                    this.add_edge(secondary.clone(), output.clone(), EdgeKind::Output);
                     this.primary_outputs
                         .insert(secondary.clone(), output.clone());
                 }
@@ -53,40 +69,84 @@ impl BuildGraph {
 
             components.sort_by_key(|component| component.len());
 
-            return Err(BuildGraphError::CircularDependency(
-                components
-                    .into_iter()
-                    .rev()
-                    .find(|component| component.contains(&cycle.node_id()))
-                    .unwrap()
-                    .into_iter()
-                    .map(|id| self.graph[id].clone())
-                    .collect(),
-            ));
+            let component = components
+                .into_iter()
+                .rev()
+                .find(|component| component.contains(&cycle.node_id()))
+                .unwrap();
+
+            let mut cycle = self
+                .find_cycle(cycle.node_id(), &component.into_iter().collect())
+                .into_iter()
+                .map(|id| self.graph[id].clone())
+                .collect::<Vec<_>>();
+
+            // Start a cycle from the minimum output for deterministic errors.
+            if let Some(index) = cycle.iter().position_min() {
+                cycle.rotate_left(index);
+            }
+
+            return Err(BuildGraphError::CircularDependency(cycle));
         }
 
         Ok(())
     }
 
+    // Finds the shortest cycle from a node by following edges within a strongly
+    // connected component. The node is not repeated at the end.
+    fn find_cycle(
+        &self,
+        start: NodeIndex<DefaultIx>,
+        component: &HashSet<NodeIndex<DefaultIx>>,
+    ) -> Vec<NodeIndex<DefaultIx>> {
+        let mut parents = HashMap::new();
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(node) = queue.pop_front() {
+            for neighbor in self.graph.neighbors(node) {
+                if neighbor == start {
+                    let mut cycle = vec![node];
+
+                    while let Some(&parent) = parents.get(cycle.last().unwrap()) {
+                        cycle.push(parent);
+                    }
+
+                    cycle.reverse();
+
+                    return cycle;
+                } else if component.contains(&neighbor) && !parents.contains_key(&neighbor) {
+                    parents.insert(neighbor, node);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        unreachable!("no cycle found in strongly connected component")
+    }
+
     pub fn validate_dynamic(
         &mut self,
         configuration: &DynamicConfiguration,
     ) -> Result<(), BuildGraphError> {
         for (output, build) in configuration.outputs() {
             for input in build.inputs() {
-                self.add_edge(self.primary_outputs[output].clone(), input.clone());
+                self.add_edge(
+                    self.primary_outputs[output].clone(),
+                    input.clone(),
+                    EdgeKind::Implicit,
+                );
             }
         }
 
         self.validate()
     }
 
-    fn add_edge(&mut self, output: Arc<str>, input: Arc<str>) {
+    fn add_edge(&mut self, output: Arc<str>, input: Arc<str>, kind: EdgeKind) {
         self.add_node(&output);
         self.add_node(&input);
 
         self.graph
-            .add_edge(self.nodes[&output], self.nodes[&input], ());
+            .add_edge(self.nodes[&output], self.nodes[&input], kind);
     }
 
     fn add_node(&mut self, output: &Arc<str>) {
@@ -265,29 +325,25 @@ mod tests {
 
     #[test]
     fn validate_two_circular_builds() {
-        let BuildGraphError::CircularDependency(paths) = validate_builds(
-            &[
-                (
-                    "foo".into(),
-                    explicit_build(vec!["foo".into()], vec!["bar".into()]).into(),
-                ),
-                (
-                    "bar".into(),
-                    explicit_build(vec!["bar".into()], vec!["foo".into()]).into(),
-                ),
-            ]
-            .into_iter()
-            .collect(),
-        )
-        .unwrap_err();
-
         assert_eq!(
-            &paths,
-            &if &*paths[0] == "foo" {
-                ["foo".into(), "bar".into()]
-            } else {
-                ["bar".into(), "foo".into()]
-            }
+            validate_builds(
+                &[
+                    (
+                        "foo".into(),
+                        explicit_build(vec!["foo".into()], vec!["bar".into()]).into(),
+                    ),
+                    (
+                        "bar".into(),
+                        explicit_build(vec!["bar".into()], vec!["foo".into()]).into(),
+                    ),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+            Err(BuildGraphError::CircularDependency(vec![
+                "bar".into(),
+                "foo".into()
+            ]))
         );
     }
 
@@ -317,8 +373,8 @@ mod tests {
                     .collect(),
             )),
             Err(BuildGraphError::CircularDependency(vec![
-                "foo".into(),
                 "bar".into(),
+                "foo".into(),
             ]))
         );
     }
@@ -369,4 +425,107 @@ mod tests {
             ]))
         );
     }
+
+    #[test]
+    fn validate_circular_builds_in_order() {
+        assert_eq!(
+            validate_builds(
+                &[
+                    ("foo", vec!["bar", "qux"]),
+                    ("bar", vec!["baz"]),
+                    ("baz", vec!["foo"]),
+                    ("qux", vec![]),
+                ]
+                .into_iter()
+                .map(|(output, inputs)| {
+                    (
+                        output.into(),
+                        explicit_build(
+                            vec![output.into()],
+                            inputs.into_iter().map(From::from).collect(),
+                        )
+                        .into(),
+                    )
+                })
+                .collect(),
+            ),
+            Err(BuildGraphError::CircularDependency(vec![
+                "bar".into(),
+                "baz".into(),
+                "foo".into()
+            ]))
+        );
+    }
+
+    mod edge_kind {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn edge_kind(graph: &BuildGraph, output: &str, input: &str) -> Option<EdgeKind> {
+            graph
+                .graph
+                .find_edge(graph.nodes[output], graph.nodes[input])
+                .map(|edge| graph.graph[edge])
+        }
+
+        #[test]
+        fn distinguish_inputs() {
+            let graph = BuildGraph::new(
+                &[(
+                    "foo".into(),
+                    Build::new(
+                        vec!["foo".into()],
+                        vec![],
+                        Rule::new("", None).into(),
+                        vec!["bar".into(), "baz".into()],
+                        vec!["qux".into()],
+                        None,
+                    )
+                    .with_implicit_input_count(1)
+                    .into(),
+                )]
+                .into_iter()
+                .collect(),
+            );
+
+            assert_eq!(edge_kind(&graph, "foo", "bar"), Some(EdgeKind::Explicit));
+            assert_eq!(edge_kind(&graph, "foo", "baz"), Some(EdgeKind::Implicit));
+            assert_eq!(edge_kind(&graph, "foo", "qux"), Some(EdgeKind::OrderOnly));
+        }
+
+        #[test]
+        fn link_secondary_output_to_primary_one() {
+            let build = Arc::new(explicit_build(vec!["foo".into(), "bar".into()], vec![]));
+            let graph = BuildGraph::new(
+                &[("foo".into(), build.clone()), ("bar".into(), build)]
+                    .into_iter()
+                    .collect(),
+            );
+
+            assert_eq!(edge_kind(&graph, "bar", "foo"), Some(EdgeKind::Output));
+            assert_eq!(edge_kind(&graph, "foo", "bar"), None);
+        }
+
+        #[test]
+        fn add_dynamic_inputs_as_implicit_ones() {
+            let mut graph = BuildGraph::new(
+                &[(
+                    "foo".into(),
+                    explicit_build(vec!["foo".into()], vec![]).into(),
+                )]
+                .into_iter()
+                .collect(),
+            );
+
+            graph
+                .validate_dynamic(&DynamicConfiguration::new(
+                    [("foo".into(), DynamicBuild::new(vec!["bar".into()]))]
+                        .into_iter()
+                        .collect(),
+                ))
+                .unwrap();
+
+            assert_eq!(edge_kind(&graph, "foo", "bar"), Some(EdgeKind::Implicit));
+        }
+    }
 }
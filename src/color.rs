@@ -0,0 +1,44 @@
+// Colors of diagnostics in ANSI escape sequences.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Color {
+    Red,
+    Green,
+    Yellow,
+}
+
+impl Color {
+    fn code(self) -> &'static str {
+        match self {
+            Self::Red => "31",
+            Self::Green => "32",
+            Self::Yellow => "33",
+        }
+    }
+}
+
+// Colors a string if enabled. A string is kept as it is otherwise so that
+// callers do not need to branch on colors themselves.
+pub fn paint(string: &str, color: Color, enabled: bool) -> String {
+    if enabled {
+        format!("\x1b[{}m{}\x1b[0m", color.code(), string)
+    } else {
+        string.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn paint_string() {
+        assert_eq!(paint("foo", Color::Red, true), "\x1b[31mfoo\x1b[0m");
+        assert_eq!(paint("foo", Color::Green, true), "\x1b[32mfoo\x1b[0m");
+    }
+
+    #[test]
+    fn paint_nothing_if_disabled() {
+        assert_eq!(paint("foo", Color::Red, false), "foo");
+    }
+}
@@ -2,18 +2,28 @@ mod context;
 mod error;
 mod global_state;
 mod module_state;
+mod options;
+mod warning;
 
-pub use self::error::CompileError;
 use self::{context::Context, global_state::GlobalState, module_state::ModuleState};
+pub use self::{error::CompileError, options::Options, warning::CompileWarning};
 use crate::{
     ast,
-    ir::{Build, Configuration, DynamicBuild, DynamicConfiguration, Rule},
+    ir::{
+        Build, Configuration, DependencyFormat, DynamicBuild, DynamicConfiguration, Environment,
+        ResponseFile, Rule, CONSOLE_POOL, DEFAULT_MSVC_DEPENDENCY_PREFIX,
+    },
     module_dependency::ModuleDependencyMap,
+    path::normalize_path,
+    warning::WarningLevel,
 };
+use itertools::Itertools;
 use once_cell::sync::Lazy;
 use regex::{Captures, Regex};
 use std::{
-    collections::HashMap,
+    borrow::Cow,
+    collections::{hash_map::Entry, HashMap, HashSet},
+    mem,
     path::{Path, PathBuf},
     sync::Arc,
 };
@@ -21,51 +31,95 @@ use train_map::TrainMap;
 
 const PHONY_RULE: &str = "phony";
 const BUILD_DIRECTORY_VARIABLE: &str = "builddir";
+const COMMAND_VARIABLE: &str = "command";
+const DESCRIPTION_VARIABLE: &str = "description";
+const POOL_VARIABLE: &str = "pool";
+const DEPFILE_VARIABLE: &str = "depfile";
+const DEPENDENCY_FORMAT_VARIABLE: &str = "deps";
+const MSVC_DEPENDENCY_PREFIX_VARIABLE: &str = "msvc_deps_prefix";
+const RESTAT_VARIABLE: &str = "restat";
+const GENERATOR_VARIABLE: &str = "generator";
+const RESPONSE_FILE_VARIABLE: &str = "rspfile";
+const RESPONSE_FILE_CONTENT_VARIABLE: &str = "rspfile_content";
+const DIRECTORY_VARIABLE: &str = "chdir";
+const ENVIRONMENT_VARIABLE: &str = "environment";
+const CLEAR_ENVIRONMENT_VARIABLE: &str = "env_clear";
 const DYNAMIC_MODULE_VARIABLE: &str = "dyndep";
 const SOURCE_VARIABLE_NAME: &str = "srcdep";
+const SUPPORTED_DYNAMIC_MODULE_VERSIONS: &[&str] = &["1", "1.0"];
 
-static VARIABLE_PATTERN: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"\$([[:alpha:]_][[:alnum:]_]*)").unwrap());
+static VARIABLE_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\$(\$|#|\{([[:alpha:]_][[:alnum:]_]*)\}|([[:alpha:]_][[:alnum:]_]*))").unwrap()
+});
 
+// Root modules are independent of each other except that they share outputs
+// and pools. Default outputs are the union of the ones of each root module.
+//
 // TODO Use a string pool for paths.
 pub fn compile(
     modules: &HashMap<PathBuf, ast::Module>,
     dependencies: &ModuleDependencyMap,
-    root_module_path: &Path,
-) -> Result<Configuration, CompileError> {
-    let context = Context::new(modules, dependencies);
+    root_module_paths: &[PathBuf],
+    options: &Options,
+) -> Result<(Configuration, Vec<CompileWarning>), CompileError> {
+    let context = Context::new(modules, dependencies, options);
 
     let mut global_state = GlobalState {
         outputs: Default::default(),
         default_outputs: Default::default(),
         source_map: Default::default(),
+        pools: Default::default(),
+        warnings: Default::default(),
     };
-    let mut module_state = ModuleState {
-        rules: TrainMap::new(),
-        variables: TrainMap::new(),
-    };
+    let mut default_outputs = HashSet::new();
+    let mut build_directory = None;
 
-    compile_module(
-        &context,
-        &mut global_state,
-        &mut module_state,
-        root_module_path,
-    )?;
+    for path in root_module_paths {
+        let mut module_state = ModuleState {
+            rules: TrainMap::new(),
+            variables: TrainMap::new(),
+        };
 
-    let default_outputs = if global_state.default_outputs.is_empty() {
-        global_state.outputs.keys().cloned().collect()
-    } else {
-        global_state.default_outputs
-    };
+        for (name, value) in &options.variables {
+            module_state
+                .variables
+                .insert(name.as_str(), value.as_str().into());
+        }
+
+        let previous_outputs = global_state.outputs.keys().cloned().collect::<HashSet<_>>();
+
+        compile_module(&context, &mut global_state, &mut module_state, path, 0)?;
+
+        let outputs = mem::take(&mut global_state.default_outputs);
+
+        // Root modules without default statements default to all of their outputs.
+        default_outputs.extend(if outputs.is_empty() {
+            global_state
+                .outputs
+                .keys()
+                .filter(|&output| !previous_outputs.contains(output))
+                .cloned()
+                .collect()
+        } else {
+            outputs
+        });
+        build_directory = build_directory.or_else(|| {
+            module_state
+                .variables
+                .get(BUILD_DIRECTORY_VARIABLE)
+                .cloned()
+        });
+    }
 
-    Ok(Configuration::new(
-        global_state.outputs,
-        default_outputs,
-        global_state.source_map,
-        module_state
-            .variables
-            .get(BUILD_DIRECTORY_VARIABLE)
-            .cloned(),
+    Ok((
+        Configuration::new(
+            global_state.outputs,
+            default_outputs,
+            global_state.source_map,
+            global_state.pools,
+            build_directory,
+        ),
+        global_state.warnings,
     ))
 }
 
@@ -74,7 +128,14 @@ fn compile_module<'a>(
     global_state: &mut GlobalState,
     module_state: &mut ModuleState<'a, '_>,
     path: &Path,
+    depth: usize,
 ) -> Result<(), CompileError> {
+    // Modules are compiled recursively. So we limit their depth so as not to
+    // overflow a stack.
+    if depth > context.options().module_depth_limit {
+        return Err(CompileError::ModuleDepthExceeded(path.into()));
+    }
+
     let module = &context
         .modules()
         .get(path)
@@ -83,77 +144,14 @@ fn compile_module<'a>(
     for statement in module.statements() {
         match statement {
             ast::Statement::Build(build) => {
-                let mut variables = module_state.variables.fork();
-
-                variables.extend(
-                    build
-                        .variable_definitions()
-                        .iter()
-                        .map(|definition| (definition.name(), definition.value().into()))
-                        .chain([
-                            ("in", build.inputs().join(" ").into()),
-                            ("out", build.outputs().join(" ").into()),
-                        ]),
-                );
-
-                let ir = Arc::new(Build::new(
-                    build
-                        .outputs()
-                        .iter()
-                        .map(|string| string.as_str().into())
-                        .collect(),
-                    build
-                        .implicit_outputs()
-                        .iter()
-                        .map(|string| string.as_str().into())
-                        .collect(),
-                    if build.rule() == PHONY_RULE {
-                        None
-                    } else {
-                        let rule = &module_state
-                            .rules
-                            .get(build.rule())
-                            .ok_or_else(|| CompileError::RuleNotFound(build.rule().into()))?;
-
-                        Some(Rule::new(
-                            interpolate_variables(rule.command(), &variables),
-                            rule.description()
-                                .map(|description| interpolate_variables(description, &variables)),
-                        ))
-                    },
-                    build
-                        .inputs()
-                        .iter()
-                        .chain(build.implicit_inputs())
-                        .map(|string| string.as_str().into())
-                        .collect(),
-                    build
-                        .order_only_inputs()
-                        .iter()
-                        .map(|string| string.as_str().into())
-                        .collect(),
-                    variables.get(DYNAMIC_MODULE_VARIABLE).cloned(),
-                ));
-
-                let outputs = || build.outputs().iter().chain(build.implicit_outputs());
-
-                global_state
-                    .outputs
-                    .extend(outputs().map(|output| (output.as_str().into(), ir.clone())));
-
-                if let Some(source) = variables.get(SOURCE_VARIABLE_NAME) {
-                    global_state
-                        .source_map
-                        .extend(outputs().map(|output| (output.as_str().into(), source.clone())));
-                }
+                compile_build(context, global_state, module_state, build)?;
             }
+            // Default outputs are global like the ones in Ninja. So default
+            // statements in both included and child modules contribute to them.
             ast::Statement::Default(default) => {
-                global_state.default_outputs.extend(
-                    default
-                        .outputs()
-                        .iter()
-                        .map(|string| string.as_str().into()),
-                );
+                global_state
+                    .default_outputs
+                    .extend(normalize_paths(default.outputs()));
             }
             ast::Statement::Include(include) => {
                 compile_module(
@@ -161,8 +159,17 @@ fn compile_module<'a>(
                     global_state,
                     module_state,
                     resolve_dependency(context, path, include.path())?,
+                    depth + 1,
                 )?;
             }
+            ast::Statement::Pool(pool) => {
+                global_state.pools.insert(
+                    pool.name().into(),
+                    interpolate_variables(pool.depth(), &module_state.variables)
+                        .parse()
+                        .map_err(|_| CompileError::InvalidPoolDepth(pool.name().into()))?,
+                );
+            }
             ast::Statement::Rule(rule) => {
                 module_state.rules.insert(rule.name(), rule.clone());
             }
@@ -172,12 +179,222 @@ fn compile_module<'a>(
                     global_state,
                     &mut module_state.fork(),
                     resolve_dependency(context, path, submodule.path())?,
+                    depth + 1,
                 )?;
             }
+            // Variables given on the command line take precedence over ones
+            // defined at the top levels of modules.
+            ast::Statement::VariableDefinition(definition)
+                if context.options().variables.contains_key(definition.name()) => {}
             ast::Statement::VariableDefinition(definition) => {
+                let value = interpolate_variables(definition.value(), &module_state.variables);
+
                 module_state
                     .variables
-                    .insert(definition.name(), definition.value().into());
+                    .insert(definition.name(), value.into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn compile_build<'a>(
+    context: &'a Context,
+    global_state: &mut GlobalState,
+    module_state: &ModuleState<'a, '_>,
+    build: &'a ast::Build,
+) -> Result<(), CompileError> {
+    let mut variables = module_state.variables.fork();
+
+    // Build-local variables are evaluated in a module scope where `in` and
+    // `out` variables are not defined yet.
+    variables.extend(
+        build
+            .variable_definitions()
+            .iter()
+            .map(|definition| {
+                (
+                    definition.name(),
+                    interpolate_variables(definition.value(), &module_state.variables).into(),
+                )
+            })
+            .chain([
+                ("in", join_paths(build.inputs(), " ").into()),
+                ("in_newline", join_paths(build.inputs(), "\n").into()),
+                (
+                    "in_first",
+                    join_paths(build.inputs().get(..1).unwrap_or_default(), " ").into(),
+                ),
+                ("out", join_paths(build.outputs(), " ").into()),
+            ]),
+    );
+
+    let (inputs, implicit_input_count) = normalize_inputs(build.inputs(), build.implicit_inputs());
+    let ir = Arc::new(
+        Build::new(
+            normalize_paths(build.outputs()),
+            normalize_paths(build.implicit_outputs()),
+            if build.rule() == PHONY_RULE {
+                None
+            } else {
+                let rule = &module_state
+                    .rules
+                    .get(build.rule())
+                    .ok_or_else(|| CompileError::RuleNotFound(build.rule().into()))?;
+                let variable = |name| resolve_rule_variable(rule, build, name, &variables);
+                let (command, undefined_variables) = resolve_command(rule, build, &variables);
+
+                for name in undefined_variables {
+                    let output = build.outputs()[0].clone();
+
+                    match context.options().warnings.undefined_variable {
+                        WarningLevel::Off => {}
+                        WarningLevel::Warn => {
+                            global_state
+                                .warnings
+                                .push(CompileWarning::UndefinedVariable(
+                                    name,
+                                    rule.name().into(),
+                                    output,
+                                ))
+                        }
+                        WarningLevel::Err => {
+                            return Err(CompileError::UndefinedVariable(
+                                name,
+                                rule.name().into(),
+                                output,
+                            ))
+                        }
+                    }
+                }
+
+                let command = command.unwrap_or_default();
+
+                if command.trim().is_empty() {
+                    let output = build.outputs()[0].clone();
+
+                    match context.options().warnings.empty_command {
+                        WarningLevel::Off => {}
+                        WarningLevel::Warn => global_state
+                            .warnings
+                            .push(CompileWarning::EmptyCommand(rule.name().into(), output)),
+                        WarningLevel::Err => {
+                            return Err(CompileError::EmptyCommand(rule.name().into(), output))
+                        }
+                    }
+                }
+
+                let mut ir =
+                    Rule::new(command, variable(DESCRIPTION_VARIABLE)).with_name(rule.name());
+
+                if let Some(pool) = variable(POOL_VARIABLE).filter(|pool| !pool.is_empty()) {
+                    if pool != CONSOLE_POOL && !global_state.pools.contains_key(pool.as_str()) {
+                        return Err(CompileError::PoolNotFound(pool));
+                    }
+
+                    ir = ir.with_pool(pool);
+                }
+
+                if let Some(depfile) =
+                    variable(DEPFILE_VARIABLE).filter(|depfile| !depfile.is_empty())
+                {
+                    ir = ir.with_depfile(depfile);
+                }
+
+                match variable(DEPENDENCY_FORMAT_VARIABLE).as_deref() {
+                    None | Some("") => {}
+                    Some("gcc") => {
+                        ir = ir.with_dependency_format(DependencyFormat::Gcc);
+                    }
+                    Some("msvc") => {
+                        ir = ir.with_dependency_format(DependencyFormat::Msvc(
+                            variable(MSVC_DEPENDENCY_PREFIX_VARIABLE)
+                                .or_else(|| {
+                                    variables
+                                        .get(MSVC_DEPENDENCY_PREFIX_VARIABLE)
+                                        .map(|prefix| prefix.as_ref().into())
+                                })
+                                .unwrap_or_else(|| DEFAULT_MSVC_DEPENDENCY_PREFIX.into()),
+                        ));
+                    }
+                    Some(format) => {
+                        return Err(CompileError::InvalidDependencyFormat(format.into()))
+                    }
+                }
+
+                if let Some(path) = variable(RESPONSE_FILE_VARIABLE).filter(|path| !path.is_empty())
+                {
+                    ir = ir.with_response_file(ResponseFile::new(
+                        path,
+                        variable(RESPONSE_FILE_CONTENT_VARIABLE).unwrap_or_default(),
+                    ));
+                }
+
+                if let Some(directory) =
+                    variable(DIRECTORY_VARIABLE).filter(|directory| !directory.is_empty())
+                {
+                    ir = ir.with_directory(directory);
+                }
+
+                let flag = |name| variable(name).is_some_and(|value| !value.is_empty());
+
+                ir = ir.with_environment(Environment::new(
+                    variable(ENVIRONMENT_VARIABLE)
+                        .unwrap_or_default()
+                        .split_whitespace()
+                        .map(|pair| {
+                            pair.split_once('=')
+                                .filter(|(name, _)| !name.is_empty())
+                                .map(|(name, value)| (name.into(), value.into()))
+                                .ok_or_else(|| {
+                                    CompileError::InvalidEnvironmentVariable(pair.into())
+                                })
+                        })
+                        .collect::<Result<_, _>>()?,
+                    flag(CLEAR_ENVIRONMENT_VARIABLE),
+                ));
+
+                ir = ir
+                    .with_restat(flag(RESTAT_VARIABLE))
+                    .with_generator(flag(GENERATOR_VARIABLE));
+
+                Some(ir)
+            },
+            inputs,
+            normalize_paths(build.order_only_inputs()),
+            variables.get(DYNAMIC_MODULE_VARIABLE).cloned(),
+        )
+        .with_implicit_input_count(implicit_input_count),
+    );
+
+    let source = variables.get(SOURCE_VARIABLE_NAME);
+
+    for output in ir.outputs().iter().chain(ir.implicit_outputs()) {
+        match global_state.outputs.entry(output.clone()) {
+            Entry::Occupied(entry) => {
+                if Arc::ptr_eq(entry.get(), &ir) {
+                    continue;
+                }
+
+                match context.options().warnings.duplicate_build {
+                    WarningLevel::Off => {}
+                    WarningLevel::Warn => global_state
+                        .warnings
+                        .push(CompileWarning::DuplicateOutput(output.as_ref().into())),
+                    WarningLevel::Err => {
+                        return Err(CompileError::DuplicateOutput(output.as_ref().into()))
+                    }
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(ir.clone());
+
+                if let Some(source) = source {
+                    global_state
+                        .source_map
+                        .insert(output.clone(), source.clone());
+                }
             }
         }
     }
@@ -186,26 +403,57 @@ fn compile_module<'a>(
 }
 
 pub fn compile_dynamic(module: &ast::DynamicModule) -> Result<DynamicConfiguration, CompileError> {
+    if !SUPPORTED_DYNAMIC_MODULE_VERSIONS.contains(&module.version()) {
+        return Err(CompileError::UnsupportedDynamicModuleVersion(
+            module.version().into(),
+        ));
+    }
+
     Ok(DynamicConfiguration::new(
         module
             .builds()
             .iter()
             .map(|build| {
                 (
-                    build.output().into(),
-                    DynamicBuild::new(
-                        build
-                            .implicit_inputs()
-                            .iter()
-                            .map(|string| string.as_str().into())
-                            .collect(),
-                    ),
+                    normalize(build.output()),
+                    DynamicBuild::new(normalize_paths(build.implicit_inputs())),
                 )
             })
             .collect(),
     ))
 }
 
+// Paths are normalized lexically so that equivalent ones like `foo` and
+// `./foo` refer to the same file.
+fn normalize(path: &str) -> Arc<str> {
+    normalize_path(path.as_ref()).to_string_lossy().into()
+}
+
+fn normalize_paths(paths: &[String]) -> Vec<Arc<str>> {
+    paths.iter().map(|path| normalize(path)).collect()
+}
+
+// Deduplicates normalized explicit and implicit inputs. Implicit inputs
+// duplicate with explicit ones are dropped.
+fn normalize_inputs(inputs: &[String], implicit_inputs: &[String]) -> (Vec<Arc<str>>, usize) {
+    let mut paths = HashSet::<Arc<str>>::new();
+    let mut normalize_unique = |inputs: &[String]| {
+        inputs
+            .iter()
+            .map(|path| normalize(path))
+            .filter(|path| paths.insert(path.clone()))
+            .collect::<Vec<_>>()
+    };
+
+    let mut normalized = normalize_unique(inputs);
+    let implicit_inputs = normalize_unique(implicit_inputs);
+    let implicit_input_count = implicit_inputs.len();
+
+    normalized.extend(implicit_inputs);
+
+    (normalized, implicit_input_count)
+}
+
 fn resolve_dependency<'a>(
     context: &'a Context,
     module_path: &Path,
@@ -219,32 +467,148 @@ fn resolve_dependency<'a>(
         .ok_or_else(|| CompileError::ModuleNotFound(submodule_path.into()))?)
 }
 
+// Build-local variables take precedence over rule variables. Variables can
+// refer to the other rule variables unless their references are circular.
+fn resolve_rule_variable(
+    rule: &ast::Rule,
+    build: &ast::Build,
+    name: &str,
+    variables: &TrainMap<&str, Arc<str>>,
+) -> Option<String> {
+    resolve_rule_variable_recursively(rule, build, name, variables, &mut vec![], &mut vec![])
+}
+
+// Resolves a command with names of undefined variables referenced in it.
+fn resolve_command(
+    rule: &ast::Rule,
+    build: &ast::Build,
+    variables: &TrainMap<&str, Arc<str>>,
+) -> (Option<String>, Vec<String>) {
+    let mut undefined_variables = vec![];
+    let command = resolve_rule_variable_recursively(
+        rule,
+        build,
+        COMMAND_VARIABLE,
+        variables,
+        &mut vec![],
+        &mut undefined_variables,
+    );
+
+    (command, undefined_variables.into_iter().unique().collect())
+}
+
+fn resolve_rule_variable_recursively(
+    rule: &ast::Rule,
+    build: &ast::Build,
+    name: &str,
+    variables: &TrainMap<&str, Arc<str>>,
+    names: &mut Vec<String>,
+    undefined_variables: &mut Vec<String>,
+) -> Option<String> {
+    let definition = build
+        .variable_definitions()
+        .iter()
+        .rev()
+        .chain(rule.variable_definitions().iter().rev())
+        .find(|definition| definition.name() == name)?;
+
+    names.push(name.into());
+
+    let value = interpolate(definition.value(), |name| {
+        if !names.iter().any(|other| other == name)
+            && rule
+                .variable_definitions()
+                .iter()
+                .any(|definition| definition.name() == name)
+        {
+            resolve_rule_variable_recursively(
+                rule,
+                build,
+                name,
+                variables,
+                names,
+                undefined_variables,
+            )
+        } else if let Some(value) = variables.get(name) {
+            Some(value.as_ref().into())
+        } else {
+            undefined_variables.push(name.into());
+            None
+        }
+    });
+
+    names.pop();
+
+    Some(value)
+}
+
+fn join_paths(paths: &[String], separator: &str) -> String {
+    paths.iter().map(|path| escape_shell(path)).join(separator)
+}
+
+// Quotes a string for shells only if it contains special characters in the
+// same way as Ninja.
+fn escape_shell(string: &str) -> Cow<'_, str> {
+    if string
+        .chars()
+        .all(|character| character.is_ascii_alphanumeric() || "_+-./".contains(character))
+    {
+        string.into()
+    } else {
+        format!("'{}'", string.replace('\'', "'\\''")).into()
+    }
+}
+
 fn interpolate_variables(template: &str, variables: &TrainMap<&str, Arc<str>>) -> String {
+    interpolate(template, |name| {
+        variables.get(name).map(|value| value.as_ref().into())
+    })
+}
+
+// Interpolates variables and escaped dollar and hash signs in a single pass so
+// that interpolated values are never processed again.
+fn interpolate(template: &str, mut resolve: impl FnMut(&str) -> Option<String>) -> String {
     VARIABLE_PATTERN
         .replace_all(template, |captures: &Captures| {
-            variables
-                .get(&captures[1])
-                .map(|string| string.as_ref())
-                .unwrap_or_default()
+            if let Some(name) = captures.get(2).or_else(|| captures.get(3)) {
+                resolve(name.as_str()).unwrap_or_default()
+            } else {
+                captures[1].into()
+            }
         })
-        .replace("$$", "$")
+        .into()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ast;
+    use crate::{ast, warning::WarningConfiguration};
     use once_cell::sync::Lazy;
     use pretty_assertions::assert_eq;
     use std::collections::HashSet;
 
     static ROOT_MODULE_PATH: Lazy<PathBuf> = Lazy::new(|| PathBuf::from("build.ninja"));
+    static ROOT_MODULE_PATHS: Lazy<Vec<PathBuf>> = Lazy::new(|| vec![ROOT_MODULE_PATH.clone()]);
     static DEFAULT_DEPENDENCIES: Lazy<ModuleDependencyMap> = Lazy::new(|| {
         [(ROOT_MODULE_PATH.clone(), Default::default())]
             .into_iter()
             .collect()
     });
 
+    fn compile(
+        modules: &HashMap<PathBuf, ast::Module>,
+        dependencies: &ModuleDependencyMap,
+        root_module_path: &Path,
+    ) -> Result<Configuration, CompileError> {
+        super::compile(
+            modules,
+            dependencies,
+            &[root_module_path.into()],
+            &Default::default(),
+        )
+        .map(|(configuration, _)| configuration)
+    }
+
     fn ast_explicit_build(
         outputs: Vec<String>,
         rule: impl Into<String>,
@@ -262,6 +626,13 @@ mod tests {
         )
     }
 
+    fn ast_rule(name: &str, command: &str) -> ast::Rule {
+        ast::Rule::new(
+            name,
+            vec![ast::VariableDefinition::new(COMMAND_VARIABLE, command)],
+        )
+    }
+
     fn ir_explicit_build(outputs: Vec<Arc<str>>, rule: Rule, inputs: Vec<Arc<str>>) -> Build {
         Build::new(outputs, vec![], rule.into(), inputs, vec![], None)
     }
@@ -270,7 +641,13 @@ mod tests {
         outputs: HashMap<Arc<str>, Arc<Build>>,
         default_outputs: HashSet<Arc<str>>,
     ) -> Configuration {
-        Configuration::new(outputs, default_outputs, Default::default(), None)
+        Configuration::new(
+            outputs,
+            default_outputs,
+            Default::default(),
+            Default::default(),
+            None,
+        )
     }
 
     #[test]
@@ -296,7 +673,7 @@ mod tests {
                     ROOT_MODULE_PATH.clone(),
                     ast::Module::new(vec![
                         ast::VariableDefinition::new("x", "42").into(),
-                        ast::Rule::new("foo", "$x", None).into(),
+                        ast_rule("foo", "$x").into(),
                         ast_explicit_build(vec!["bar".into()], "foo", vec![], vec![]).into(),
                     ])
                 )]
@@ -309,7 +686,12 @@ mod tests {
             create_simple_configuration(
                 [(
                     "bar".into(),
-                    ir_explicit_build(vec!["bar".into()], Rule::new("42", None), vec![]).into()
+                    ir_explicit_build(
+                        vec!["bar".into()],
+                        Rule::new("42", None).with_name("foo"),
+                        vec![]
+                    )
+                    .into()
                 )]
                 .into_iter()
                 .collect(),
@@ -327,7 +709,7 @@ mod tests {
                     ast::Module::new(vec![
                         ast::VariableDefinition::new("x", "1").into(),
                         ast::VariableDefinition::new("y", "2").into(),
-                        ast::Rule::new("foo", "$x $y", None).into(),
+                        ast_rule("foo", "$x $y").into(),
                         ast_explicit_build(vec!["bar".into()], "foo", vec![], vec![]).into(),
                     ])
                 )]
@@ -340,7 +722,12 @@ mod tests {
             create_simple_configuration(
                 [(
                     "bar".into(),
-                    ir_explicit_build(vec!["bar".into()], Rule::new("1 2", None), vec![]).into()
+                    ir_explicit_build(
+                        vec!["bar".into()],
+                        Rule::new("1 2", None).with_name("foo"),
+                        vec![]
+                    )
+                    .into()
                 )]
                 .into_iter()
                 .collect(),
@@ -357,7 +744,7 @@ mod tests {
                     ROOT_MODULE_PATH.clone(),
                     ast::Module::new(vec![
                         ast::VariableDefinition::new("x_y", "42").into(),
-                        ast::Rule::new("foo", "$x_y", None).into(),
+                        ast_rule("foo", "$x_y").into(),
                         ast_explicit_build(vec!["bar".into()], "foo", vec![], vec![]).into(),
                     ])
                 )]
@@ -370,7 +757,12 @@ mod tests {
             create_simple_configuration(
                 [(
                     "bar".into(),
-                    ir_explicit_build(vec!["bar".into()], Rule::new("42", None), vec![]).into()
+                    ir_explicit_build(
+                        vec!["bar".into()],
+                        Rule::new("42", None).with_name("foo"),
+                        vec![]
+                    )
+                    .into()
                 )]
                 .into_iter()
                 .collect(),
@@ -380,14 +772,16 @@ mod tests {
     }
 
     #[test]
-    fn interpolate_dollar_sign_in_command() {
+    fn interpolate_variable_with_braces_in_command() {
         assert_eq!(
             compile(
                 &[(
                     ROOT_MODULE_PATH.clone(),
                     ast::Module::new(vec![
-                        ast::Rule::new("foo", "$$", None).into(),
-                        ast_explicit_build(vec!["bar".into()], "foo", vec![], vec![]).into()
+                        ast::VariableDefinition::new("x", "1").into(),
+                        ast::VariableDefinition::new("xy", "2").into(),
+                        ast_rule("foo", "${x}y $xy ${xy}").into(),
+                        ast_explicit_build(vec!["bar".into()], "foo", vec![], vec![]).into(),
                     ])
                 )]
                 .into_iter()
@@ -399,7 +793,12 @@ mod tests {
             create_simple_configuration(
                 [(
                     "bar".into(),
-                    ir_explicit_build(vec!["bar".into()], Rule::new("$", None), vec![]).into()
+                    ir_explicit_build(
+                        vec!["bar".into()],
+                        Rule::new("1y 2 2", None).with_name("foo"),
+                        vec![]
+                    )
+                    .into()
                 )]
                 .into_iter()
                 .collect(),
@@ -409,15 +808,17 @@ mod tests {
     }
 
     #[test]
-    fn interpolate_in_variable_in_command() {
+    fn interpolate_variable_at_definition() {
         assert_eq!(
             compile(
                 &[(
                     ROOT_MODULE_PATH.clone(),
                     ast::Module::new(vec![
-                        ast::Rule::new("foo", "$in", None).into(),
-                        ast_explicit_build(vec!["bar".into()], "foo", vec!["baz".into()], vec![])
-                            .into(),
+                        ast::VariableDefinition::new("x", "1").into(),
+                        ast::VariableDefinition::new("y", "$x").into(),
+                        ast::VariableDefinition::new("x", "2").into(),
+                        ast_rule("foo", "$x $y").into(),
+                        ast_explicit_build(vec!["bar".into()], "foo", vec![], vec![]).into(),
                     ])
                 )]
                 .into_iter()
@@ -431,8 +832,8 @@ mod tests {
                     "bar".into(),
                     ir_explicit_build(
                         vec!["bar".into()],
-                        Rule::new("baz", None),
-                        vec!["baz".into()]
+                        Rule::new("2 1", None).with_name("foo"),
+                        vec![]
                     )
                     .into()
                 )]
@@ -444,21 +845,22 @@ mod tests {
     }
 
     #[test]
-    fn interpolate_in_variable_with_implicit_input() {
+    fn interpolate_build_local_variable_at_definition() {
         assert_eq!(
             compile(
                 &[(
                     ROOT_MODULE_PATH.clone(),
                     ast::Module::new(vec![
-                        ast::Rule::new("foo", "$in", None).into(),
+                        ast::VariableDefinition::new("x", "1").into(),
+                        ast_rule("foo", "$y").into(),
                         ast::Build::new(
                             vec!["bar".into()],
                             vec![],
                             "foo",
                             vec!["baz".into()],
-                            vec!["blah".into()],
                             vec![],
-                            vec![]
+                            vec![],
+                            vec![ast::VariableDefinition::new("y", "$x$in")]
                         )
                         .into(),
                     ])
@@ -474,8 +876,8 @@ mod tests {
                     "bar".into(),
                     ir_explicit_build(
                         vec!["bar".into()],
-                        Rule::new("baz", None),
-                        vec!["baz".into(), "blah".into()]
+                        Rule::new("1", None).with_name("foo"),
+                        vec!["baz".into()]
                     )
                     .into()
                 )]
@@ -487,14 +889,14 @@ mod tests {
     }
 
     #[test]
-    fn interpolate_out_variable_in_command() {
+    fn interpolate_dollar_sign_in_command() {
         assert_eq!(
             compile(
                 &[(
                     ROOT_MODULE_PATH.clone(),
                     ast::Module::new(vec![
-                        ast::Rule::new("foo", "$out", None).into(),
-                        ast_explicit_build(vec!["bar".into()], "foo", vec![], vec![]).into(),
+                        ast_rule("foo", "$$").into(),
+                        ast_explicit_build(vec!["bar".into()], "foo", vec![], vec![]).into()
                     ])
                 )]
                 .into_iter()
@@ -506,7 +908,12 @@ mod tests {
             create_simple_configuration(
                 [(
                     "bar".into(),
-                    ir_explicit_build(vec!["bar".into()], Rule::new("bar", None), vec![]).into()
+                    ir_explicit_build(
+                        vec!["bar".into()],
+                        Rule::new("$", None).with_name("foo"),
+                        vec![]
+                    )
+                    .into()
                 )]
                 .into_iter()
                 .collect(),
@@ -516,32 +923,14 @@ mod tests {
     }
 
     #[test]
-    fn interpolate_out_variable_with_implicit_output() {
-        let build = Arc::new(Build::new(
-            vec!["bar".into()],
-            vec!["baz".into()],
-            Rule::new("bar", None).into(),
-            vec![],
-            vec![],
-            None,
-        ));
-
+    fn interpolate_hash_sign_in_command() {
         assert_eq!(
             compile(
                 &[(
                     ROOT_MODULE_PATH.clone(),
                     ast::Module::new(vec![
-                        ast::Rule::new("foo", "$out", None).into(),
-                        ast::Build::new(
-                            vec!["bar".into()],
-                            vec!["baz".into()],
-                            "foo",
-                            vec![],
-                            vec![],
-                            vec![],
-                            vec![]
-                        )
-                        .into(),
+                        ast_rule("foo", "echo $#1").into(),
+                        ast_explicit_build(vec!["bar".into()], "foo", vec![], vec![]).into()
                     ])
                 )]
                 .into_iter()
@@ -551,32 +940,32 @@ mod tests {
             )
             .unwrap(),
             create_simple_configuration(
-                [("baz".into(), build.clone()), ("bar".into(), build)]
-                    .into_iter()
-                    .collect(),
-                ["baz".into(), "bar".into()].into_iter().collect()
+                [(
+                    "bar".into(),
+                    ir_explicit_build(
+                        vec!["bar".into()],
+                        Rule::new("echo #1", None).with_name("foo"),
+                        vec![]
+                    )
+                    .into()
+                )]
+                .into_iter()
+                .collect(),
+                ["bar".into()].into_iter().collect()
             )
         );
     }
 
     #[test]
-    fn compile_order_only_inputs() {
+    fn do_not_unescape_dollar_signs_in_variable_values() {
         assert_eq!(
             compile(
                 &[(
                     ROOT_MODULE_PATH.clone(),
                     ast::Module::new(vec![
-                        ast::Rule::new("foo", "$in", None).into(),
-                        ast::Build::new(
-                            vec!["bar".into()],
-                            vec![],
-                            "foo",
-                            vec![],
-                            vec![],
-                            vec!["baz".into()],
-                            vec![]
-                        )
-                        .into(),
+                        ast::VariableDefinition::new("x", "a$$$$b").into(),
+                        ast_rule("foo", "$x $$x").into(),
+                        ast_explicit_build(vec!["bar".into()], "foo", vec![], vec![]).into()
                     ])
                 )]
                 .into_iter()
@@ -588,13 +977,10 @@ mod tests {
             create_simple_configuration(
                 [(
                     "bar".into(),
-                    Build::new(
+                    ir_explicit_build(
                         vec!["bar".into()],
-                        vec![],
-                        Some(Rule::new("", None)),
-                        vec![],
-                        vec!["baz".into()],
-                        None
+                        Rule::new("a$$b $x", None).with_name("foo"),
+                        vec![]
                     )
                     .into()
                 )]
@@ -606,15 +992,15 @@ mod tests {
     }
 
     #[test]
-    fn generate_build_ids() {
+    fn interpolate_in_variable_in_command() {
         assert_eq!(
             compile(
                 &[(
                     ROOT_MODULE_PATH.clone(),
                     ast::Module::new(vec![
-                        ast::Rule::new("foo", "", None).into(),
-                        ast_explicit_build(vec!["bar".into()], "foo", vec![], vec![]).into(),
-                        ast_explicit_build(vec!["baz".into()], "foo", vec![], vec![]).into()
+                        ast_rule("foo", "$in").into(),
+                        ast_explicit_build(vec!["bar".into()], "foo", vec!["baz".into()], vec![])
+                            .into(),
                     ])
                 )]
                 .into_iter()
@@ -624,36 +1010,103 @@ mod tests {
             )
             .unwrap(),
             create_simple_configuration(
-                [
-                    (
-                        "bar".into(),
-                        ir_explicit_build(vec!["bar".into()], Rule::new("", None), vec![]).into()
-                    ),
-                    (
-                        "baz".into(),
-                        ir_explicit_build(vec!["baz".into()], Rule::new("", None), vec![]).into()
+                [(
+                    "bar".into(),
+                    ir_explicit_build(
+                        vec!["bar".into()],
+                        Rule::new("baz", None).with_name("foo"),
+                        vec!["baz".into()]
                     )
-                ]
+                    .into()
+                )]
                 .into_iter()
                 .collect(),
-                ["bar".into(), "baz".into()].into_iter().collect()
+                ["bar".into()].into_iter().collect()
             )
         );
     }
 
     #[test]
-    fn interpolate_build_local_variable() {
+    fn interpolate_in_first_variable_in_command() {
         assert_eq!(
             compile(
                 &[(
                     ROOT_MODULE_PATH.clone(),
                     ast::Module::new(vec![
-                        ast::Rule::new("foo", "$x", None).into(),
-                        ast_explicit_build(
+                        ast_rule("foo", "$in_first").into(),
+                        ast::Build::new(
                             vec!["bar".into()],
+                            vec![],
                             "foo",
+                            vec!["baz".into(), "qux".into()],
+                            vec!["quux".into()],
                             vec![],
-                            vec![ast::VariableDefinition::new("x", "42")]
+                            vec![]
+                        )
+                        .into(),
+                    ])
+                )]
+                .into_iter()
+                .collect(),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATH
+            )
+            .unwrap()
+            .outputs()["bar"]
+                .rule()
+                .unwrap()
+                .command(),
+            "baz"
+        );
+    }
+
+    #[test]
+    fn interpolate_in_first_variable_without_inputs() {
+        assert_eq!(
+            compile(
+                &[(
+                    ROOT_MODULE_PATH.clone(),
+                    ast::Module::new(vec![
+                        ast_rule("foo", "echo $in_first").into(),
+                        ast::Build::new(
+                            vec!["bar".into()],
+                            vec![],
+                            "foo",
+                            vec![],
+                            vec!["baz".into()],
+                            vec![],
+                            vec![]
+                        )
+                        .into(),
+                    ])
+                )]
+                .into_iter()
+                .collect(),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATH
+            )
+            .unwrap()
+            .outputs()["bar"]
+                .rule()
+                .unwrap()
+                .command(),
+            "echo "
+        );
+    }
+
+    #[test]
+    fn interpolate_in_newline_variable_in_command() {
+        assert_eq!(
+            compile(
+                &[(
+                    ROOT_MODULE_PATH.clone(),
+                    ast::Module::new(vec![
+                        ast_rule("foo", "$in_newline $in").into(),
+                        ast_explicit_build(
+                            vec!["bar".into()],
+                            "foo",
+                            vec!["baz".into(), "qux".into()],
+                            vec![]
                         )
                         .into(),
                     ])
@@ -667,7 +1120,12 @@ mod tests {
             create_simple_configuration(
                 [(
                     "bar".into(),
-                    ir_explicit_build(vec!["bar".into()], Rule::new("42", None), vec![]).into()
+                    ir_explicit_build(
+                        vec!["bar".into()],
+                        Rule::new("baz\nqux baz qux", None).with_name("foo"),
+                        vec!["baz".into(), "qux".into()]
+                    )
+                    .into()
                 )]
                 .into_iter()
                 .collect(),
@@ -677,21 +1135,61 @@ mod tests {
     }
 
     #[test]
-    fn compile_source_map() {
+    fn escape_paths_in_in_and_out_variables() {
         assert_eq!(
             compile(
                 &[(
                     ROOT_MODULE_PATH.clone(),
                     ast::Module::new(vec![
-                        ast::Rule::new("foo", "foo", None).into(),
+                        ast_rule("foo", "$in $out").into(),
                         ast_explicit_build(
+                            vec!["bar baz".into()],
+                            "foo",
+                            vec!["qux".into(), "it's".into()],
+                            vec![]
+                        )
+                        .into(),
+                    ])
+                )]
+                .into_iter()
+                .collect(),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATH
+            )
+            .unwrap(),
+            create_simple_configuration(
+                [(
+                    "bar baz".into(),
+                    ir_explicit_build(
+                        vec!["bar baz".into()],
+                        Rule::new("qux 'it'\\''s' 'bar baz'", None).with_name("foo"),
+                        vec!["qux".into(), "it's".into()]
+                    )
+                    .into()
+                )]
+                .into_iter()
+                .collect(),
+                ["bar baz".into()].into_iter().collect()
+            )
+        );
+    }
+
+    #[test]
+    fn interpolate_in_variable_with_implicit_input() {
+        assert_eq!(
+            compile(
+                &[(
+                    ROOT_MODULE_PATH.clone(),
+                    ast::Module::new(vec![
+                        ast_rule("foo", "$in").into(),
+                        ast::Build::new(
                             vec!["bar".into()],
+                            vec![],
                             "foo",
+                            vec!["baz".into()],
+                            vec!["blah".into()],
                             vec![],
-                            vec![ast::VariableDefinition::new(
-                                SOURCE_VARIABLE_NAME,
-                                "oh-my-src"
-                            )]
+                            vec![]
                         )
                         .into(),
                     ])
@@ -702,33 +1200,34 @@ mod tests {
                 &ROOT_MODULE_PATH
             )
             .unwrap(),
-            Configuration::new(
+            create_simple_configuration(
                 [(
                     "bar".into(),
-                    ir_explicit_build(vec!["bar".into()], Rule::new("foo", None), vec![]).into()
+                    ir_explicit_build(
+                        vec!["bar".into()],
+                        Rule::new("baz", None).with_name("foo"),
+                        vec!["baz".into(), "blah".into()]
+                    )
+                    .with_implicit_input_count(1)
+                    .into()
                 )]
                 .into_iter()
                 .collect(),
-                ["bar".into()].into_iter().collect(),
-                [("bar".into(), "oh-my-src".into())].into_iter().collect(),
-                None,
+                ["bar".into()].into_iter().collect()
             )
         );
     }
 
     #[test]
-    fn compile_phony_rule() {
+    fn interpolate_out_variable_in_command() {
         assert_eq!(
             compile(
                 &[(
                     ROOT_MODULE_PATH.clone(),
-                    ast::Module::new(vec![ast_explicit_build(
-                        vec!["foo".into()],
-                        "phony",
-                        vec!["bar".into()],
-                        vec![]
-                    )
-                    .into(),])
+                    ast::Module::new(vec![
+                        ast_rule("foo", "$out").into(),
+                        ast_explicit_build(vec!["bar".into()], "foo", vec![], vec![]).into(),
+                    ])
                 )]
                 .into_iter()
                 .collect(),
@@ -738,31 +1237,49 @@ mod tests {
             .unwrap(),
             create_simple_configuration(
                 [(
-                    "foo".into(),
-                    Build::new(
-                        vec!["foo".into()],
-                        vec![],
-                        None,
+                    "bar".into(),
+                    ir_explicit_build(
                         vec!["bar".into()],
-                        vec![],
-                        None
+                        Rule::new("bar", None).with_name("foo"),
+                        vec![]
                     )
                     .into()
                 )]
                 .into_iter()
                 .collect(),
-                ["foo".into()].into_iter().collect()
+                ["bar".into()].into_iter().collect()
             )
         );
     }
 
     #[test]
-    fn compile_build_directory() {
+    fn interpolate_out_variable_with_implicit_output() {
+        let build = Arc::new(Build::new(
+            vec!["bar".into()],
+            vec!["baz".into()],
+            Rule::new("bar", None).with_name("foo").into(),
+            vec![],
+            vec![],
+            None,
+        ));
+
         assert_eq!(
             compile(
                 &[(
                     ROOT_MODULE_PATH.clone(),
-                    ast::Module::new(vec![ast::VariableDefinition::new("builddir", "foo").into()])
+                    ast::Module::new(vec![
+                        ast_rule("foo", "$out").into(),
+                        ast::Build::new(
+                            vec!["bar".into()],
+                            vec!["baz".into()],
+                            "foo",
+                            vec![],
+                            vec![],
+                            vec![],
+                            vec![]
+                        )
+                        .into(),
+                    ])
                 )]
                 .into_iter()
                 .collect(),
@@ -770,28 +1287,34 @@ mod tests {
                 &ROOT_MODULE_PATH
             )
             .unwrap(),
-            Configuration::new(
-                Default::default(),
-                Default::default(),
-                Default::default(),
-                Some("foo".into())
+            create_simple_configuration(
+                [("baz".into(), build.clone()), ("bar".into(), build)]
+                    .into_iter()
+                    .collect(),
+                ["baz".into(), "bar".into()].into_iter().collect()
             )
         );
     }
 
     #[test]
-    fn compile_dynamic_module_variable() {
+    fn compile_order_only_inputs() {
         assert_eq!(
             compile(
                 &[(
                     ROOT_MODULE_PATH.clone(),
-                    ast::Module::new(vec![ast_explicit_build(
-                        vec!["foo".into()],
-                        "phony",
-                        vec![],
-                        vec![ast::VariableDefinition::new("dyndep", "bar")]
-                    )
-                    .into()])
+                    ast::Module::new(vec![
+                        ast_rule("foo", "$in").into(),
+                        ast::Build::new(
+                            vec!["bar".into()],
+                            vec![],
+                            "foo",
+                            vec![],
+                            vec![],
+                            vec!["baz".into()],
+                            vec![]
+                        )
+                        .into(),
+                    ])
                 )]
                 .into_iter()
                 .collect(),
@@ -801,88 +1324,557 @@ mod tests {
             .unwrap(),
             create_simple_configuration(
                 [(
-                    "foo".into(),
+                    "bar".into(),
                     Build::new(
-                        vec!["foo".into()],
-                        vec![],
-                        None,
+                        vec!["bar".into()],
                         vec![],
+                        Some(Rule::new("", None).with_name("foo")),
                         vec![],
-                        Some("bar".into())
+                        vec!["baz".into()],
+                        None
                     )
                     .into()
                 )]
                 .into_iter()
                 .collect(),
-                ["foo".into()].into_iter().collect()
+                ["bar".into()].into_iter().collect()
             )
         );
     }
 
-    mod submodule {
-        use super::*;
-        use pretty_assertions::assert_eq;
-
-        #[test]
-        fn reference_variable_in_parent_module() {
-            const SUBMODULE_PATH: &str = "foo.ninja";
-
-            assert_eq!(
-                compile(
-                    &[
-                        (
-                            ROOT_MODULE_PATH.clone(),
-                            ast::Module::new(vec![
-                                ast::VariableDefinition::new("x", "42").into(),
-                                ast::Submodule::new(SUBMODULE_PATH).into(),
-                            ])
-                        ),
-                        (
-                            SUBMODULE_PATH.into(),
-                            ast::Module::new(vec![
-                                ast::Rule::new("foo", "$x", None).into(),
-                                ast_explicit_build(vec!["bar".into()], "foo", vec![], vec![])
-                                    .into()
-                            ])
+    #[test]
+    fn generate_build_ids() {
+        assert_eq!(
+            compile(
+                &[(
+                    ROOT_MODULE_PATH.clone(),
+                    ast::Module::new(vec![
+                        ast_rule("foo", "").into(),
+                        ast_explicit_build(vec!["bar".into()], "foo", vec![], vec![]).into(),
+                        ast_explicit_build(vec!["baz".into()], "foo", vec![], vec![]).into()
+                    ])
+                )]
+                .into_iter()
+                .collect(),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATH
+            )
+            .unwrap(),
+            create_simple_configuration(
+                [
+                    (
+                        "bar".into(),
+                        ir_explicit_build(
+                            vec!["bar".into()],
+                            Rule::new("", None).with_name("foo"),
+                            vec![]
                         )
-                    ]
-                    .into_iter()
-                    .collect(),
-                    &[(
-                        ROOT_MODULE_PATH.clone(),
-                        [(SUBMODULE_PATH.into(), PathBuf::from(SUBMODULE_PATH))]
-                            .into_iter()
-                            .collect()
-                    )]
+                        .into()
+                    ),
+                    (
+                        "baz".into(),
+                        ir_explicit_build(
+                            vec!["baz".into()],
+                            Rule::new("", None).with_name("foo"),
+                            vec![]
+                        )
+                        .into()
+                    )
+                ]
+                .into_iter()
+                .collect(),
+                ["bar".into(), "baz".into()].into_iter().collect()
+            )
+        );
+    }
+
+    #[test]
+    fn interpolate_build_local_variable() {
+        assert_eq!(
+            compile(
+                &[(
+                    ROOT_MODULE_PATH.clone(),
+                    ast::Module::new(vec![
+                        ast_rule("foo", "$x").into(),
+                        ast_explicit_build(
+                            vec!["bar".into()],
+                            "foo",
+                            vec![],
+                            vec![ast::VariableDefinition::new("x", "42")]
+                        )
+                        .into(),
+                    ])
+                )]
+                .into_iter()
+                .collect(),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATH
+            )
+            .unwrap(),
+            create_simple_configuration(
+                [(
+                    "bar".into(),
+                    ir_explicit_build(
+                        vec!["bar".into()],
+                        Rule::new("42", None).with_name("foo"),
+                        vec![]
+                    )
+                    .into()
+                )]
+                .into_iter()
+                .collect(),
+                ["bar".into()].into_iter().collect()
+            )
+        );
+    }
+
+    #[test]
+    fn compile_source_map() {
+        assert_eq!(
+            compile(
+                &[(
+                    ROOT_MODULE_PATH.clone(),
+                    ast::Module::new(vec![
+                        ast_rule("foo", "foo").into(),
+                        ast_explicit_build(
+                            vec!["bar".into()],
+                            "foo",
+                            vec![],
+                            vec![ast::VariableDefinition::new(
+                                SOURCE_VARIABLE_NAME,
+                                "oh-my-src"
+                            )]
+                        )
+                        .into(),
+                    ])
+                )]
+                .into_iter()
+                .collect(),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATH
+            )
+            .unwrap(),
+            Configuration::new(
+                [(
+                    "bar".into(),
+                    ir_explicit_build(
+                        vec!["bar".into()],
+                        Rule::new("foo", None).with_name("foo"),
+                        vec![]
+                    )
+                    .into()
+                )]
+                .into_iter()
+                .collect(),
+                ["bar".into()].into_iter().collect(),
+                [("bar".into(), "oh-my-src".into())].into_iter().collect(),
+                Default::default(),
+                None,
+            )
+        );
+    }
+
+    #[test]
+    fn compile_phony_rule() {
+        assert_eq!(
+            compile(
+                &[(
+                    ROOT_MODULE_PATH.clone(),
+                    ast::Module::new(vec![ast_explicit_build(
+                        vec!["foo".into()],
+                        "phony",
+                        vec!["bar".into()],
+                        vec![]
+                    )
+                    .into(),])
+                )]
+                .into_iter()
+                .collect(),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATH
+            )
+            .unwrap(),
+            create_simple_configuration(
+                [(
+                    "foo".into(),
+                    Build::new(
+                        vec!["foo".into()],
+                        vec![],
+                        None,
+                        vec!["bar".into()],
+                        vec![],
+                        None
+                    )
+                    .into()
+                )]
+                .into_iter()
+                .collect(),
+                ["foo".into()].into_iter().collect()
+            )
+        );
+    }
+
+    #[test]
+    fn compile_build_directory() {
+        assert_eq!(
+            compile(
+                &[(
+                    ROOT_MODULE_PATH.clone(),
+                    ast::Module::new(vec![ast::VariableDefinition::new("builddir", "foo").into()])
+                )]
+                .into_iter()
+                .collect(),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATH
+            )
+            .unwrap(),
+            Configuration::new(
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Some("foo".into())
+            )
+        );
+    }
+
+    #[test]
+    fn compile_dynamic_module_variable() {
+        assert_eq!(
+            compile(
+                &[(
+                    ROOT_MODULE_PATH.clone(),
+                    ast::Module::new(vec![ast_explicit_build(
+                        vec!["foo".into()],
+                        "phony",
+                        vec![],
+                        vec![ast::VariableDefinition::new("dyndep", "bar")]
+                    )
+                    .into()])
+                )]
+                .into_iter()
+                .collect(),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATH
+            )
+            .unwrap(),
+            create_simple_configuration(
+                [(
+                    "foo".into(),
+                    Build::new(
+                        vec!["foo".into()],
+                        vec![],
+                        None,
+                        vec![],
+                        vec![],
+                        Some("bar".into())
+                    )
+                    .into()
+                )]
+                .into_iter()
+                .collect(),
+                ["foo".into()].into_iter().collect()
+            )
+        );
+    }
+
+    #[test]
+    fn interpolate_variable_in_description() {
+        assert_eq!(
+            compile(
+                &[(
+                    ROOT_MODULE_PATH.clone(),
+                    ast::Module::new(vec![
+                        ast::Rule::new(
+                            "foo",
+                            vec![
+                                ast::VariableDefinition::new("command", "bar"),
+                                ast::VariableDefinition::new("description", "$out")
+                            ]
+                        )
+                        .into(),
+                        ast_explicit_build(vec!["bar".into()], "foo", vec![], vec![]).into(),
+                    ])
+                )]
+                .into_iter()
+                .collect(),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATH
+            )
+            .unwrap(),
+            create_simple_configuration(
+                [(
+                    "bar".into(),
+                    ir_explicit_build(
+                        vec!["bar".into()],
+                        Rule::new("bar", Some("bar".into())).with_name("foo"),
+                        vec![]
+                    )
+                    .into()
+                )]
+                .into_iter()
+                .collect(),
+                ["bar".into()].into_iter().collect()
+            )
+        );
+    }
+
+    mod pool {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn compile_pool() {
+            assert_eq!(
+                compile(
+                    &[(
+                        ROOT_MODULE_PATH.clone(),
+                        ast::Module::new(vec![ast::Pool::new("foo", "42").into()])
+                    )]
                     .into_iter()
                     .collect(),
+                    &DEFAULT_DEPENDENCIES,
                     &ROOT_MODULE_PATH
                 )
                 .unwrap(),
-                create_simple_configuration(
-                    [(
-                        "bar".into(),
-                        ir_explicit_build(vec!["bar".into()], Rule::new("42", None), vec![]).into()
-                    )]
-                    .into_iter()
-                    .collect(),
-                    ["bar".into()].into_iter().collect()
+                Configuration::new(
+                    Default::default(),
+                    Default::default(),
+                    Default::default(),
+                    [("foo".into(), 42)].into_iter().collect(),
+                    None
                 )
             );
         }
 
         #[test]
-        fn reference_rule_in_parent_module() {
-            const SUBMODULE_PATH: &str = "foo.ninja";
-
+        fn compile_pool_with_variable() {
             assert_eq!(
                 compile(
-                    &[
-                        (
+                    &[(
+                        ROOT_MODULE_PATH.clone(),
+                        ast::Module::new(vec![
+                            ast::VariableDefinition::new("x", "42").into(),
+                            ast::Pool::new("foo", "$x").into()
+                        ])
+                    )]
+                    .into_iter()
+                    .collect(),
+                    &DEFAULT_DEPENDENCIES,
+                    &ROOT_MODULE_PATH
+                )
+                .unwrap()
+                .pools(),
+                &[("foo".into(), 42)].into_iter().collect()
+            );
+        }
+
+        #[test]
+        fn fail_to_compile_pool_with_invalid_depth() {
+            assert_eq!(
+                compile(
+                    &[(
+                        ROOT_MODULE_PATH.clone(),
+                        ast::Module::new(vec![ast::Pool::new("foo", "bar").into()])
+                    )]
+                    .into_iter()
+                    .collect(),
+                    &DEFAULT_DEPENDENCIES,
+                    &ROOT_MODULE_PATH
+                ),
+                Err(CompileError::InvalidPoolDepth("foo".into()))
+            );
+        }
+
+        #[test]
+        fn compile_rule_with_pool() {
+            assert_eq!(
+                compile(
+                    &[(
+                        ROOT_MODULE_PATH.clone(),
+                        ast::Module::new(vec![
+                            ast::Pool::new("foo", "1").into(),
+                            ast::Rule::new(
+                                "bar",
+                                vec![
+                                    ast::VariableDefinition::new("command", "baz"),
+                                    ast::VariableDefinition::new("pool", "foo")
+                                ]
+                            )
+                            .into(),
+                            ast_explicit_build(vec!["baz".into()], "bar", vec![], vec![]).into(),
+                        ])
+                    )]
+                    .into_iter()
+                    .collect(),
+                    &DEFAULT_DEPENDENCIES,
+                    &ROOT_MODULE_PATH
+                )
+                .unwrap()
+                .outputs()["baz"]
+                    .rule()
+                    .unwrap()
+                    .pool(),
+                Some(&"foo".into())
+            );
+        }
+
+        #[test]
+        fn compile_build_with_pool() {
+            assert_eq!(
+                compile(
+                    &[(
+                        ROOT_MODULE_PATH.clone(),
+                        ast::Module::new(vec![
+                            ast::Pool::new("foo", "1").into(),
+                            ast_rule("bar", "baz").into(),
+                            ast_explicit_build(
+                                vec!["baz".into()],
+                                "bar",
+                                vec![],
+                                vec![ast::VariableDefinition::new("pool", "foo")]
+                            )
+                            .into(),
+                        ])
+                    )]
+                    .into_iter()
+                    .collect(),
+                    &DEFAULT_DEPENDENCIES,
+                    &ROOT_MODULE_PATH
+                )
+                .unwrap()
+                .outputs()["baz"]
+                    .rule()
+                    .unwrap()
+                    .pool(),
+                Some(&"foo".into())
+            );
+        }
+
+        #[test]
+        fn compile_build_with_console_pool() {
+            assert!(compile(
+                &[(
+                    ROOT_MODULE_PATH.clone(),
+                    ast::Module::new(vec![
+                        ast_rule("bar", "baz").into(),
+                        ast_explicit_build(
+                            vec!["baz".into()],
+                            "bar",
+                            vec![],
+                            vec![ast::VariableDefinition::new("pool", "console")]
+                        )
+                        .into(),
+                    ])
+                )]
+                .into_iter()
+                .collect(),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATH
+            )
+            .unwrap()
+            .outputs()["baz"]
+                .rule()
+                .unwrap()
+                .is_console());
+        }
+
+        #[test]
+        fn fail_to_compile_build_with_unknown_pool() {
+            assert_eq!(
+                compile(
+                    &[(
+                        ROOT_MODULE_PATH.clone(),
+                        ast::Module::new(vec![
+                            ast_rule("bar", "baz").into(),
+                            ast_explicit_build(
+                                vec!["baz".into()],
+                                "bar",
+                                vec![],
+                                vec![ast::VariableDefinition::new("pool", "foo")]
+                            )
+                            .into(),
+                        ])
+                    )]
+                    .into_iter()
+                    .collect(),
+                    &DEFAULT_DEPENDENCIES,
+                    &ROOT_MODULE_PATH
+                ),
+                Err(CompileError::PoolNotFound("foo".into()))
+            );
+        }
+    }
+
+    mod submodule {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn reference_variable_in_parent_module() {
+            const SUBMODULE_PATH: &str = "foo.ninja";
+
+            assert_eq!(
+                compile(
+                    &[
+                        (
+                            ROOT_MODULE_PATH.clone(),
+                            ast::Module::new(vec![
+                                ast::VariableDefinition::new("x", "42").into(),
+                                ast::Submodule::new(SUBMODULE_PATH).into(),
+                            ])
+                        ),
+                        (
+                            SUBMODULE_PATH.into(),
+                            ast::Module::new(vec![
+                                ast_rule("foo", "$x").into(),
+                                ast_explicit_build(vec!["bar".into()], "foo", vec![], vec![])
+                                    .into()
+                            ])
+                        )
+                    ]
+                    .into_iter()
+                    .collect(),
+                    &[(
+                        ROOT_MODULE_PATH.clone(),
+                        [(SUBMODULE_PATH.into(), PathBuf::from(SUBMODULE_PATH))]
+                            .into_iter()
+                            .collect()
+                    )]
+                    .into_iter()
+                    .collect(),
+                    &ROOT_MODULE_PATH
+                )
+                .unwrap(),
+                create_simple_configuration(
+                    [(
+                        "bar".into(),
+                        ir_explicit_build(
+                            vec!["bar".into()],
+                            Rule::new("42", None).with_name("foo"),
+                            vec![]
+                        )
+                        .into()
+                    )]
+                    .into_iter()
+                    .collect(),
+                    ["bar".into()].into_iter().collect()
+                )
+            );
+        }
+
+        #[test]
+        fn reference_rule_in_parent_module() {
+            const SUBMODULE_PATH: &str = "foo.ninja";
+
+            assert_eq!(
+                compile(
+                    &[
+                        (
                             ROOT_MODULE_PATH.clone(),
                             ast::Module::new(vec![
                                 ast::VariableDefinition::new("x", "42").into(),
-                                ast::Rule::new("foo", "$x", None).into(),
+                                ast_rule("foo", "$x").into(),
                                 ast::Submodule::new(SUBMODULE_PATH).into(),
                             ])
                         ),
@@ -913,59 +1905,1163 @@ mod tests {
                 create_simple_configuration(
                     [(
                         "bar".into(),
-                        ir_explicit_build(vec!["bar".into()], Rule::new("42", None), vec![]).into()
+                        ir_explicit_build(
+                            vec!["bar".into()],
+                            Rule::new("42", None).with_name("foo"),
+                            vec![]
+                        )
+                        .into()
+                    )]
+                    .into_iter()
+                    .collect(),
+                    ["bar".into()].into_iter().collect()
+                )
+            );
+        }
+
+        #[test]
+        fn do_not_overwrite_variable_in_parent_module() {
+            const SUBMODULE_PATH: &str = "foo.ninja";
+
+            assert_eq!(
+                compile(
+                    &[
+                        (
+                            ROOT_MODULE_PATH.clone(),
+                            ast::Module::new(vec![
+                                ast::VariableDefinition::new("x", "42").into(),
+                                ast_rule("foo", "$x").into(),
+                                ast::Submodule::new(SUBMODULE_PATH).into(),
+                                ast_explicit_build(vec!["bar".into()], "foo", vec![], vec![])
+                                    .into(),
+                            ])
+                        ),
+                        (
+                            SUBMODULE_PATH.into(),
+                            ast::Module::new(vec![ast::VariableDefinition::new("x", "13").into(),])
+                        )
+                    ]
+                    .into_iter()
+                    .collect(),
+                    &[(
+                        ROOT_MODULE_PATH.clone(),
+                        [(SUBMODULE_PATH.into(), PathBuf::from(SUBMODULE_PATH))]
+                            .into_iter()
+                            .collect()
+                    )]
+                    .into_iter()
+                    .collect(),
+                    &ROOT_MODULE_PATH
+                )
+                .unwrap(),
+                create_simple_configuration(
+                    [(
+                        "bar".into(),
+                        ir_explicit_build(
+                            vec!["bar".into()],
+                            Rule::new("42", None).with_name("foo"),
+                            vec![]
+                        )
+                        .into()
+                    )]
+                    .into_iter()
+                    .collect(),
+                    ["bar".into()].into_iter().collect()
+                )
+            );
+        }
+    }
+
+    mod include {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn overwrite_variable_in_parent_module() {
+            const INCLUDED_MODULE_PATH: &str = "foo.ninja";
+
+            assert_eq!(
+                compile(
+                    &[
+                        (
+                            ROOT_MODULE_PATH.clone(),
+                            ast::Module::new(vec![
+                                ast::VariableDefinition::new("x", "42").into(),
+                                ast_rule("foo", "$x").into(),
+                                ast::Include::new(INCLUDED_MODULE_PATH).into(),
+                                ast_explicit_build(vec!["bar".into()], "foo", vec![], vec![])
+                                    .into(),
+                            ])
+                        ),
+                        (
+                            INCLUDED_MODULE_PATH.into(),
+                            ast::Module::new(vec![ast::VariableDefinition::new("x", "13").into()])
+                        )
+                    ]
+                    .into_iter()
+                    .collect(),
+                    &[(
+                        ROOT_MODULE_PATH.clone(),
+                        [(
+                            INCLUDED_MODULE_PATH.into(),
+                            PathBuf::from(INCLUDED_MODULE_PATH)
+                        )]
+                        .into_iter()
+                        .collect()
+                    )]
+                    .into_iter()
+                    .collect(),
+                    &ROOT_MODULE_PATH
+                )
+                .unwrap(),
+                create_simple_configuration(
+                    [(
+                        "bar".into(),
+                        ir_explicit_build(
+                            vec!["bar".into()],
+                            Rule::new("13", None).with_name("foo"),
+                            vec![]
+                        )
+                        .into()
+                    )]
+                    .into_iter()
+                    .collect(),
+                    ["bar".into()].into_iter().collect()
+                )
+            );
+        }
+
+        fn compile_include_chain(
+            length: usize,
+        ) -> Result<(Configuration, Vec<CompileWarning>), CompileError> {
+            let path = |index: usize| PathBuf::from(format!("{index}.ninja"));
+            let modules = (0..length)
+                .map(|index| {
+                    (
+                        path(index),
+                        ast::Module::new(if index + 1 < length {
+                            vec![ast::Include::new(path(index + 1).display().to_string()).into()]
+                        } else {
+                            vec![
+                                ast_rule("foo", "").into(),
+                                ast_explicit_build(vec!["bar".into()], "foo", vec![], vec![])
+                                    .into(),
+                            ]
+                        }),
+                    )
+                })
+                .collect();
+            let dependencies = (0..length)
+                .map(|index| {
+                    (
+                        path(index),
+                        (index + 1 < length)
+                            .then(|| (path(index + 1).display().to_string(), path(index + 1)))
+                            .into_iter()
+                            .collect(),
+                    )
+                })
+                .collect();
+
+            crate::compile::compile(&modules, &dependencies, &[path(0)], &Default::default())
+        }
+
+        #[test]
+        fn compile_long_include_chain() {
+            assert!(compile_include_chain(200).is_ok());
+        }
+
+        #[test]
+        fn fail_to_compile_too_long_include_chain() {
+            assert_eq!(
+                compile_include_chain(10000),
+                Err(CompileError::ModuleDepthExceeded("257.ninja".into()))
+            );
+        }
+    }
+
+    #[test]
+    fn compile_depfile() {
+        assert_eq!(
+            compile(
+                &[(
+                    ROOT_MODULE_PATH.clone(),
+                    ast::Module::new(vec![
+                        ast::Rule::new(
+                            "foo",
+                            vec![
+                                ast::VariableDefinition::new("command", "bar"),
+                                ast::VariableDefinition::new("depfile", "$out.d")
+                            ]
+                        )
+                        .into(),
+                        ast_explicit_build(vec!["baz".into()], "foo", vec![], vec![]).into(),
+                    ])
+                )]
+                .into_iter()
+                .collect(),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATH
+            )
+            .unwrap()
+            .outputs()["baz"]
+                .rule()
+                .unwrap()
+                .depfile(),
+            Some("baz.d")
+        );
+    }
+
+    #[test]
+    fn interpolate_rule_variable_in_rule_variable() {
+        assert_eq!(
+            compile(
+                &[(
+                    ROOT_MODULE_PATH.clone(),
+                    ast::Module::new(vec![
+                        ast::VariableDefinition::new("y", "module").into(),
+                        ast::Rule::new(
+                            "foo",
+                            vec![
+                                ast::VariableDefinition::new("command", "$x $y $command"),
+                                ast::VariableDefinition::new("x", "rule"),
+                                ast::VariableDefinition::new("y", "$y"),
+                            ],
+                        )
+                        .into(),
+                        ast_explicit_build(
+                            vec!["bar".into()],
+                            "foo",
+                            vec![],
+                            vec![ast::VariableDefinition::new("x", "build")]
+                        )
+                        .into(),
+                    ]),
+                )]
+                .into_iter()
+                .collect(),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATH,
+            )
+            .unwrap()
+            .outputs()["bar"]
+                .rule()
+                .unwrap()
+                .command(),
+            "build module "
+        );
+    }
+
+    #[test]
+    fn compile_response_file() {
+        assert_eq!(
+            compile(
+                &[(
+                    ROOT_MODULE_PATH.clone(),
+                    ast::Module::new(vec![
+                        ast::Rule::new(
+                            "foo",
+                            vec![
+                                ast::VariableDefinition::new("command", "bar @$rspfile"),
+                                ast::VariableDefinition::new("rspfile", "$out.rsp"),
+                                ast::VariableDefinition::new("rspfile_content", "$in"),
+                            ],
+                        )
+                        .into(),
+                        ast_explicit_build(vec!["baz".into()], "foo", vec!["qux".into()], vec![])
+                            .into(),
+                    ]),
+                )]
+                .into_iter()
+                .collect(),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATH,
+            )
+            .unwrap()
+            .outputs()["baz"]
+                .rule(),
+            Some(
+                &Rule::new("bar @baz.rsp", None)
+                    .with_name("foo")
+                    .with_response_file(ResponseFile::new("baz.rsp", "qux"))
+            )
+        );
+    }
+
+    #[test]
+    fn compile_directory() {
+        assert_eq!(
+            compile(
+                &[(
+                    ROOT_MODULE_PATH.clone(),
+                    ast::Module::new(vec![
+                        ast::Rule::new(
+                            "foo",
+                            vec![
+                                ast::VariableDefinition::new("command", "bar"),
+                                ast::VariableDefinition::new("chdir", "$out.d"),
+                            ],
+                        )
+                        .into(),
+                        ast_explicit_build(vec!["baz".into()], "foo", vec![], vec![]).into(),
+                    ]),
+                )]
+                .into_iter()
+                .collect(),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATH,
+            )
+            .unwrap()
+            .outputs()["baz"]
+                .rule(),
+            Some(
+                &Rule::new("bar", None)
+                    .with_name("foo")
+                    .with_directory("baz.d")
+            )
+        );
+    }
+
+    #[test]
+    fn compile_environment() {
+        assert_eq!(
+            compile(
+                &[(
+                    ROOT_MODULE_PATH.clone(),
+                    ast::Module::new(vec![
+                        ast::Rule::new(
+                            "foo",
+                            vec![
+                                ast::VariableDefinition::new("command", "bar"),
+                                ast::VariableDefinition::new("environment", "FOO=$out BAR="),
+                                ast::VariableDefinition::new("env_clear", "1"),
+                            ],
+                        )
+                        .into(),
+                        ast_explicit_build(vec!["baz".into()], "foo", vec![], vec![]).into(),
+                    ]),
+                )]
+                .into_iter()
+                .collect(),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATH,
+            )
+            .unwrap()
+            .outputs()["baz"]
+                .rule(),
+            Some(
+                &Rule::new("bar", None)
+                    .with_name("foo")
+                    .with_environment(Environment::new(
+                        vec![("FOO".into(), "baz".into()), ("BAR".into(), "".into())],
+                        true
+                    ))
+            )
+        );
+    }
+
+    #[test]
+    fn fail_to_compile_invalid_environment_variable() {
+        assert_eq!(
+            compile(
+                &[(
+                    ROOT_MODULE_PATH.clone(),
+                    ast::Module::new(vec![
+                        ast::Rule::new(
+                            "foo",
+                            vec![
+                                ast::VariableDefinition::new("command", "bar"),
+                                ast::VariableDefinition::new("environment", "FOO"),
+                            ],
+                        )
+                        .into(),
+                        ast_explicit_build(vec!["baz".into()], "foo", vec![], vec![]).into(),
+                    ]),
+                )]
+                .into_iter()
+                .collect(),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATH,
+            ),
+            Err(CompileError::InvalidEnvironmentVariable("FOO".into()))
+        );
+    }
+
+    #[test]
+    fn compile_generator() {
+        assert!(compile(
+            &[(
+                ROOT_MODULE_PATH.clone(),
+                ast::Module::new(vec![
+                    ast::Rule::new(
+                        "foo",
+                        vec![
+                            ast::VariableDefinition::new("command", "bar"),
+                            ast::VariableDefinition::new("generator", "1"),
+                        ],
+                    )
+                    .into(),
+                    ast_explicit_build(vec!["baz".into()], "foo", vec![], vec![]).into(),
+                ]),
+            )]
+            .into_iter()
+            .collect(),
+            &DEFAULT_DEPENDENCIES,
+            &ROOT_MODULE_PATH,
+        )
+        .unwrap()
+        .outputs()["baz"]
+            .rule()
+            .unwrap()
+            .generator());
+    }
+
+    #[test]
+    fn compile_restat() {
+        let compile_restat = |value| {
+            compile(
+                &[(
+                    ROOT_MODULE_PATH.clone(),
+                    ast::Module::new(vec![
+                        ast::Rule::new(
+                            "foo",
+                            vec![
+                                ast::VariableDefinition::new("command", "bar"),
+                                ast::VariableDefinition::new("restat", value),
+                            ],
+                        )
+                        .into(),
+                        ast_explicit_build(vec!["baz".into()], "foo", vec![], vec![]).into(),
+                    ]),
+                )]
+                .into_iter()
+                .collect(),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATH,
+            )
+            .unwrap()
+            .outputs()["baz"]
+                .rule()
+                .unwrap()
+                .restat()
+        };
+
+        assert!(compile_restat("1"));
+        assert!(!compile_restat(""));
+    }
+
+    mod default_output {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        const CHILD_MODULE_PATH: &str = "foo.ninja";
+
+        fn compile_default_outputs(
+            statements: Vec<ast::Statement>,
+            child_statement: ast::Statement,
+        ) -> HashSet<Arc<str>> {
+            compile(
+                &[
+                    (
+                        ROOT_MODULE_PATH.clone(),
+                        ast::Module::new(
+                            [
+                                ast_rule("foo", "").into(),
+                                ast_explicit_build(vec!["foo".into()], "foo", vec![], vec![])
+                                    .into(),
+                                ast_explicit_build(vec!["bar".into()], "foo", vec![], vec![])
+                                    .into(),
+                                ast_explicit_build(vec!["baz".into()], "foo", vec![], vec![])
+                                    .into(),
+                                child_statement,
+                            ]
+                            .into_iter()
+                            .chain(statements)
+                            .collect(),
+                        ),
+                    ),
+                    (
+                        CHILD_MODULE_PATH.into(),
+                        ast::Module::new(vec![ast::DefaultOutput::new(vec!["bar".into()]).into()]),
+                    ),
+                ]
+                .into_iter()
+                .collect(),
+                &[(
+                    ROOT_MODULE_PATH.clone(),
+                    [(CHILD_MODULE_PATH.into(), PathBuf::from(CHILD_MODULE_PATH))]
+                        .into_iter()
+                        .collect(),
+                )]
+                .into_iter()
+                .collect(),
+                &ROOT_MODULE_PATH,
+            )
+            .unwrap()
+            .default_outputs()
+            .clone()
+        }
+
+        #[test]
+        fn compile_default_output_in_included_module() {
+            assert_eq!(
+                compile_default_outputs(vec![], ast::Include::new(CHILD_MODULE_PATH).into()),
+                ["bar".into()].into_iter().collect()
+            );
+        }
+
+        #[test]
+        fn compile_default_output_in_child_module() {
+            assert_eq!(
+                compile_default_outputs(vec![], ast::Submodule::new(CHILD_MODULE_PATH).into()),
+                ["bar".into()].into_iter().collect()
+            );
+        }
+
+        #[test]
+        fn accumulate_default_outputs_across_modules() {
+            assert_eq!(
+                compile_default_outputs(
+                    vec![ast::DefaultOutput::new(vec!["foo".into()]).into()],
+                    ast::Submodule::new(CHILD_MODULE_PATH).into()
+                ),
+                ["foo".into(), "bar".into()].into_iter().collect()
+            );
+        }
+    }
+
+    mod dependency_format {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn compile_dependency_format(
+            rule_variables: Vec<ast::VariableDefinition>,
+        ) -> Result<Option<DependencyFormat>, CompileError> {
+            Ok(compile(
+                &[(
+                    ROOT_MODULE_PATH.clone(),
+                    ast::Module::new(vec![
+                        ast::Rule::new(
+                            "foo",
+                            [ast::VariableDefinition::new("command", "bar")]
+                                .into_iter()
+                                .chain(rule_variables)
+                                .collect(),
+                        )
+                        .into(),
+                        ast_explicit_build(vec!["baz".into()], "foo", vec![], vec![]).into(),
+                    ]),
+                )]
+                .into_iter()
+                .collect(),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATH,
+            )?
+            .outputs()["baz"]
+                .rule()
+                .unwrap()
+                .dependency_format()
+                .cloned())
+        }
+
+        #[test]
+        fn compile_no_format() {
+            assert_eq!(compile_dependency_format(vec![]), Ok(None));
+        }
+
+        #[test]
+        fn compile_gcc_format() {
+            assert_eq!(
+                compile_dependency_format(vec![ast::VariableDefinition::new("deps", "gcc")]),
+                Ok(Some(DependencyFormat::Gcc))
+            );
+        }
+
+        #[test]
+        fn compile_msvc_format() {
+            assert_eq!(
+                compile_dependency_format(vec![ast::VariableDefinition::new("deps", "msvc")]),
+                Ok(Some(DependencyFormat::Msvc(
+                    DEFAULT_MSVC_DEPENDENCY_PREFIX.into()
+                )))
+            );
+        }
+
+        #[test]
+        fn compile_msvc_format_with_prefix() {
+            assert_eq!(
+                compile_dependency_format(vec![
+                    ast::VariableDefinition::new("deps", "msvc"),
+                    ast::VariableDefinition::new("msvc_deps_prefix", "foo:"),
+                ]),
+                Ok(Some(DependencyFormat::Msvc("foo:".into())))
+            );
+        }
+
+        #[test]
+        fn fail_to_compile_invalid_format() {
+            assert_eq!(
+                compile_dependency_format(vec![ast::VariableDefinition::new("deps", "foo")]),
+                Err(CompileError::InvalidDependencyFormat("foo".into()))
+            );
+        }
+    }
+
+    mod duplicate_output {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn create_modules() -> HashMap<PathBuf, ast::Module> {
+            [(
+                ROOT_MODULE_PATH.clone(),
+                ast::Module::new(vec![
+                    ast_rule("foo", "baz").into(),
+                    ast_explicit_build(vec!["bar".into()], "foo", vec![], vec![]).into(),
+                    ast_explicit_build(vec!["bar".into()], "phony", vec![], vec![]).into(),
+                ]),
+            )]
+            .into_iter()
+            .collect()
+        }
+
+        #[test]
+        fn fail_to_compile_duplicate_output() {
+            assert_eq!(
+                compile(&create_modules(), &DEFAULT_DEPENDENCIES, &ROOT_MODULE_PATH),
+                Err(CompileError::DuplicateOutput("bar".into()))
+            );
+        }
+
+        #[test]
+        fn compile_duplicate_output_with_warning() {
+            let (configuration, warnings) = crate::compile::compile(
+                &create_modules(),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATHS,
+                &Options {
+                    warnings: WarningConfiguration {
+                        duplicate_build: WarningLevel::Warn,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+            assert_eq!(
+                configuration,
+                create_simple_configuration(
+                    [(
+                        "bar".into(),
+                        ir_explicit_build(
+                            vec!["bar".into()],
+                            Rule::new("baz", None).with_name("foo"),
+                            vec![]
+                        )
+                        .into()
                     )]
                     .into_iter()
                     .collect(),
                     ["bar".into()].into_iter().collect()
                 )
             );
+            assert_eq!(
+                warnings,
+                vec![CompileWarning::DuplicateOutput("bar".into())]
+            );
         }
 
         #[test]
-        fn do_not_overwrite_variable_in_parent_module() {
-            const SUBMODULE_PATH: &str = "foo.ninja";
+        fn compile_duplicate_output_without_warning() {
+            let (_, warnings) = crate::compile::compile(
+                &create_modules(),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATHS,
+                &Options {
+                    warnings: WarningConfiguration {
+                        duplicate_build: WarningLevel::Off,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+            assert_eq!(warnings, vec![]);
+        }
+
+        #[test]
+        fn compile_duplicate_outputs_in_build() {
+            assert!(compile(
+                &[(
+                    ROOT_MODULE_PATH.clone(),
+                    ast::Module::new(vec![
+                        ast_rule("foo", "").into(),
+                        ast_explicit_build(vec!["bar".into(), "bar".into()], "foo", vec![], vec![])
+                            .into(),
+                    ]),
+                )]
+                .into_iter()
+                .collect(),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATH
+            )
+            .is_ok());
+        }
+    }
+
+    mod path_normalization {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn compile_build(build: ast::Build) -> Configuration {
+            compile(
+                &[(
+                    ROOT_MODULE_PATH.clone(),
+                    ast::Module::new(vec![ast_rule("cc", "").into(), build.into()]),
+                )]
+                .into_iter()
+                .collect(),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATH,
+            )
+            .unwrap()
+        }
+
+        #[test]
+        fn normalize_outputs_and_inputs() {
+            let configuration = compile_build(ast_explicit_build(
+                vec!["./foo".into()],
+                "cc",
+                vec!["bar/../baz".into()],
+                vec![],
+            ));
+
+            assert_eq!(
+                configuration.outputs()["foo"].inputs(),
+                &["baz".into()] as &[Arc<str>]
+            );
+        }
 
+        #[test]
+        fn deduplicate_inputs() {
+            let configuration = compile_build(ast_explicit_build(
+                vec!["foo".into()],
+                "cc",
+                vec!["bar".into(), "./bar".into(), "baz//qux".into()],
+                vec![],
+            ));
+
+            assert_eq!(
+                configuration.outputs()["foo"].inputs(),
+                &["bar".into(), "baz/qux".into()] as &[Arc<str>]
+            );
+        }
+
+        #[test]
+        fn drop_implicit_inputs_duplicate_with_explicit_ones() {
+            let configuration = compile_build(ast::Build::new(
+                vec!["foo".into()],
+                vec![],
+                "cc",
+                vec!["bar".into()],
+                vec!["./bar".into(), "baz".into()],
+                vec![],
+                vec![],
+            ));
+            let build = &configuration.outputs()["foo"];
+
+            assert_eq!(build.inputs(), &["bar".into(), "baz".into()] as &[Arc<str>]);
+            assert_eq!(build.explicit_inputs(), &["bar".into()] as &[Arc<str>]);
+            assert_eq!(build.implicit_inputs(), &["baz".into()] as &[Arc<str>]);
+        }
+
+        #[test]
+        fn fail_to_compile_equivalent_duplicate_outputs() {
             assert_eq!(
                 compile(
-                    &[
-                        (
-                            ROOT_MODULE_PATH.clone(),
-                            ast::Module::new(vec![
-                                ast::VariableDefinition::new("x", "42").into(),
-                                ast::Rule::new("foo", "$x", None).into(),
-                                ast::Submodule::new(SUBMODULE_PATH).into(),
-                                ast_explicit_build(vec!["bar".into()], "foo", vec![], vec![])
-                                    .into(),
-                            ])
-                        ),
-                        (
-                            SUBMODULE_PATH.into(),
-                            ast::Module::new(vec![ast::VariableDefinition::new("x", "13").into(),])
-                        )
-                    ]
-                    .into_iter()
-                    .collect(),
                     &[(
                         ROOT_MODULE_PATH.clone(),
-                        [(SUBMODULE_PATH.into(), PathBuf::from(SUBMODULE_PATH))]
-                            .into_iter()
-                            .collect()
-                    )]
-                    .into_iter()
-                    .collect(),
-                    &ROOT_MODULE_PATH
-                )
-                .unwrap(),
-                create_simple_configuration(
-                    [(
-                        "bar".into(),
-                        ir_explicit_build(vec!["bar".into()], Rule::new("42", None), vec![]).into()
+                        ast::Module::new(vec![
+                            ast_rule("cc", "").into(),
+                            ast_explicit_build(vec!["foo".into()], "cc", vec![], vec![]).into(),
+                            ast_explicit_build(vec!["./foo".into()], "cc", vec![], vec![]).into(),
+                        ]),
                     )]
                     .into_iter()
                     .collect(),
-                    ["bar".into()].into_iter().collect()
-                )
+                    &DEFAULT_DEPENDENCIES,
+                    &ROOT_MODULE_PATH,
+                ),
+                Err(CompileError::DuplicateOutput("foo".into()))
+            );
+        }
+    }
+
+    mod undefined_variable {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn create_modules(command: &str) -> HashMap<PathBuf, ast::Module> {
+            [(
+                ROOT_MODULE_PATH.clone(),
+                ast::Module::new(vec![
+                    ast::VariableDefinition::new("foo", "").into(),
+                    ast_rule("cc", command).into(),
+                    ast_explicit_build(vec!["bar".into()], "cc", vec!["baz".into()], vec![]).into(),
+                ]),
+            )]
+            .into_iter()
+            .collect()
+        }
+
+        fn compile_warnings(command: &str) -> Vec<CompileWarning> {
+            crate::compile::compile(
+                &create_modules(command),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATHS,
+                &Default::default(),
+            )
+            .unwrap()
+            .1
+        }
+
+        #[test]
+        fn warn_undefined_variable() {
+            assert_eq!(
+                compile_warnings("cc $outt $outt"),
+                vec![CompileWarning::UndefinedVariable(
+                    "outt".into(),
+                    "cc".into(),
+                    "bar".into()
+                )]
+            );
+        }
+
+        #[test]
+        fn do_not_warn_defined_variables() {
+            assert_eq!(compile_warnings("cc $foo $in $out $$bar"), vec![]);
+        }
+
+        #[test]
+        fn fail_to_compile_undefined_variable() {
+            assert_eq!(
+                crate::compile::compile(
+                    &create_modules("cc ${outt}"),
+                    &DEFAULT_DEPENDENCIES,
+                    &ROOT_MODULE_PATHS,
+                    &Options {
+                        warnings: WarningConfiguration {
+                            undefined_variable: WarningLevel::Err,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                ),
+                Err(CompileError::UndefinedVariable(
+                    "outt".into(),
+                    "cc".into(),
+                    "bar".into()
+                ))
+            );
+        }
+    }
+
+    mod empty_command {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn create_modules(command: &str) -> HashMap<PathBuf, ast::Module> {
+            [(
+                ROOT_MODULE_PATH.clone(),
+                ast::Module::new(vec![
+                    ast_rule("cc", command).into(),
+                    ast_explicit_build(vec!["bar".into()], "cc", vec!["baz".into()], vec![]).into(),
+                    ast_explicit_build(vec!["qux".into()], "phony", vec!["bar".into()], vec![])
+                        .into(),
+                ]),
+            )]
+            .into_iter()
+            .collect()
+        }
+
+        fn compile_warnings(command: &str, options: &Options) -> Vec<CompileWarning> {
+            crate::compile::compile(
+                &create_modules(command),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATHS,
+                options,
+            )
+            .unwrap()
+            .1
+        }
+
+        #[test]
+        fn warn_empty_command() {
+            assert_eq!(
+                compile_warnings(
+                    " $cmd ",
+                    &Options {
+                        warnings: WarningConfiguration {
+                            undefined_variable: WarningLevel::Off,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    }
+                ),
+                vec![CompileWarning::EmptyCommand("cc".into(), "bar".into())]
+            );
+        }
+
+        #[test]
+        fn do_not_warn_non_empty_command() {
+            assert_eq!(compile_warnings("cc $in", &Default::default()), vec![]);
+        }
+
+        #[test]
+        fn do_not_warn_disabled_empty_command() {
+            assert_eq!(
+                compile_warnings(
+                    "$cmd",
+                    &Options {
+                        warnings: WarningConfiguration {
+                            empty_command: WarningLevel::Off,
+                            undefined_variable: WarningLevel::Off,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    }
+                ),
+                vec![]
+            );
+        }
+
+        #[test]
+        fn fail_to_compile_empty_command() {
+            assert_eq!(
+                crate::compile::compile(
+                    &create_modules("$cmd"),
+                    &DEFAULT_DEPENDENCIES,
+                    &ROOT_MODULE_PATHS,
+                    &Options {
+                        warnings: WarningConfiguration {
+                            empty_command: WarningLevel::Err,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                ),
+                Err(CompileError::EmptyCommand("cc".into(), "bar".into()))
+            );
+        }
+    }
+
+    mod variable_override {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn compile_command(build_variables: Vec<ast::VariableDefinition>) -> String {
+            let (configuration, _) = crate::compile::compile(
+                &[(
+                    ROOT_MODULE_PATH.clone(),
+                    ast::Module::new(vec![
+                        ast::VariableDefinition::new("cflags", "-O0").into(),
+                        ast_rule("cc", "cc $cflags $in").into(),
+                        ast_explicit_build(
+                            vec!["bar".into()],
+                            "cc",
+                            vec!["baz".into()],
+                            build_variables,
+                        )
+                        .into(),
+                    ]),
+                )]
+                .into_iter()
+                .collect(),
+                &DEFAULT_DEPENDENCIES,
+                &ROOT_MODULE_PATHS,
+                &Options {
+                    variables: [("cflags".into(), "-O2".into())].into_iter().collect(),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+            configuration.outputs()["bar"]
+                .rule()
+                .unwrap()
+                .command()
+                .into()
+        }
+
+        #[test]
+        fn override_top_level_variable() {
+            assert_eq!(compile_command(vec![]), "cc -O2 baz");
+        }
+
+        #[test]
+        fn shadow_overridden_variable_by_build_variable() {
+            assert_eq!(
+                compile_command(vec![ast::VariableDefinition::new("cflags", "-Os")]),
+                "cc -Os baz"
+            );
+        }
+    }
+
+    mod dynamic_module {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn compile_supported_version() {
+            assert_eq!(
+                compile_dynamic(&ast::DynamicModule::new(
+                    "1",
+                    vec![ast::DynamicBuild::new("foo", vec!["bar".into()])]
+                )),
+                Ok(DynamicConfiguration::new(
+                    [("foo".into(), DynamicBuild::new(vec!["bar".into()]))]
+                        .into_iter()
+                        .collect()
+                ))
+            );
+        }
+
+        #[test]
+        fn fail_to_compile_unsupported_version() {
+            assert_eq!(
+                compile_dynamic(&ast::DynamicModule::new("2", vec![])),
+                Err(CompileError::UnsupportedDynamicModuleVersion("2".into()))
+            );
+        }
+    }
+
+    mod root_module {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        const FOO_MODULE_PATH: &str = "foo.ninja";
+        const BAR_MODULE_PATH: &str = "bar.ninja";
+        const RULE_MODULE_PATH: &str = "rules.ninja";
+
+        fn compile_roots(
+            foo_statements: Vec<ast::Statement>,
+            bar_statements: Vec<ast::Statement>,
+        ) -> Result<Configuration, CompileError> {
+            let dependencies = [(RULE_MODULE_PATH.into(), PathBuf::from(RULE_MODULE_PATH))]
+                .into_iter()
+                .collect::<HashMap<_, _>>();
+
+            crate::compile::compile(
+                &[
+                    (FOO_MODULE_PATH.into(), ast::Module::new(foo_statements)),
+                    (BAR_MODULE_PATH.into(), ast::Module::new(bar_statements)),
+                    (
+                        RULE_MODULE_PATH.into(),
+                        ast::Module::new(vec![ast_rule("cp", "cp $in $out").into()]),
+                    ),
+                ]
+                .into_iter()
+                .collect(),
+                &[
+                    (FOO_MODULE_PATH.into(), dependencies.clone()),
+                    (BAR_MODULE_PATH.into(), dependencies),
+                    (RULE_MODULE_PATH.into(), Default::default()),
+                ]
+                .into_iter()
+                .collect(),
+                &[FOO_MODULE_PATH.into(), BAR_MODULE_PATH.into()],
+                &Default::default(),
+            )
+            .map(|(configuration, _)| configuration)
+        }
+
+        fn build_statement(output: &str, input: &str) -> ast::Statement {
+            ast_explicit_build(vec![output.into()], "cp", vec![input.into()], vec![]).into()
+        }
+
+        #[test]
+        fn compile_roots_sharing_included_module() {
+            let configuration = compile_roots(
+                vec![
+                    ast::Include::new(RULE_MODULE_PATH).into(),
+                    build_statement("foo", "a"),
+                ],
+                vec![
+                    ast::Include::new(RULE_MODULE_PATH).into(),
+                    build_statement("bar", "b"),
+                ],
+            )
+            .unwrap();
+
+            assert_eq!(
+                configuration
+                    .outputs()
+                    .keys()
+                    .cloned()
+                    .collect::<HashSet<_>>(),
+                ["foo".into(), "bar".into()].into_iter().collect()
+            );
+            assert_eq!(
+                configuration.outputs()["bar"].rule().unwrap().command(),
+                "cp b bar"
+            );
+            assert_eq!(
+                configuration.default_outputs(),
+                &["foo".into(), "bar".into()].into_iter().collect()
+            );
+        }
+
+        #[test]
+        fn merge_default_outputs_of_roots() {
+            let configuration = compile_roots(
+                vec![
+                    ast::Include::new(RULE_MODULE_PATH).into(),
+                    build_statement("foo", "a"),
+                    build_statement("baz", "a"),
+                    ast::DefaultOutput::new(vec!["foo".into()]).into(),
+                ],
+                vec![
+                    ast::Include::new(RULE_MODULE_PATH).into(),
+                    build_statement("bar", "b"),
+                ],
+            )
+            .unwrap();
+
+            assert_eq!(
+                configuration.default_outputs(),
+                &["foo".into(), "bar".into()].into_iter().collect()
+            );
+        }
+
+        #[test]
+        fn fail_to_compile_output_defined_in_roots() {
+            assert_eq!(
+                compile_roots(
+                    vec![
+                        ast::Include::new(RULE_MODULE_PATH).into(),
+                        build_statement("foo", "a"),
+                    ],
+                    vec![
+                        ast::Include::new(RULE_MODULE_PATH).into(),
+                        build_statement("foo", "b"),
+                    ],
+                ),
+                Err(CompileError::DuplicateOutput("foo".into()))
+            );
+        }
+
+        #[test]
+        fn do_not_share_rules_between_roots() {
+            assert_eq!(
+                compile_roots(
+                    vec![
+                        ast::Include::new(RULE_MODULE_PATH).into(),
+                        build_statement("foo", "a"),
+                    ],
+                    vec![build_statement("bar", "b")],
+                ),
+                Err(CompileError::RuleNotFound("cp".into()))
             );
         }
     }
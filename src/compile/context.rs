@@ -1,3 +1,4 @@
+use super::options::Options;
 use crate::{ast::Module, module_dependency::ModuleDependencyMap};
 use std::{collections::HashMap, path::PathBuf};
 
@@ -5,16 +6,19 @@ use std::{collections::HashMap, path::PathBuf};
 pub struct Context<'a> {
     modules: &'a HashMap<PathBuf, Module>,
     dependencies: &'a ModuleDependencyMap,
+    options: &'a Options,
 }
 
 impl<'a> Context<'a> {
     pub fn new(
         modules: &'a HashMap<PathBuf, Module>,
         dependencies: &'a ModuleDependencyMap,
+        options: &'a Options,
     ) -> Self {
         Self {
             modules,
             dependencies,
+            options,
         }
     }
 
@@ -25,4 +29,8 @@ impl<'a> Context<'a> {
     pub fn dependencies(&self) -> &ModuleDependencyMap {
         self.dependencies
     }
+
+    pub fn options(&self) -> &Options {
+        self.options
+    }
 }
@@ -6,8 +6,17 @@ use std::{
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum CompileError {
+    DuplicateOutput(String),
+    EmptyCommand(String, String),
+    InvalidDependencyFormat(String),
+    InvalidEnvironmentVariable(String),
+    InvalidPoolDepth(String),
+    ModuleDepthExceeded(PathBuf),
     ModuleNotFound(PathBuf),
+    PoolNotFound(String),
     RuleNotFound(String),
+    UndefinedVariable(String, String, String),
+    UnsupportedDynamicModuleVersion(String),
 }
 
 impl Error for CompileError {}
@@ -15,12 +24,45 @@ impl Error for CompileError {}
 impl Display for CompileError {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         match self {
+            Self::DuplicateOutput(output) => {
+                write!(formatter, "multiple builds generate output \"{output}\"")
+            }
+            Self::EmptyCommand(rule, output) => write!(
+                formatter,
+                "empty command in rule \"{rule}\" of build \"{output}\""
+            ),
+            Self::InvalidDependencyFormat(format) => {
+                write!(formatter, "invalid dependency format \"{format}\"")
+            }
+            Self::InvalidEnvironmentVariable(variable) => {
+                write!(formatter, "invalid environment variable \"{variable}\"")
+            }
+            Self::InvalidPoolDepth(pool) => {
+                write!(formatter, "invalid depth of pool \"{pool}\"")
+            }
+            Self::ModuleDepthExceeded(path) => {
+                write!(
+                    formatter,
+                    "build file \"{}\" nested too deeply",
+                    path.display()
+                )
+            }
             Self::ModuleNotFound(path) => {
                 write!(formatter, "module \"{}\" not found", path.display())
             }
+            Self::PoolNotFound(pool) => {
+                write!(formatter, "pool \"{pool}\" not found")
+            }
             Self::RuleNotFound(rule) => {
                 write!(formatter, "rule \"{rule}\" not found")
             }
+            Self::UndefinedVariable(variable, rule, output) => write!(
+                formatter,
+                "undefined variable \"{variable}\" in rule \"{rule}\" of build \"{output}\""
+            ),
+            Self::UnsupportedDynamicModuleVersion(version) => {
+                write!(formatter, "unsupported dyndep version \"{version}\"")
+            }
         }
     }
 }
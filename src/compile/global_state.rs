@@ -1,3 +1,4 @@
+use super::warning::CompileWarning;
 use crate::ir::Build;
 use std::{
     collections::{HashMap, HashSet},
@@ -9,4 +10,6 @@ pub struct GlobalState {
     pub outputs: HashMap<Arc<str>, Arc<Build>>,
     pub default_outputs: HashSet<Arc<str>>,
     pub source_map: HashMap<Arc<str>, Arc<str>>,
+    pub pools: HashMap<Arc<str>, usize>,
+    pub warnings: Vec<CompileWarning>,
 }
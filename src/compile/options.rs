@@ -0,0 +1,27 @@
+use crate::warning::WarningConfiguration;
+use std::collections::HashMap;
+
+const DEFAULT_MODULE_DEPTH_LIMIT: usize = 256;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Options {
+    // Duplicate outputs and references to undefined variables in commands are
+    // reported as warnings or errors, or ignored by their levels. Then, the
+    // first builds of duplicate outputs are used.
+    pub warnings: WarningConfiguration,
+    // The maximum depth of nested `include` and `subninja` statements.
+    pub module_depth_limit: usize,
+    // Variables given on the command line override top-level ones of the same
+    // names in build files.
+    pub variables: HashMap<String, String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            warnings: Default::default(),
+            module_depth_limit: DEFAULT_MODULE_DEPTH_LIMIT,
+            variables: Default::default(),
+        }
+    }
+}
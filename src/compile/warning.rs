@@ -0,0 +1,26 @@
+use std::fmt::{self, Display, Formatter};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CompileWarning {
+    DuplicateOutput(String),
+    EmptyCommand(String, String),
+    UndefinedVariable(String, String, String),
+}
+
+impl Display for CompileWarning {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::DuplicateOutput(output) => {
+                write!(formatter, "multiple builds generate output \"{output}\"")
+            }
+            Self::EmptyCommand(rule, output) => write!(
+                formatter,
+                "empty command in rule \"{rule}\" of build \"{output}\""
+            ),
+            Self::UndefinedVariable(variable, rule, output) => write!(
+                formatter,
+                "undefined variable \"{variable}\" in rule \"{rule}\" of build \"{output}\""
+            ),
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use crate::{
+    context::Context,
+    error::ApplicationError,
+    ir::{Build, Configuration},
+};
+use serde::Serialize;
+use std::sync::Arc;
+
+#[derive(Serialize)]
+struct ConfigurationJson<'a> {
+    builds: Vec<BuildJson<'a>>,
+    default_outputs: Vec<&'a Arc<str>>,
+}
+
+#[derive(Serialize)]
+struct BuildJson<'a> {
+    outputs: &'a [Arc<str>],
+    implicit_outputs: &'a [Arc<str>],
+    rule: Option<&'a str>,
+    command: Option<&'a str>,
+    inputs: &'a [Arc<str>],
+    implicit_inputs: &'a [Arc<str>],
+    order_only_inputs: &'a [Arc<str>],
+}
+
+impl<'a> From<&'a Build> for BuildJson<'a> {
+    fn from(build: &'a Build) -> Self {
+        Self {
+            outputs: build.outputs(),
+            implicit_outputs: build.implicit_outputs(),
+            rule: build.rule().map(|rule| rule.name().unwrap_or_default()),
+            command: build.rule().map(|rule| rule.command()),
+            inputs: build.explicit_inputs(),
+            implicit_inputs: build.implicit_inputs(),
+            order_only_inputs: build.order_only_inputs(),
+        }
+    }
+}
+
+// Prints a whole configuration in JSON for external tools. Builds are sorted
+// by their outputs so that outputs are deterministic.
+pub async fn emit(
+    context: &Context,
+    configuration: &Configuration,
+) -> Result<(), ApplicationError> {
+    context
+        .console()
+        .lock()
+        .await
+        .write_stdout(render(configuration).as_bytes())
+        .await?;
+
+    Ok(())
+}
+
+fn render(configuration: &Configuration) -> String {
+    let mut builds = configuration.outputs().values().collect::<Vec<_>>();
+
+    builds.sort_by(|one, other| one.outputs().cmp(other.outputs()));
+    builds.dedup_by_key(|build| build.id());
+
+    let mut default_outputs = configuration.default_outputs().iter().collect::<Vec<_>>();
+
+    default_outputs.sort();
+
+    let mut string = serde_json::to_string_pretty(&ConfigurationJson {
+        builds: builds
+            .into_iter()
+            .map(|build| build.as_ref().into())
+            .collect(),
+        default_outputs,
+    })
+    .expect("configuration serializable");
+
+    string.push('\n');
+
+    string
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::Rule;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn render_empty() {
+        assert_eq!(
+            render(&Configuration::from_builds(vec![]).with_default_outputs(&[])),
+            "{\n  \"builds\": [],\n  \"default_outputs\": []\n}\n"
+        );
+    }
+
+    #[test]
+    fn render_builds() {
+        assert_eq!(
+            render(
+                &Configuration::from_builds(vec![
+                    Build::new(
+                        vec!["foo.o".into()],
+                        vec![],
+                        Some(Rule::new("cc -c \"foo.c\"", None).with_name("cc")),
+                        vec!["foo.c".into(), "foo.h".into()],
+                        vec!["bar".into()],
+                        None,
+                    )
+                    .with_implicit_input_count(1),
+                    Build::new(
+                        vec!["all".into()],
+                        vec![],
+                        None,
+                        vec!["foo.o".into()],
+                        vec![],
+                        None,
+                    ),
+                ])
+                .with_default_outputs(&["all"])
+            ),
+            [
+                "{",
+                r#"  "builds": ["#,
+                "    {",
+                r#"      "outputs": ["#,
+                r#"        "all""#,
+                "      ],",
+                r#"      "implicit_outputs": [],"#,
+                r#"      "rule": null,"#,
+                r#"      "command": null,"#,
+                r#"      "inputs": ["#,
+                r#"        "foo.o""#,
+                "      ],",
+                r#"      "implicit_inputs": [],"#,
+                r#"      "order_only_inputs": []"#,
+                "    },",
+                "    {",
+                r#"      "outputs": ["#,
+                r#"        "foo.o""#,
+                "      ],",
+                r#"      "implicit_outputs": [],"#,
+                r#"      "rule": "cc","#,
+                r#"      "command": "cc -c \"foo.c\"","#,
+                r#"      "inputs": ["#,
+                r#"        "foo.c""#,
+                "      ],",
+                r#"      "implicit_inputs": ["#,
+                r#"        "foo.h""#,
+                "      ],",
+                r#"      "order_only_inputs": ["#,
+                r#"        "bar""#,
+                "      ]",
+                "    }",
+                "  ],",
+                r#"  "default_outputs": ["#,
+                r#"    "all""#,
+                "  ]",
+                "}",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -1,4 +1,8 @@
-use crate::infrastructure::{CommandRunner, Console, Database, FileSystem};
+use crate::{
+    error::ApplicationError,
+    infrastructure::{CommandRunner, Console, Database, FileSystem, OsCommandRunner, OsDatabase},
+};
+use std::path::Path;
 use tokio::sync::Mutex;
 
 pub struct Context {
@@ -9,21 +13,49 @@ pub struct Context {
 }
 
 impl Context {
+    // Commands are run in processes of an operating system by default.
     pub fn new(
-        command_runner: impl CommandRunner + Send + Sync + 'static,
         console: impl Console + Send + Sync + 'static,
-        database: impl Database + Send + Sync + 'static,
         file_system: impl FileSystem + Send + Sync + 'static,
     ) -> Self {
         Self {
-            command_runner: Box::new(command_runner),
+            command_runner: Box::new(OsCommandRunner::new()),
             console: Mutex::new(Box::new(console)),
             file_system: Box::new(file_system),
-            database: Box::new(database),
+            database: Box::new(OsDatabase::new()),
         }
     }
 
-    pub fn command_runner(&self) -> &(dyn CommandRunner + Send + Sync) {
+    // Replaces a command runner to run commands in other environments, such
+    // as containers.
+    pub fn with_command_runner(
+        self,
+        command_runner: impl CommandRunner + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            command_runner: Box::new(command_runner),
+            ..self
+        }
+    }
+
+    // Opens a persistent database at a path to cache build states. A database
+    // needs to be initialized once before running builds.
+    pub fn with_database_path(self, path: &Path) -> Result<Self, ApplicationError> {
+        self.database
+            .initialize(path)
+            .map_err(|error| ApplicationError::Database(path.into(), Box::new(error.into())))?;
+
+        Ok(self)
+    }
+
+    // Opens a database removed on exit so that nothing is cached across runs.
+    pub fn with_temporary_database(self) -> Result<Self, ApplicationError> {
+        self.database.initialize_temporary()?;
+
+        Ok(self)
+    }
+
+    pub(crate) fn command_runner(&self) -> &(dyn CommandRunner + Send + Sync) {
         &*self.command_runner
     }
 
@@ -31,7 +63,7 @@ impl Context {
         &self.console
     }
 
-    pub fn database(&self) -> &(dyn Database + Send + Sync) {
+    pub(crate) fn database(&self) -> &(dyn Database + Send + Sync) {
         &*self.database
     }
 
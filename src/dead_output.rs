@@ -0,0 +1,140 @@
+use crate::ir::Configuration;
+use std::{collections::HashSet, sync::Arc};
+
+// Finds outputs of builds which are neither default outputs nor inputs of any
+// other builds. Builds with any of their outputs used are not considered dead.
+pub fn find(configuration: &Configuration) -> Vec<Arc<str>> {
+    let used_outputs = configuration
+        .outputs()
+        .values()
+        .flat_map(|build| build.inputs().iter().chain(build.order_only_inputs()))
+        .chain(configuration.default_outputs())
+        .map(|output| output.as_ref())
+        .collect::<HashSet<&str>>();
+
+    let mut outputs = configuration
+        .outputs()
+        .values()
+        .filter(|build| {
+            !build
+                .outputs()
+                .iter()
+                .chain(build.implicit_outputs())
+                .any(|output| used_outputs.contains(output.as_ref()))
+        })
+        .map(|build| build.outputs()[0].clone())
+        .collect::<Vec<_>>();
+
+    outputs.sort();
+    outputs.dedup();
+
+    outputs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Build, Rule};
+
+    fn explicit_build(outputs: Vec<Arc<str>>, inputs: Vec<Arc<str>>) -> Build {
+        Build::new(
+            outputs,
+            vec![],
+            Rule::new("", None).into(),
+            inputs,
+            vec![],
+            None,
+        )
+    }
+
+    fn find_dead_outputs(builds: Vec<Build>, default_outputs: &[&str]) -> Vec<Arc<str>> {
+        find(&Configuration::from_builds(builds).with_default_outputs(default_outputs))
+    }
+
+    #[test]
+    fn find_nothing_in_empty_configuration() {
+        assert_eq!(find_dead_outputs(vec![], &[]), Vec::<Arc<str>>::new());
+    }
+
+    #[test]
+    fn find_nothing_for_default_output() {
+        assert_eq!(
+            find_dead_outputs(vec![explicit_build(vec!["foo".into()], vec![])], &["foo"]),
+            Vec::<Arc<str>>::new()
+        );
+    }
+
+    #[test]
+    fn find_nothing_for_input() {
+        assert_eq!(
+            find_dead_outputs(
+                vec![
+                    explicit_build(vec!["foo".into()], vec!["bar".into()]),
+                    explicit_build(vec!["bar".into()], vec![])
+                ],
+                &["foo"]
+            ),
+            Vec::<Arc<str>>::new()
+        );
+    }
+
+    #[test]
+    fn find_nothing_for_order_only_input() {
+        assert_eq!(
+            find_dead_outputs(
+                vec![
+                    Build::new(
+                        vec!["foo".into()],
+                        vec![],
+                        Rule::new("", None).into(),
+                        vec![],
+                        vec!["bar".into()],
+                        None,
+                    ),
+                    explicit_build(vec!["bar".into()], vec![])
+                ],
+                &["foo"]
+            ),
+            Vec::<Arc<str>>::new()
+        );
+    }
+
+    #[test]
+    fn find_nothing_for_secondary_output() {
+        assert_eq!(
+            find_dead_outputs(
+                vec![explicit_build(vec!["foo".into(), "bar".into()], vec![])],
+                &["bar"]
+            ),
+            Vec::<Arc<str>>::new()
+        );
+    }
+
+    #[test]
+    fn find_dead_output() {
+        assert_eq!(
+            find_dead_outputs(
+                vec![
+                    explicit_build(vec!["foo".into()], vec![]),
+                    explicit_build(vec!["bar".into()], vec![])
+                ],
+                &["foo"]
+            ),
+            vec!["bar".into()]
+        );
+    }
+
+    #[test]
+    fn find_dead_output_with_input() {
+        assert_eq!(
+            find_dead_outputs(
+                vec![
+                    explicit_build(vec!["foo".into()], vec![]),
+                    explicit_build(vec!["bar".into()], vec!["foo".into()])
+                ],
+                &["foo"]
+            ),
+            vec!["bar".into()]
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use crate::{error::ApplicationError, ir::Configuration, path::normalize_path};
+use std::collections::{HashMap, HashSet};
+
+// Finds given outputs and ones of builds depending on them transitively. Given
+// paths can also be source files which are inputs of builds.
+//
+// Outputs of phony builds are excluded as they often aggregate unrelated ones.
+pub fn find(
+    configuration: &Configuration,
+    paths: &[String],
+) -> Result<Vec<String>, ApplicationError> {
+    let mut dependents = HashMap::<&str, Vec<_>>::new();
+
+    for build in configuration.outputs().values() {
+        for input in build.inputs().iter().chain(build.order_only_inputs()) {
+            dependents.entry(input.as_ref()).or_default().push(build);
+        }
+    }
+
+    let paths = paths
+        .iter()
+        .map(|path| normalize_path(path.as_ref()).to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+
+    for path in &paths {
+        if !configuration.outputs().contains_key(path.as_str())
+            && !dependents.contains_key(path.as_str())
+        {
+            return Err(ApplicationError::OutputNotFound(path.clone()));
+        }
+    }
+
+    let mut queue = paths.iter().map(String::as_str).collect::<Vec<_>>();
+    let mut visited = HashSet::new();
+    let mut outputs = paths
+        .iter()
+        .filter(|path| configuration.outputs().contains_key(path.as_str()))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    while let Some(path) = queue.pop() {
+        if !visited.insert(path) {
+            continue;
+        }
+
+        for build in dependents.get(path).into_iter().flatten() {
+            if build.rule().is_some() {
+                outputs.push(build.outputs()[0].to_string());
+            }
+
+            queue.extend(
+                build
+                    .outputs()
+                    .iter()
+                    .chain(build.implicit_outputs())
+                    .map(AsRef::as_ref),
+            );
+        }
+    }
+
+    outputs.sort();
+    outputs.dedup();
+
+    Ok(outputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Build, Rule};
+    use pretty_assertions::assert_eq;
+    use std::sync::Arc;
+
+    fn explicit_build(outputs: Vec<Arc<str>>, inputs: Vec<Arc<str>>) -> Build {
+        Build::new(
+            outputs,
+            vec![],
+            Rule::new("", None).into(),
+            inputs,
+            vec![],
+            None,
+        )
+    }
+
+    fn phony_build(outputs: Vec<Arc<str>>, inputs: Vec<Arc<str>>) -> Build {
+        Build::new(outputs, vec![], None, inputs, vec![], None)
+    }
+
+    // foo <- bar <- qux
+    //     <- baz <-
+    fn create_diamond_configuration() -> Configuration {
+        Configuration::from_builds(vec![
+            explicit_build(vec!["foo".into()], vec!["src".into()]),
+            explicit_build(vec!["bar".into()], vec!["foo".into()]),
+            explicit_build(vec!["baz".into()], vec!["foo".into()]),
+            explicit_build(vec!["qux".into()], vec!["bar".into(), "baz".into()]),
+            explicit_build(vec!["quux".into()], vec![]),
+        ])
+    }
+
+    #[test]
+    fn find_downstream_outputs_in_diamond() {
+        assert_eq!(
+            find(&create_diamond_configuration(), &["foo".into()]).unwrap(),
+            ["bar", "baz", "foo", "qux"]
+        );
+    }
+
+    #[test]
+    fn find_downstream_outputs_of_middle_output() {
+        assert_eq!(
+            find(&create_diamond_configuration(), &["bar".into()]).unwrap(),
+            ["bar", "qux"]
+        );
+    }
+
+    #[test]
+    fn find_downstream_outputs_of_source() {
+        assert_eq!(
+            find(&create_diamond_configuration(), &["./src".into()]).unwrap(),
+            ["bar", "baz", "foo", "qux"]
+        );
+    }
+
+    #[test]
+    fn find_downstream_outputs_through_phony_build() {
+        assert_eq!(
+            find(
+                &Configuration::from_builds(vec![
+                    explicit_build(vec!["foo".into()], vec![]),
+                    phony_build(vec!["bar".into()], vec!["foo".into()]),
+                    explicit_build(vec!["baz".into()], vec!["bar".into()]),
+                    phony_build(vec!["all".into()], vec!["baz".into(), "qux".into()]),
+                    explicit_build(vec!["qux".into()], vec![]),
+                ]),
+                &["foo".into()]
+            )
+            .unwrap(),
+            ["baz", "foo"]
+        );
+    }
+
+    #[test]
+    fn fail_to_find_unknown_path() {
+        assert_eq!(
+            find(&create_diamond_configuration(), &["foo/bar".into()]),
+            Err(ApplicationError::OutputNotFound("foo/bar".into()))
+        );
+    }
+}
@@ -5,25 +5,142 @@ use crate::{
 use std::{
     error::Error,
     fmt::{self, Display, Formatter},
+    path::{Path, PathBuf},
     sync::Arc,
 };
-use tokio::{io, task::JoinError};
+use tokio::{io, sync::AcquireError, task::JoinError};
+
+// Exit code 2 is not used as it is for invalid command line arguments.
+const BUILD_EXIT_CODE: i32 = 1;
+const BUILD_FILE_EXIT_CODE: i32 = 3;
+const BUILD_GRAPH_EXIT_CODE: i32 = 4;
+const INFRASTRUCTURE_EXIT_CODE: i32 = 5;
+const INTERRUPTION_EXIT_CODE: i32 = 130; // 128 + SIGINT
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum ApplicationError {
     Build,
+    Builds(usize),
     BuildGraph(BuildGraphError),
+    CommandTimeout(String),
     Compile(CompileError),
+    Database(PathBuf, Box<ApplicationError>),
+    DeadOutputs(Vec<Arc<str>>),
     DefaultOutputNotFound(Arc<str>),
+    DependencyPathNotFound(String, String),
     DynamicDependencyNotFound(Arc<Build>),
+    DynamicParse(Arc<Build>, ParseError),
     FileNotFound(String),
+    FilesNotFound(Vec<String>),
     InputNotBuilt(String),
     InputNotFound(String),
+    Interrupted,
+    InvalidToolArgument(String),
+    Io(io::ErrorKind, String),
+    ManifestNotFound(PathBuf),
     ModuleDependency(ModuleDependencyError),
     Other(String),
     OutputNotFound(String),
+    OutputsMissing(Vec<String>),
     Parse(ParseError),
     Sled(sled::Error),
+    SourcesOverwritten(Vec<String>),
+}
+
+impl ApplicationError {
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Io(kind, _) => is_transient_io_error(*kind),
+            Self::Sled(sled::Error::Io(error)) => is_transient_io_error(error.kind()),
+            Self::Database(_, error) => error.is_transient(),
+            _ => false,
+        }
+    }
+
+    // Build failures and the other errors share the same code for compatibility
+    // with Ninja.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Compile(_)
+            | Self::DynamicDependencyNotFound(_)
+            | Self::DynamicParse(_, _)
+            | Self::ManifestNotFound(_)
+            | Self::ModuleDependency(_)
+            | Self::Parse(_) => BUILD_FILE_EXIT_CODE,
+            Self::BuildGraph(_) | Self::DeadOutputs(_) => BUILD_GRAPH_EXIT_CODE,
+            Self::Database(_, _) | Self::Io(_, _) | Self::Sled(_) => INFRASTRUCTURE_EXIT_CODE,
+            Self::Interrupted => INTERRUPTION_EXIT_CODE,
+            _ => BUILD_EXIT_CODE,
+        }
+    }
+
+    // Names of variants in snake case for machine-readable error reports
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Build => "build",
+            Self::Builds(_) => "builds",
+            Self::BuildGraph(_) => "build_graph",
+            Self::CommandTimeout(_) => "command_timeout",
+            Self::Compile(_) => "compile",
+            Self::Database(_, _) => "database",
+            Self::DeadOutputs(_) => "dead_outputs",
+            Self::DefaultOutputNotFound(_) => "default_output_not_found",
+            Self::DependencyPathNotFound(_, _) => "dependency_path_not_found",
+            Self::DynamicDependencyNotFound(_) => "dynamic_dependency_not_found",
+            Self::DynamicParse(_, _) => "dynamic_parse",
+            Self::FileNotFound(_) => "file_not_found",
+            Self::FilesNotFound(_) => "files_not_found",
+            Self::InputNotBuilt(_) => "input_not_built",
+            Self::InputNotFound(_) => "input_not_found",
+            Self::Interrupted => "interrupted",
+            Self::InvalidToolArgument(_) => "invalid_tool_argument",
+            Self::Io(_, _) => "io",
+            Self::ManifestNotFound(_) => "manifest_not_found",
+            Self::ModuleDependency(_) => "module_dependency",
+            Self::Other(_) => "other",
+            Self::OutputNotFound(_) => "output_not_found",
+            Self::OutputsMissing(_) => "outputs_missing",
+            Self::Parse(_) => "parse",
+            Self::Sled(_) => "sled",
+            Self::SourcesOverwritten(_) => "sources_overwritten",
+        }
+    }
+
+    pub fn outputs(&self) -> Option<Vec<&str>> {
+        match self {
+            Self::BuildGraph(BuildGraphError::CircularDependency(outputs))
+            | Self::DeadOutputs(outputs) => Some(outputs.iter().map(AsRef::as_ref).collect()),
+            Self::DefaultOutputNotFound(output) => Some(vec![output]),
+            Self::DynamicDependencyNotFound(build) | Self::DynamicParse(build, _) => {
+                Some(build.outputs().iter().map(AsRef::as_ref).collect())
+            }
+            Self::OutputNotFound(output) => Some(vec![output]),
+            Self::OutputsMissing(outputs) | Self::SourcesOverwritten(outputs) => {
+                Some(outputs.iter().map(AsRef::as_ref).collect())
+            }
+            _ => None,
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Database(path, _) => Some(path),
+            Self::DynamicParse(_, error) | Self::Parse(error) => error.path(),
+            Self::FileNotFound(path) => Some(path.as_ref()),
+            Self::ManifestNotFound(path) => Some(path),
+            _ => None,
+        }
+    }
+}
+
+fn is_transient_io_error(kind: io::ErrorKind) -> bool {
+    matches!(
+        kind,
+        io::ErrorKind::Interrupted
+            | io::ErrorKind::ResourceBusy
+            | io::ErrorKind::TimedOut
+            | io::ErrorKind::WouldBlock
+    )
 }
 
 impl Error for ApplicationError {}
@@ -32,10 +149,24 @@ impl Display for ApplicationError {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         match self {
             Self::Build => write!(formatter, "build failed"),
+            Self::Builds(count) => write!(formatter, "{count} builds failed"),
+            Self::CommandTimeout(command) => write!(formatter, "command timed out: {command}"),
             Self::Compile(error) => write!(formatter, "{error}"),
+            Self::Database(path, error) => write!(
+                formatter,
+                "failed to open database \"{}\": {}",
+                path.display(),
+                error
+            ),
+            Self::DeadOutputs(outputs) => {
+                write!(formatter, "outputs never used: {}", outputs.join(", "))
+            }
             Self::DefaultOutputNotFound(output) => {
                 write!(formatter, "default output \"{output}\" not found")
             }
+            Self::DependencyPathNotFound(from, to) => {
+                write!(formatter, "no dependency path from \"{from}\" to \"{to}\"")
+            }
             Self::DynamicDependencyNotFound(build) => {
                 write!(
                     formatter,
@@ -44,13 +175,40 @@ impl Display for ApplicationError {
                     build.dynamic_module().unwrap()
                 )
             }
+            Self::DynamicParse(build, error) => {
+                write!(
+                    formatter,
+                    "failed to parse dynamic dependency file for outputs {}: {}",
+                    build.outputs().join(", "),
+                    error
+                )
+            }
             Self::FileNotFound(path) => write!(formatter, "file \"{path}\" not found"),
+            Self::FilesNotFound(paths) => write!(
+                formatter,
+                "files {} not found",
+                paths
+                    .iter()
+                    .map(|path| format!("\"{path}\""))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
             Self::InputNotBuilt(input) => {
                 write!(formatter, "input \"{input}\" not built yet")
             }
             Self::InputNotFound(input) => {
                 write!(formatter, "input \"{input}\" not found")
             }
+            Self::Interrupted => write!(formatter, "build interrupted"),
+            Self::InvalidToolArgument(argument) => {
+                write!(formatter, "invalid tool argument \"{argument}\"")
+            }
+            Self::Io(_, message) => write!(formatter, "{message}"),
+            Self::ManifestNotFound(path) => write!(
+                formatter,
+                "build file \"{}\" not found (use -f to specify a build file or -C to change a directory)",
+                path.display()
+            ),
             Self::ModuleDependency(error) => {
                 write!(formatter, "{error}")
             }
@@ -58,16 +216,46 @@ impl Display for ApplicationError {
             Self::OutputNotFound(output) => {
                 write!(formatter, "output \"{output}\" not found")
             }
+            Self::OutputsMissing(outputs) => write!(
+                formatter,
+                "outputs {} missing after builds",
+                outputs
+                    .iter()
+                    .map(|output| format!("\"{output}\""))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
             Self::Parse(error) => write!(formatter, "{error}"),
             Self::Sled(error) => write!(formatter, "{error}"),
+            Self::SourcesOverwritten(outputs) => write!(
+                formatter,
+                "outputs {} would overwrite source files",
+                outputs
+                    .iter()
+                    .map(|output| format!("\"{output}\""))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
             Self::BuildGraph(error) => write!(formatter, "{error}"),
         }
     }
 }
 
+impl From<AcquireError> for ApplicationError {
+    fn from(error: AcquireError) -> Self {
+        Self::Other(error.to_string())
+    }
+}
+
 impl From<Box<dyn Error>> for ApplicationError {
     fn from(error: Box<dyn Error>) -> Self {
-        Self::Other(error.to_string())
+        match error.downcast::<io::Error>() {
+            Ok(error) => (*error).into(),
+            Err(error) => match error.downcast::<sled::Error>() {
+                Ok(error) => (*error).into(),
+                Err(error) => Self::Other(error.to_string()),
+            },
+        }
     }
 }
 
@@ -79,6 +267,12 @@ impl From<CompileError> for ApplicationError {
 
 impl From<io::Error> for ApplicationError {
     fn from(error: io::Error) -> Self {
+        Self::Io(error.kind(), error.to_string())
+    }
+}
+
+impl From<notify::Error> for ApplicationError {
+    fn from(error: notify::Error) -> Self {
         Self::Other(error.to_string())
     }
 }
@@ -112,3 +306,38 @@ impl From<BuildGraphError> for ApplicationError {
         Self::BuildGraph(error)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn exit_code() {
+        assert_eq!(ApplicationError::Build.exit_code(), 1);
+        assert_eq!(ApplicationError::Builds(2).exit_code(), 1);
+        assert_eq!(
+            ApplicationError::CommandTimeout("foo".into()).exit_code(),
+            1
+        );
+        assert_eq!(
+            ApplicationError::Parse(parse("foo").unwrap_err()).exit_code(),
+            3
+        );
+        assert_eq!(
+            ApplicationError::Compile(CompileError::RuleNotFound("foo".into())).exit_code(),
+            3
+        );
+        assert_eq!(
+            ApplicationError::BuildGraph(BuildGraphError::CircularDependency(vec!["foo".into()]))
+                .exit_code(),
+            4
+        );
+        assert_eq!(
+            ApplicationError::Io(io::ErrorKind::PermissionDenied, "foo".into()).exit_code(),
+            5
+        );
+        assert_eq!(ApplicationError::Interrupted.exit_code(), 130);
+    }
+}
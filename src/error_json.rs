@@ -0,0 +1,75 @@
+use crate::{error::ApplicationError, json};
+use std::fmt::Write;
+
+// Renders an error as a JSON object in a line. Fields of outputs and paths are
+// present only for errors related to them.
+pub fn render(error: &ApplicationError) -> String {
+    let mut string = String::new();
+
+    write!(
+        string,
+        "{{\"kind\":{},\"message\":{}",
+        json::quote(error.kind()),
+        json::quote(&error.to_string())
+    )
+    .unwrap();
+
+    if let Some(outputs) = error.outputs() {
+        write!(
+            string,
+            ",\"outputs\":[{}]",
+            outputs
+                .into_iter()
+                .map(json::quote)
+                .collect::<Vec<_>>()
+                .join(",")
+        )
+        .unwrap();
+    }
+
+    if let Some(path) = error.path() {
+        write!(
+            string,
+            ",\"path\":{}",
+            json::quote(&path.display().to_string())
+        )
+        .unwrap();
+    }
+
+    writeln!(string, "}}").unwrap();
+
+    string
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build_graph::BuildGraphError;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn render_error() {
+        assert_eq!(
+            render(&ApplicationError::Build),
+            "{\"kind\":\"build\",\"message\":\"build failed\"}\n"
+        );
+    }
+
+    #[test]
+    fn render_error_with_outputs() {
+        assert_eq!(
+            render(&ApplicationError::BuildGraph(
+                BuildGraphError::CircularDependency(vec!["foo".into(), "bar".into()])
+            )),
+            "{\"kind\":\"build_graph\",\"message\":\"dependency cycle detected: foo -> bar -> foo\",\"outputs\":[\"foo\",\"bar\"]}\n"
+        );
+    }
+
+    #[test]
+    fn render_error_with_path() {
+        assert_eq!(
+            render(&ApplicationError::FileNotFound("foo\"bar".into())),
+            "{\"kind\":\"file_not_found\",\"message\":\"file \\\"foo\\\"bar\\\" not found\",\"path\":\"foo\\\"bar\"}\n"
+        );
+    }
+}
@@ -0,0 +1,74 @@
+// Matches a string with a glob pattern where `*` and `?` match any characters
+// and a character except `/` respectively, and `**` matches any characters
+// including `/`.
+pub fn matches(pattern: &str, string: &str) -> bool {
+    match_characters(
+        &pattern.chars().collect::<Vec<_>>(),
+        &string.chars().collect::<Vec<_>>(),
+    )
+}
+
+fn match_characters(pattern: &[char], string: &[char]) -> bool {
+    match pattern {
+        [] => string.is_empty(),
+        // Leading directories are optional.
+        ['*', '*', '/', pattern @ ..] => {
+            match_characters(pattern, string)
+                || (0..string.len())
+                    .filter(|&index| string[index] == '/')
+                    .any(|index| match_characters(pattern, &string[index + 1..]))
+        }
+        ['*', '*', pattern @ ..] => {
+            (0..=string.len()).any(|index| match_characters(pattern, &string[index..]))
+        }
+        ['*', pattern @ ..] => (0..=string.len())
+            .take_while(|&index| index == 0 || string[index - 1] != '/')
+            .any(|index| match_characters(pattern, &string[index..])),
+        ['?', pattern @ ..] => {
+            matches!(string, [character, string @ ..] if *character != '/' && match_characters(pattern, string))
+        }
+        [character, pattern @ ..] => {
+            matches!(string, [other, string @ ..] if other == character && match_characters(pattern, string))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_literal() {
+        assert!(matches("foo", "foo"));
+        assert!(!matches("foo", "bar"));
+        assert!(!matches("foo", "foo/bar"));
+        assert!(matches("", ""));
+    }
+
+    #[test]
+    fn match_star() {
+        assert!(matches("*", "foo"));
+        assert!(matches("*.o", "foo.o"));
+        assert!(matches("foo*", "foo"));
+        assert!(!matches("*.o", "foo.c"));
+        assert!(!matches("*.o", "foo/bar.o"));
+    }
+
+    #[test]
+    fn match_question_mark() {
+        assert!(matches("fo?", "foo"));
+        assert!(!matches("fo?", "fo"));
+        assert!(!matches("foo?bar", "foo/bar"));
+    }
+
+    #[test]
+    fn match_double_star() {
+        assert!(matches("**", "foo/bar"));
+        assert!(matches("**/*.o", "foo.o"));
+        assert!(matches("**/*.o", "foo/bar/baz.o"));
+        assert!(matches("foo/**/bar", "foo/bar"));
+        assert!(matches("foo/**/bar", "foo/baz/qux/bar"));
+        assert!(matches("foo/**", "foo/bar/baz"));
+        assert!(!matches("**/*.o", "foo/bar.c"));
+    }
+}
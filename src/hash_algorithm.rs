@@ -0,0 +1,42 @@
+use crate::hasher::FnvHasher;
+use std::{collections::hash_map::DefaultHasher, hash::Hasher};
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum HashAlgorithm {
+    #[default]
+    Fnv,
+    // Hashes of Rust's standard library are not stable across its versions.
+    Std,
+}
+
+impl HashAlgorithm {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Fnv => "fnv",
+            Self::Std => "std",
+        }
+    }
+
+    pub fn hasher(self) -> Box<dyn Hasher + Send> {
+        match self {
+            Self::Fnv => Box::new(FnvHasher::new()),
+            Self::Std => Box::new(DefaultHasher::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_with_different_algorithms() {
+        let hash = |algorithm: HashAlgorithm| {
+            let mut hasher = algorithm.hasher();
+            hasher.write(b"foo");
+            hasher.finish()
+        };
+
+        assert_ne!(hash(HashAlgorithm::Fnv), hash(HashAlgorithm::Std));
+    }
+}
@@ -1,5 +1,10 @@
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum HashType {
+    Command,
     Content,
     Timestamp,
 }
+
+impl HashType {
+    pub const ALL: [Self; 3] = [Self::Command, Self::Content, Self::Timestamp];
+}
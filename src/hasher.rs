@@ -0,0 +1,124 @@
+use std::hash::Hasher;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+const FNV_128_OFFSET_BASIS: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+const FNV_128_PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+
+// An FNV-1a hasher. Unlike `DefaultHasher`, its outputs are stable across Rust
+// versions and therefore can be persisted in databases.
+#[derive(Debug)]
+pub struct FnvHasher(u64);
+
+impl FnvHasher {
+    pub fn new() -> Self {
+        Self(FNV_OFFSET_BASIS)
+    }
+}
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+// A 128-bit FNV-1a hasher for hashes which must be resistant to collisions.
+#[derive(Debug)]
+pub struct Fnv128Hasher(u128);
+
+impl Fnv128Hasher {
+    pub fn new() -> Self {
+        Self(FNV_128_OFFSET_BASIS)
+    }
+
+    pub fn finish_u128(&self) -> u128 {
+        self.0
+    }
+}
+
+impl Default for Fnv128Hasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hasher for Fnv128Hasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u128;
+            self.0 = self.0.wrapping_mul(FNV_128_PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0 as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::hash::Hash;
+
+    #[test]
+    fn hash_nothing() {
+        assert_eq!(FnvHasher::new().finish(), 0xcbf2_9ce4_8422_2325);
+    }
+
+    #[test]
+    fn hash_bytes() {
+        let mut hasher = FnvHasher::new();
+
+        hasher.write(b"a");
+
+        assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+
+        let mut hasher = FnvHasher::new();
+
+        hasher.write(b"foobar");
+
+        assert_eq!(hasher.finish(), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn hash_string() {
+        let mut hasher = FnvHasher::new();
+
+        "foo".hash(&mut hasher);
+
+        assert_eq!(hasher.finish(), 0xdd12_8179_0c25_d618);
+    }
+
+    #[test]
+    fn hash_nothing_in_128_bits() {
+        assert_eq!(
+            Fnv128Hasher::new().finish_u128(),
+            0x6c62_272e_07bb_0142_62b8_2175_6295_c58d
+        );
+    }
+
+    #[test]
+    fn hash_bytes_in_128_bits() {
+        let mut hasher = Fnv128Hasher::new();
+
+        hasher.write(b"a");
+
+        assert_eq!(
+            hasher.finish_u128(),
+            0xd228_cb69_6f1a_8caf_7891_2b70_4e4a_8964
+        );
+    }
+}
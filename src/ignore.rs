@@ -0,0 +1,56 @@
+use crate::glob;
+
+// A list of glob patterns of files never removed by clean tools. Empty lines
+// and lines starting with `#` are skipped.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IgnoreList {
+    patterns: Vec<String>,
+}
+
+impl IgnoreList {
+    pub fn parse(source: &str) -> Self {
+        Self {
+            patterns: source
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(String::from)
+                .collect(),
+        }
+    }
+
+    pub fn is_ignored(&self, path: &str) -> bool {
+        self.patterns
+            .iter()
+            .any(|pattern| glob::matches(pattern, path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_empty() {
+        assert_eq!(IgnoreList::parse(""), IgnoreList::default());
+    }
+
+    #[test]
+    fn skip_comments_and_blank_lines() {
+        assert_eq!(
+            IgnoreList::parse("# foo\n\n  bar  \n"),
+            IgnoreList {
+                patterns: vec!["bar".into()]
+            }
+        );
+    }
+
+    #[test]
+    fn ignore_paths() {
+        let list = IgnoreList::parse("foo\n*.o\n");
+
+        assert!(list.is_ignored("foo"));
+        assert!(list.is_ignored("bar.o"));
+        assert!(!list.is_ignored("bar"));
+    }
+}
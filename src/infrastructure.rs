@@ -1,9 +1,15 @@
+mod buffer_console;
 mod command_runner;
 mod console;
 mod database;
 mod file_system;
+mod in_memory_file_system;
+mod load_average;
 
+pub use buffer_console::*;
 pub use command_runner::*;
 pub use console::*;
 pub use database::*;
 pub use file_system::*;
+pub use in_memory_file_system::*;
+pub use load_average::*;
@@ -0,0 +1,65 @@
+use super::Console;
+use async_trait::async_trait;
+use std::{
+    error::Error,
+    sync::{Arc, Mutex},
+};
+
+// A console capturing outputs on memory. Clones share the same buffers so that
+// outputs can be read after the console is moved into a context.
+#[derive(Clone, Debug, Default)]
+pub struct BufferConsole {
+    stdout: Arc<Mutex<Vec<u8>>>,
+    stderr: Arc<Mutex<Vec<u8>>>,
+}
+
+impl BufferConsole {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn stdout(&self) -> String {
+        String::from_utf8_lossy(&self.stdout.lock().unwrap()).into()
+    }
+
+    pub fn stderr(&self) -> String {
+        String::from_utf8_lossy(&self.stderr.lock().unwrap()).into()
+    }
+}
+
+#[async_trait]
+impl Console for BufferConsole {
+    async fn write_stdout(&mut self, src: &[u8]) -> Result<(), Box<dyn Error>> {
+        self.stdout.lock().unwrap().extend_from_slice(src);
+
+        Ok(())
+    }
+
+    async fn write_stderr(&mut self, src: &[u8]) -> Result<(), Box<dyn Error>> {
+        self.stderr.lock().unwrap().extend_from_slice(src);
+
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn capture_outputs() {
+        let console = BufferConsole::new();
+        let mut clone = console.clone();
+
+        clone.write_stdout(b"foo").await.unwrap();
+        clone.write_stderr(b"bar").await.unwrap();
+        clone.write_stdout(b"baz").await.unwrap();
+
+        assert_eq!(console.stdout(), "foobaz");
+        assert_eq!(console.stderr(), "bar");
+    }
+}
@@ -1,42 +1,134 @@
+use crate::ir::Environment;
 use async_trait::async_trait;
-use std::{error::Error, process::Output};
-use tokio::{process::Command, sync::Semaphore};
+use std::{
+    error::Error,
+    path::Path,
+    process::{ExitStatus, Output},
+};
+use tokio::process::Command;
 
+#[allow(clippy::double_must_use)]
 #[async_trait]
 pub trait CommandRunner {
-    async fn run(&self, command: &str) -> Result<Output, Box<dyn Error>>;
+    // Runs a command in a given directory or a current one.
+    async fn run(
+        &self,
+        command: &str,
+        directory: Option<&Path>,
+        environment: &Environment,
+    ) -> Result<Output, Box<dyn Error>>;
+    // Runs a command with standard I/O inherited from the current process.
+    async fn run_console(
+        &self,
+        command: &str,
+        directory: Option<&Path>,
+        environment: &Environment,
+    ) -> Result<ExitStatus, Box<dyn Error>>;
 }
 
-#[derive(Debug)]
-pub struct OsCommandRunner {
-    semaphore: Semaphore,
-}
+// Commands are run without any limit as jobs are limited by callers.
+#[derive(Debug, Default)]
+pub struct OsCommandRunner {}
 
 impl OsCommandRunner {
-    pub fn new(job_limit: usize) -> Self {
-        Self {
-            semaphore: Semaphore::new(job_limit),
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    fn command(string: &str, directory: Option<&Path>, environment: &Environment) -> Command {
+        let mut command = if cfg!(target_os = "windows") {
+            let components = string.split_whitespace().collect::<Vec<_>>();
+            let mut command = Command::new(components[0]);
+            command.args(&components[1..]);
+            command
+        } else {
+            let mut command = Command::new("sh");
+            command.arg("-ec").arg(string);
+            command
+        };
+
+        if let Some(directory) = directory {
+            command.current_dir(directory);
         }
+
+        if environment.clear() {
+            command.env_clear();
+        }
+
+        command.envs(environment.variables().iter().cloned());
+
+        // Kill processes of cancelled jobs on interruption.
+        command.kill_on_drop(true);
+        command
     }
 }
 
 #[async_trait]
 impl CommandRunner for OsCommandRunner {
-    async fn run(&self, command: &str) -> Result<Output, Box<dyn Error>> {
-        let permit = self.semaphore.acquire().await?;
-
-        let output = if cfg!(target_os = "windows") {
-            let components = command.split_whitespace().collect::<Vec<_>>();
-            Command::new(components[0])
-                .args(&components[1..])
-                .output()
-                .await?
-        } else {
-            Command::new("sh").arg("-ec").arg(command).output().await?
-        };
+    async fn run(
+        &self,
+        command: &str,
+        directory: Option<&Path>,
+        environment: &Environment,
+    ) -> Result<Output, Box<dyn Error>> {
+        Ok(Self::command(command, directory, environment)
+            .output()
+            .await?)
+    }
+
+    async fn run_console(
+        &self,
+        command: &str,
+        directory: Option<&Path>,
+        environment: &Environment,
+    ) -> Result<ExitStatus, Box<dyn Error>> {
+        Ok(Self::command(command, directory, environment)
+            .status()
+            .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn run_command_in_directory() {
+        let directory = tempfile::tempdir().unwrap();
+        let output = OsCommandRunner::new()
+            .run("pwd", Some(directory.path()), &Default::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            Path::new(String::from_utf8(output.stdout).unwrap().trim())
+                .canonicalize()
+                .unwrap(),
+            directory.path().canonicalize().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn run_command_with_environment_variables() {
+        let output = OsCommandRunner::new()
+            .run(
+                "echo $FOO",
+                None,
+                &Environment::new(vec![("FOO".into(), "bar".into())], false),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(output.stdout, b"bar\n");
+    }
 
-        drop(permit);
+    #[tokio::test]
+    async fn run_command_with_cleared_environment_variables() {
+        let output = OsCommandRunner::new()
+            .run("echo $HOME", None, &Environment::new(vec![], true))
+            .await
+            .unwrap();
 
-        Ok(output)
+        assert_eq!(output.stdout, b"\n");
     }
 }
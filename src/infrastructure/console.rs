@@ -2,10 +2,12 @@ use async_trait::async_trait;
 use std::{error::Error, fmt::Debug};
 use tokio::io::{stderr, stdout, AsyncWriteExt, Stderr, Stdout};
 
+#[allow(clippy::double_must_use)]
 #[async_trait]
 pub trait Console {
     async fn write_stdout(&mut self, buffer: &[u8]) -> Result<(), Box<dyn Error>>;
     async fn write_stderr(&mut self, buffer: &[u8]) -> Result<(), Box<dyn Error>>;
+    async fn flush(&mut self) -> Result<(), Box<dyn Error>>;
 }
 
 #[derive(Debug)]
@@ -23,6 +25,12 @@ impl OsConsole {
     }
 }
 
+impl Default for OsConsole {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[async_trait]
 impl Console for OsConsole {
     async fn write_stdout(&mut self, src: &[u8]) -> Result<(), Box<dyn Error>> {
@@ -36,4 +44,11 @@ impl Console for OsConsole {
 
         Ok(())
     }
+
+    async fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+        self.stdout.flush().await?;
+        self.stderr.flush().await?;
+
+        Ok(())
+    }
 }
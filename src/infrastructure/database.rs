@@ -1,47 +1,118 @@
 use crate::{hash_type::HashType, ir::BuildId};
 use async_trait::async_trait;
 use once_cell::sync::OnceCell;
-use std::{error::Error, path::Path, str};
-
+use std::{
+    collections::HashSet,
+    env,
+    error::Error,
+    fs, io,
+    path::{Path, PathBuf},
+    process, str,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::SystemTime,
+};
+
+const COMMAND_HASH_TREE_NAME: &str = "command_hash";
 const TIMESTAMP_HASH_TREE_NAME: &str = "timestamp_hash";
 const CONTENT_HASH_TREE_NAME: &str = "content_hash";
 const OUTPUT_TREE_NAME: &str = "output";
 const SOURCE_TREE_NAME: &str = "source";
+const DEPENDENCY_TREE_NAME: &str = "dependency";
+const HASH_ALGORITHM_KEY: &str = "hash_algorithm";
+const SNAPSHOT_ATTEMPT_COUNT: usize = 10;
+
+static SNAPSHOT_COUNT: AtomicUsize = AtomicUsize::new(0);
 
+#[allow(clippy::double_must_use)]
 #[async_trait]
 pub trait Database {
     fn initialize(&self, path: &Path) -> Result<(), Box<dyn Error>>;
+    // Initializes a database removed on exit.
+    fn initialize_temporary(&self) -> Result<(), Box<dyn Error>>;
+    // Initializes a snapshot of a database without locking it so that it can
+    // be read while another process is writing to it. Changes to the snapshot
+    // are discarded on exit.
+    fn initialize_read_only(&self, path: &Path) -> Result<(), Box<dyn Error>>;
+    // Removes a snapshot explicitly as it is not removed on process exit.
+    fn remove_snapshot(&self) -> Result<(), Box<dyn Error>>;
 
     fn get_hash(&self, r#type: HashType, id: BuildId) -> Result<Option<u64>, Box<dyn Error>>;
     fn set_hash(&self, r#type: HashType, id: BuildId, hash: u64) -> Result<(), Box<dyn Error>>;
+    fn clear_hashes(&self) -> Result<(), Box<dyn Error>>;
+
+    fn get_hash_algorithm(&self) -> Result<Option<String>, Box<dyn Error>>;
+    fn set_hash_algorithm(&self, algorithm: &str) -> Result<(), Box<dyn Error>>;
 
     fn get_outputs(&self) -> Result<Vec<String>, Box<dyn Error>>;
     fn set_output(&self, path: &str) -> Result<(), Box<dyn Error>>;
+    fn remove_output(&self, path: &str) -> Result<(), Box<dyn Error>>;
 
     fn get_source(&self, output: &str) -> Result<Option<String>, Box<dyn Error>>;
     fn set_source(&self, output: &str, source: &str) -> Result<(), Box<dyn Error>>;
 
+    fn get_dependencies(&self, id: BuildId) -> Result<Option<Vec<String>>, Box<dyn Error>>;
+    fn set_dependencies(&self, id: BuildId, dependencies: &[String]) -> Result<(), Box<dyn Error>>;
+
+    // Lists builds with any hashes or dependencies recorded.
+    fn get_build_ids(&self) -> Result<Vec<BuildId>, Box<dyn Error>>;
+    fn remove_build(&self, id: BuildId) -> Result<(), Box<dyn Error>>;
+
     async fn flush(&self) -> Result<(), Box<dyn Error>>;
 }
 
 #[derive(Debug)]
 pub struct OsDatabase {
     database: OnceCell<sled::Db>,
+    // A snapshot is taken lazily as most read-only tools never access a
+    // database.
+    snapshot_source: OnceCell<PathBuf>,
+    snapshot_path: OnceCell<PathBuf>,
 }
 
 impl OsDatabase {
     pub fn new() -> Self {
         Self {
             database: Default::default(),
+            snapshot_source: Default::default(),
+            snapshot_path: Default::default(),
         }
     }
 
     fn database(&self) -> Result<&sled::Db, Box<dyn Error>> {
-        Ok(self.database.get().ok_or("database not initialized")?)
+        self.database.get_or_try_init(|| {
+            self.open_snapshot(
+                self.snapshot_source
+                    .get()
+                    .ok_or("database not initialized")?,
+            )
+        })
+    }
+
+    // Sled cannot open a database without locking it. So we copy a database
+    // while no process writes to it instead, which leaves the copy in a state
+    // as consistent as after a crash.
+    fn open_snapshot(&self, path: &Path) -> Result<sled::Db, Box<dyn Error>> {
+        let snapshot_path = self.snapshot_path.get_or_init(|| {
+            env::temp_dir().join(format!(
+                "turtle-{}-{}",
+                process::id(),
+                SNAPSHOT_COUNT.fetch_add(1, Ordering::SeqCst)
+            ))
+        });
+
+        if path.exists() {
+            copy_quiescent_directory(path, snapshot_path)?;
+        }
+
+        Ok(sled::Config::new()
+            .path(snapshot_path)
+            .temporary(true)
+            .open()?)
     }
 
     fn hash_database(&self, r#type: HashType) -> Result<sled::Tree, Box<dyn Error>> {
         Ok(self.database()?.open_tree(match r#type {
+            HashType::Command => COMMAND_HASH_TREE_NAME,
             HashType::Content => CONTENT_HASH_TREE_NAME,
             HashType::Timestamp => TIMESTAMP_HASH_TREE_NAME,
         })?)
@@ -54,14 +125,38 @@ impl OsDatabase {
     fn source_database(&self) -> Result<sled::Tree, Box<dyn Error>> {
         Ok(self.database()?.open_tree(SOURCE_TREE_NAME)?)
     }
+
+    fn dependency_database(&self) -> Result<sled::Tree, Box<dyn Error>> {
+        Ok(self.database()?.open_tree(DEPENDENCY_TREE_NAME)?)
+    }
 }
 
 #[async_trait]
 impl Database for OsDatabase {
     fn initialize(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        self.database.get_or_try_init(|| sled::open(path))?;
+
+        Ok(())
+    }
+
+    fn initialize_temporary(&self) -> Result<(), Box<dyn Error>> {
         self.database
-            .set(sled::open(path)?)
-            .map_err(|_| "database already initialized")?;
+            .get_or_try_init(|| sled::Config::new().temporary(true).open())?;
+
+        Ok(())
+    }
+
+    fn initialize_read_only(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        // Ignore a second path as the other initializers do.
+        let _ = self.snapshot_source.set(path.into());
+
+        Ok(())
+    }
+
+    fn remove_snapshot(&self) -> Result<(), Box<dyn Error>> {
+        if let Some(path) = self.snapshot_path.get().filter(|path| path.exists()) {
+            fs::remove_dir_all(path)?;
+        }
 
         Ok(())
     }
@@ -81,6 +176,27 @@ impl Database for OsDatabase {
         Ok(())
     }
 
+    fn clear_hashes(&self) -> Result<(), Box<dyn Error>> {
+        for r#type in HashType::ALL {
+            self.hash_database(r#type)?.clear()?;
+        }
+
+        Ok(())
+    }
+
+    fn get_hash_algorithm(&self) -> Result<Option<String>, Box<dyn Error>> {
+        self.database()?
+            .get(HASH_ALGORITHM_KEY)?
+            .map(|algorithm| Ok::<_, Box<dyn Error>>(str::from_utf8(&algorithm)?.into()))
+            .transpose()
+    }
+
+    fn set_hash_algorithm(&self, algorithm: &str) -> Result<(), Box<dyn Error>> {
+        self.database()?.insert(HASH_ALGORITHM_KEY, algorithm)?;
+
+        Ok(())
+    }
+
     fn get_outputs(&self) -> Result<Vec<String>, Box<dyn Error>> {
         self.output_database()?
             .iter()
@@ -95,6 +211,12 @@ impl Database for OsDatabase {
         Ok(())
     }
 
+    fn remove_output(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        self.output_database()?.remove(path)?;
+
+        Ok(())
+    }
+
     fn get_source(&self, output: &str) -> Result<Option<String>, Box<dyn Error>> {
         self.source_database()?
             .get(output)?
@@ -108,6 +230,47 @@ impl Database for OsDatabase {
         Ok(())
     }
 
+    fn get_dependencies(&self, id: BuildId) -> Result<Option<Vec<String>>, Box<dyn Error>> {
+        Ok(self
+            .dependency_database()?
+            .get(id.to_bytes())?
+            .map(|value| bincode::deserialize(&value))
+            .transpose()?)
+    }
+
+    fn set_dependencies(&self, id: BuildId, dependencies: &[String]) -> Result<(), Box<dyn Error>> {
+        self.dependency_database()?
+            .insert(id.to_bytes(), bincode::serialize(dependencies)?)?;
+
+        Ok(())
+    }
+
+    fn get_build_ids(&self) -> Result<Vec<BuildId>, Box<dyn Error>> {
+        let mut ids = HashSet::new();
+
+        for tree in HashType::ALL
+            .into_iter()
+            .map(|r#type| self.hash_database(r#type))
+            .chain([self.dependency_database()])
+        {
+            for key in tree?.iter().keys() {
+                ids.insert(BuildId::from_bytes(key?.as_ref().try_into()?));
+            }
+        }
+
+        Ok(ids.into_iter().collect())
+    }
+
+    fn remove_build(&self, id: BuildId) -> Result<(), Box<dyn Error>> {
+        for r#type in HashType::ALL {
+            self.hash_database(r#type)?.remove(id.to_bytes())?;
+        }
+
+        self.dependency_database()?.remove(id.to_bytes())?;
+
+        Ok(())
+    }
+
     async fn flush(&self) -> Result<(), Box<dyn Error>> {
         let database = self.database()?;
         database.flush_async().await?;
@@ -116,6 +279,58 @@ impl Database for OsDatabase {
     }
 }
 
+fn copy_quiescent_directory(source: &Path, destination: &Path) -> Result<(), Box<dyn Error>> {
+    for _ in 0..SNAPSHOT_ATTEMPT_COUNT {
+        let states = file_states(source)?;
+
+        copy_directory(source, destination)?;
+
+        if file_states(source)? == states {
+            return Ok(());
+        }
+
+        fs::remove_dir_all(destination)?;
+    }
+
+    Err("database kept being written during snapshot".into())
+}
+
+fn file_states(path: &Path) -> io::Result<Vec<(PathBuf, u64, SystemTime)>> {
+    let mut states = vec![];
+
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+
+        if metadata.is_dir() {
+            states.extend(file_states(&entry.path())?);
+        } else {
+            states.push((entry.path(), metadata.len(), metadata.modified()?));
+        }
+    }
+
+    states.sort();
+
+    Ok(states)
+}
+
+fn copy_directory(source: &Path, destination: &Path) -> io::Result<()> {
+    fs::create_dir_all(destination)?;
+
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let path = destination.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() {
+            copy_directory(&entry.path(), &path)?;
+        } else {
+            fs::copy(entry.path(), path)?;
+        }
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -127,6 +342,80 @@ mod tests {
         database.initialize(tempdir().unwrap().path()).unwrap();
     }
 
+    #[test]
+    fn initialize_twice() {
+        let directory = tempdir().unwrap();
+        let database = OsDatabase::new();
+
+        database.initialize(directory.path()).unwrap();
+        database.initialize(directory.path()).unwrap();
+    }
+
+    #[test]
+    fn initialize_temporary() {
+        let database = OsDatabase::new();
+        database.initialize_temporary().unwrap();
+
+        database.set_source("foo", "bar").unwrap();
+
+        assert_eq!(database.get_source("foo").unwrap(), Some("bar".into()));
+    }
+
+    #[tokio::test]
+    async fn initialize_read_only() {
+        let directory = tempdir().unwrap();
+        let database = OsDatabase::new();
+        database.initialize(directory.path()).unwrap();
+        database.set_source("foo", "bar").unwrap();
+        database.flush().await.unwrap();
+
+        let snapshot = OsDatabase::new();
+        snapshot.initialize_read_only(directory.path()).unwrap();
+
+        assert_eq!(snapshot.get_source("foo").unwrap(), Some("bar".into()));
+
+        snapshot.set_source("foo", "baz").unwrap();
+
+        assert_eq!(database.get_source("foo").unwrap(), Some("bar".into()));
+    }
+
+    #[tokio::test]
+    async fn remove_snapshot() {
+        let directory = tempdir().unwrap();
+        let database = OsDatabase::new();
+        database.initialize(directory.path()).unwrap();
+        database.set_source("foo", "bar").unwrap();
+        database.flush().await.unwrap();
+
+        let snapshot = OsDatabase::new();
+        snapshot.initialize_read_only(directory.path()).unwrap();
+
+        assert_eq!(snapshot.get_source("foo").unwrap(), Some("bar".into()));
+
+        let path = snapshot.snapshot_path.get().unwrap().clone();
+
+        assert!(path.exists());
+
+        snapshot.remove_snapshot().unwrap();
+
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn remove_no_snapshot() {
+        OsDatabase::new().remove_snapshot().unwrap();
+    }
+
+    #[test]
+    fn initialize_read_only_without_database() {
+        let database = OsDatabase::new();
+        database
+            .initialize_read_only(&tempdir().unwrap().path().join("foo"))
+            .unwrap();
+
+        assert_eq!(database.get_source("foo").unwrap(), None);
+    }
+
     #[tokio::test]
     async fn flush() {
         let database = OsDatabase::new();
@@ -180,6 +469,95 @@ mod tests {
         );
     }
 
+    #[test]
+    fn clear_hashes() {
+        let database = OsDatabase::new();
+        database.initialize(tempdir().unwrap().path()).unwrap();
+
+        database
+            .set_hash(HashType::Content, BuildId::new(0), 42)
+            .unwrap();
+        database
+            .set_hash(HashType::Timestamp, BuildId::new(0), 42)
+            .unwrap();
+        database.clear_hashes().unwrap();
+
+        assert_eq!(
+            database
+                .get_hash(HashType::Content, BuildId::new(0))
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            database
+                .get_hash(HashType::Timestamp, BuildId::new(0))
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn remove_build() {
+        let database = OsDatabase::new();
+        database.initialize(tempdir().unwrap().path()).unwrap();
+
+        for r#type in HashType::ALL {
+            database.set_hash(r#type, BuildId::new(0), 42).unwrap();
+        }
+        database
+            .set_dependencies(BuildId::new(0), &["foo".into()])
+            .unwrap();
+        database
+            .set_hash(HashType::Command, BuildId::new(1), 42)
+            .unwrap();
+
+        let mut ids = database.get_build_ids().unwrap();
+        ids.sort_by_key(|id| id.to_bytes());
+
+        assert_eq!(ids, [BuildId::new(0), BuildId::new(1)]);
+
+        database.remove_build(BuildId::new(0)).unwrap();
+
+        assert_eq!(database.get_build_ids().unwrap(), [BuildId::new(1)]);
+
+        for r#type in HashType::ALL {
+            assert_eq!(database.get_hash(r#type, BuildId::new(0)).unwrap(), None);
+        }
+        assert_eq!(database.get_dependencies(BuildId::new(0)).unwrap(), None);
+    }
+
+    #[test]
+    fn hash_algorithm() {
+        let database = OsDatabase::new();
+        database.initialize(tempdir().unwrap().path()).unwrap();
+
+        assert_eq!(database.get_hash_algorithm().unwrap(), None);
+
+        database.set_hash_algorithm("foo").unwrap();
+
+        assert_eq!(database.get_hash_algorithm().unwrap(), Some("foo".into()));
+    }
+
+    #[test]
+    fn hashes_of_different_builds() {
+        let database = OsDatabase::new();
+        database.initialize(tempdir().unwrap().path()).unwrap();
+
+        let ids = [BuildId::new(1), BuildId::new(1 << 64 | 1)];
+
+        database.set_hash(HashType::Content, ids[0], 1).unwrap();
+        database.set_hash(HashType::Content, ids[1], 2).unwrap();
+
+        assert_eq!(
+            database.get_hash(HashType::Content, ids[0]).unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            database.get_hash(HashType::Content, ids[1]).unwrap(),
+            Some(2)
+        );
+    }
+
     #[test]
     fn set_output() {
         let database = OsDatabase::new();
@@ -198,6 +576,17 @@ mod tests {
         assert_eq!(database.get_outputs().unwrap(), vec!["foo"]);
     }
 
+    #[test]
+    fn remove_output() {
+        let database = OsDatabase::new();
+        database.initialize(tempdir().unwrap().path()).unwrap();
+
+        database.set_output("foo").unwrap();
+        database.remove_output("foo").unwrap();
+
+        assert_eq!(database.get_outputs().unwrap(), Vec::<String>::new());
+    }
+
     #[test]
     fn set_source() {
         let database = OsDatabase::new();
@@ -215,4 +604,21 @@ mod tests {
 
         assert_eq!(database.get_source("foo").unwrap(), Some("bar".into()));
     }
+
+    #[test]
+    fn get_dependencies() {
+        let database = OsDatabase::new();
+        database.initialize(tempdir().unwrap().path()).unwrap();
+
+        assert_eq!(database.get_dependencies(BuildId::new(0)).unwrap(), None);
+
+        database
+            .set_dependencies(BuildId::new(0), &["foo".into(), "bar".into()])
+            .unwrap();
+
+        assert_eq!(
+            database.get_dependencies(BuildId::new(0)).unwrap(),
+            Some(vec!["foo".into(), "bar".into()])
+        );
+    }
 }
@@ -1,13 +1,14 @@
 mod metadata;
 
+use crate::path::normalize_path;
 use async_trait::async_trait;
-use dashmap::DashSet;
-use metadata::Metadata;
+use dashmap::{DashMap, DashSet};
+pub use metadata::Metadata;
 use std::{
     error::Error,
     fmt::Debug,
     io,
-    path::{Path, PathBuf},
+    path::{absolute, Path, PathBuf},
 };
 use tokio::{
     fs::{self, File},
@@ -16,15 +17,24 @@ use tokio::{
     task::yield_now,
 };
 
+#[allow(clippy::double_must_use)]
 #[async_trait]
 pub trait FileSystem {
-    async fn read_file(&self, path: &Path, buffer: &mut Vec<u8>) -> Result<(), Box<dyn Error>>;
+    // Reads a file chunk by chunk with a buffer of a fixed size.
+    async fn read_file_chunks(
+        &self,
+        path: &Path,
+        buffer: &mut [u8],
+        consume: &mut (dyn for<'a> FnMut(&'a [u8]) + Send),
+    ) -> Result<(), Box<dyn Error>>;
     async fn read_file_to_string(
         &self,
         path: &Path,
         buffer: &mut String,
     ) -> Result<(), Box<dyn Error>>;
     async fn metadata(&self, path: &Path) -> Result<Metadata, Box<dyn Error>>;
+    async fn write_file(&self, path: &Path, content: &[u8]) -> Result<(), Box<dyn Error>>;
+    async fn remove_file(&self, path: &Path) -> Result<(), Box<dyn Error>>;
     async fn create_directory(&self, path: &Path) -> Result<(), Box<dyn Error>>;
     async fn canonicalize_path(&self, path: &Path) -> Result<PathBuf, Box<dyn Error>>;
 }
@@ -32,26 +42,43 @@ pub trait FileSystem {
 #[derive(Debug)]
 pub struct OsFileSystem {
     path_lock: DashSet<PathBuf>,
+    canonical_paths: DashMap<PathBuf, PathBuf>,
     semaphore: Semaphore,
+    canonicalize: bool,
 }
 
 impl OsFileSystem {
-    pub fn new(open_file_limit: usize) -> Self {
+    pub fn new(open_file_limit: usize, canonicalize: bool) -> Self {
         Self {
             path_lock: DashSet::default(),
+            canonical_paths: DashMap::default(),
             semaphore: Semaphore::new(open_file_limit),
+            canonicalize,
         }
     }
 
-    async fn read_file(&self, path: &Path, buffer: &mut Vec<u8>) -> Result<(), Box<dyn Error>> {
-        File::open(path)
-            .await
-            .map_err(|error| Self::error(error, path))?
-            .read_to_end(buffer)
+    async fn read_file_chunks(
+        &self,
+        path: &Path,
+        buffer: &mut [u8],
+        consume: &mut (dyn for<'a> FnMut(&'a [u8]) + Send),
+    ) -> Result<(), Box<dyn Error>> {
+        let mut file = File::open(path)
             .await
             .map_err(|error| Self::error(error, path))?;
 
-        Ok(())
+        loop {
+            let size = file
+                .read(buffer)
+                .await
+                .map_err(|error| Self::error(error, path))?;
+
+            if size == 0 {
+                return Ok(());
+            }
+
+            consume(&buffer[..size]);
+        }
     }
 
     async fn read_file_to_string(
@@ -69,20 +96,25 @@ impl OsFileSystem {
         Ok(())
     }
 
-    fn error(error: io::Error, path: &Path) -> String {
-        format!("{}: {}", error, path.display())
+    fn error(error: io::Error, path: &Path) -> io::Error {
+        io::Error::new(error.kind(), format!("{}: {}", error, path.display()))
     }
 }
 
 #[async_trait]
 impl FileSystem for OsFileSystem {
-    async fn read_file(&self, path: &Path, buffer: &mut Vec<u8>) -> Result<(), Box<dyn Error>> {
+    async fn read_file_chunks(
+        &self,
+        path: &Path,
+        buffer: &mut [u8],
+        consume: &mut (dyn for<'a> FnMut(&'a [u8]) + Send),
+    ) -> Result<(), Box<dyn Error>> {
         while !self.path_lock.insert(path.into()) {
             yield_now().await;
         }
 
         let permit = self.semaphore.acquire().await?;
-        let result = self.read_file(path, buffer).await;
+        let result = self.read_file_chunks(path, buffer, consume).await;
         drop(permit);
 
         self.path_lock.remove(path);
@@ -115,6 +147,24 @@ impl FileSystem for OsFileSystem {
             .try_into()?)
     }
 
+    async fn write_file(&self, path: &Path, content: &[u8]) -> Result<(), Box<dyn Error>> {
+        let permit = self.semaphore.acquire().await?;
+        let result = fs::write(path, content)
+            .await
+            .map_err(|error| Self::error(error, path));
+        drop(permit);
+
+        Ok(result?)
+    }
+
+    async fn remove_file(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        fs::remove_file(path)
+            .await
+            .map_err(|error| Self::error(error, path))?;
+
+        Ok(())
+    }
+
     async fn create_directory(&self, path: &Path) -> Result<(), Box<dyn Error>> {
         fs::create_dir_all(path)
             .await
@@ -123,9 +173,127 @@ impl FileSystem for OsFileSystem {
         Ok(())
     }
 
+    // Canonical paths are cached as file system layouts are assumed to be stable
+    // during builds. Relative paths are not cached as they depend on a current
+    // directory.
     async fn canonicalize_path(&self, path: &Path) -> Result<PathBuf, Box<dyn Error>> {
-        Ok(fs::canonicalize(path)
+        if let Some(path) = self.canonical_paths.get(path) {
+            return Ok(path.clone());
+        }
+
+        let canonical_path = if self.canonicalize {
+            fs::canonicalize(path)
+                .await
+                .map_err(|error| Self::error(error, path))?
+        } else {
+            normalize_path(&absolute(path).map_err(|error| Self::error(error, path))?)
+        };
+
+        if path.is_absolute() {
+            self.canonical_paths
+                .insert(path.into(), canonical_path.clone());
+        }
+
+        Ok(canonical_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn canonicalize_symbolic_link() {
+        let directory = tempdir().unwrap();
+        let directory = fs::canonicalize(directory.path()).await.unwrap();
+        let file = directory.join("foo.ninja");
+        let link = directory.join("bar.ninja");
+
+        fs::write(&file, "").await.unwrap();
+        fs::symlink(&file, &link).await.unwrap();
+
+        assert_eq!(
+            OsFileSystem::new(1, true)
+                .canonicalize_path(&link)
+                .await
+                .unwrap(),
+            file
+        );
+        assert_eq!(
+            OsFileSystem::new(1, false)
+                .canonicalize_path(&link)
+                .await
+                .unwrap(),
+            link
+        );
+    }
+
+    #[tokio::test]
+    async fn normalize_path_lexically() {
+        let directory = tempdir().unwrap();
+        let directory = fs::canonicalize(directory.path()).await.unwrap();
+
+        assert_eq!(
+            OsFileSystem::new(1, false)
+                .canonicalize_path(&directory.join("foo/../bar/./baz.ninja"))
+                .await
+                .unwrap(),
+            directory.join("bar/baz.ninja")
+        );
+    }
+
+    #[tokio::test]
+    async fn cache_canonical_path() {
+        let directory = tempdir().unwrap();
+        let directory = fs::canonicalize(directory.path()).await.unwrap();
+        let file = directory.join("foo.ninja");
+        let file_system = OsFileSystem::new(1, true);
+
+        fs::write(&file, "").await.unwrap();
+
+        assert_eq!(file_system.canonicalize_path(&file).await.unwrap(), file);
+
+        fs::remove_file(&file).await.unwrap();
+
+        assert_eq!(file_system.canonicalize_path(&file).await.unwrap(), file);
+    }
+
+    #[tokio::test]
+    async fn write_and_remove_file() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("foo");
+        let file_system = OsFileSystem::new(1, true);
+
+        file_system.write_file(&path, b"foo").await.unwrap();
+
+        let mut buffer = String::new();
+        FileSystem::read_file_to_string(&file_system, &path, &mut buffer)
             .await
-            .map_err(|error| Self::error(error, path))?)
+            .unwrap();
+        assert_eq!(buffer, "foo");
+
+        file_system.remove_file(&path).await.unwrap();
+
+        assert!(file_system.metadata(&path).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn read_file_chunks() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("foo");
+        let file_system = OsFileSystem::new(1, true);
+
+        file_system.write_file(&path, b"foobarbaz").await.unwrap();
+
+        let mut chunks = vec![];
+        FileSystem::read_file_chunks(&file_system, &path, &mut [0; 4], &mut |chunk| {
+            chunks.push(chunk.to_vec())
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(chunks.concat(), b"foobarbaz");
+        assert!(chunks.iter().all(|chunk| chunk.len() <= 4));
     }
 }
@@ -2,13 +2,15 @@ use std::{fs, io, time::SystemTime};
 
 pub struct Metadata {
     modified_time: SystemTime,
+    size: u64,
     directory: bool,
 }
 
 impl Metadata {
-    pub fn new(modified_time: SystemTime, directory: bool) -> Self {
+    pub fn new(modified_time: SystemTime, size: u64, directory: bool) -> Self {
         Self {
             modified_time,
+            size,
             directory,
         }
     }
@@ -17,6 +19,10 @@ impl Metadata {
         self.modified_time
     }
 
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
     pub fn is_file(&self) -> bool {
         !self.directory
     }
@@ -26,6 +32,10 @@ impl TryFrom<fs::Metadata> for Metadata {
     type Error = io::Error;
 
     fn try_from(metadata: fs::Metadata) -> Result<Self, Self::Error> {
-        Ok(Metadata::new(metadata.modified()?, metadata.is_dir()))
+        Ok(Metadata::new(
+            metadata.modified()?,
+            metadata.len(),
+            metadata.is_dir(),
+        ))
     }
 }
@@ -0,0 +1,290 @@
+use super::{FileSystem, Metadata};
+use crate::path::normalize_path;
+use async_trait::async_trait;
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    io,
+    path::{Component, Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+// A file system on memory for deterministic tests. Relative paths are resolved
+// from a root directory and files are modified at a clock advanced manually.
+#[derive(Debug)]
+pub struct InMemoryFileSystem {
+    files: Mutex<HashMap<PathBuf, (Vec<u8>, SystemTime)>>,
+    directories: Mutex<HashSet<PathBuf>>,
+    written_paths: Mutex<Vec<PathBuf>>,
+    time: Mutex<SystemTime>,
+}
+
+impl InMemoryFileSystem {
+    pub fn new() -> Self {
+        Self {
+            files: Default::default(),
+            directories: Mutex::new([Self::root()].into_iter().collect()),
+            written_paths: Default::default(),
+            time: Mutex::new(SystemTime::UNIX_EPOCH),
+        }
+    }
+
+    // Seeds a file without recording it as written.
+    pub fn with_file(self, path: impl AsRef<Path>, content: impl Into<Vec<u8>>) -> Self {
+        let path = Self::resolve(path.as_ref());
+        let time = *self.time.lock().unwrap();
+
+        self.files
+            .lock()
+            .unwrap()
+            .insert(path, (content.into(), time));
+
+        self
+    }
+
+    // Advances a clock so that files written later have newer modified times.
+    pub fn advance_time(&self, duration: Duration) {
+        *self.time.lock().unwrap() += duration;
+    }
+
+    pub fn file(&self, path: impl AsRef<Path>) -> Option<Vec<u8>> {
+        self.files
+            .lock()
+            .unwrap()
+            .get(&Self::resolve(path.as_ref()))
+            .map(|(content, _)| content.clone())
+    }
+
+    // Returns paths of files written since creation in order.
+    pub fn written_paths(&self) -> Vec<PathBuf> {
+        self.written_paths.lock().unwrap().clone()
+    }
+
+    fn read_file(&self, path: &Path) -> Result<Vec<u8>, io::Error> {
+        self.file(path).ok_or_else(|| Self::not_found(path))
+    }
+
+    fn resolve(path: &Path) -> PathBuf {
+        normalize_path(&Self::root().join(path))
+    }
+
+    fn root() -> PathBuf {
+        Component::RootDir.as_os_str().into()
+    }
+
+    fn not_found(path: &Path) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("file not found: {}", path.display()),
+        )
+    }
+}
+
+impl Default for InMemoryFileSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl FileSystem for InMemoryFileSystem {
+    async fn read_file_chunks(
+        &self,
+        path: &Path,
+        buffer: &mut [u8],
+        consume: &mut (dyn for<'a> FnMut(&'a [u8]) + Send),
+    ) -> Result<(), Box<dyn Error>> {
+        for chunk in self.read_file(path)?.chunks(buffer.len().max(1)) {
+            buffer[..chunk.len()].copy_from_slice(chunk);
+            consume(&buffer[..chunk.len()]);
+        }
+
+        Ok(())
+    }
+
+    async fn read_file_to_string(
+        &self,
+        path: &Path,
+        buffer: &mut String,
+    ) -> Result<(), Box<dyn Error>> {
+        buffer.push_str(&String::from_utf8(self.read_file(path)?)?);
+
+        Ok(())
+    }
+
+    async fn metadata(&self, path: &Path) -> Result<Metadata, Box<dyn Error>> {
+        let path = Self::resolve(path);
+
+        if let Some((content, time)) = self.files.lock().unwrap().get(&path) {
+            Ok(Metadata::new(*time, content.len() as u64, false))
+        } else if self.directories.lock().unwrap().contains(&path) {
+            Ok(Metadata::new(SystemTime::UNIX_EPOCH, 0, true))
+        } else {
+            Err(Self::not_found(&path).into())
+        }
+    }
+
+    async fn write_file(&self, path: &Path, content: &[u8]) -> Result<(), Box<dyn Error>> {
+        let path = Self::resolve(path);
+
+        if !self
+            .directories
+            .lock()
+            .unwrap()
+            .contains(path.parent().unwrap_or(&path))
+        {
+            return Err(Self::not_found(&path).into());
+        }
+
+        let time = *self.time.lock().unwrap();
+
+        self.files
+            .lock()
+            .unwrap()
+            .insert(path.clone(), (content.into(), time));
+        self.written_paths.lock().unwrap().push(path);
+
+        Ok(())
+    }
+
+    async fn remove_file(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        self.files
+            .lock()
+            .unwrap()
+            .remove(&Self::resolve(path))
+            .ok_or_else(|| Self::not_found(path))?;
+
+        Ok(())
+    }
+
+    async fn create_directory(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut directories = self.directories.lock().unwrap();
+
+        for path in Self::resolve(path).ancestors() {
+            directories.insert(path.into());
+        }
+
+        Ok(())
+    }
+
+    async fn canonicalize_path(&self, path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+        Ok(Self::resolve(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn read_seeded_file() {
+        let file_system = InMemoryFileSystem::new().with_file("foo", "bar");
+        let mut string = String::new();
+
+        file_system
+            .read_file_to_string(Path::new("/foo"), &mut string)
+            .await
+            .unwrap();
+
+        assert_eq!(string, "bar");
+        assert!(file_system.written_paths().is_empty());
+    }
+
+    #[tokio::test]
+    async fn read_file_chunks() {
+        let file_system = InMemoryFileSystem::new().with_file("foo", "foobar");
+        let mut chunks = vec![];
+
+        file_system
+            .read_file_chunks(Path::new("foo"), &mut [0; 4], &mut |chunk| {
+                chunks.push(chunk.to_vec())
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(chunks, [b"foob".to_vec(), b"ar".to_vec()]);
+    }
+
+    #[tokio::test]
+    async fn fail_to_read_missing_file() {
+        assert!(InMemoryFileSystem::new()
+            .read_file_to_string(Path::new("foo"), &mut String::new())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn write_file() {
+        let file_system = InMemoryFileSystem::new();
+
+        file_system
+            .write_file(Path::new("./foo"), b"bar")
+            .await
+            .unwrap();
+
+        assert_eq!(file_system.file("foo"), Some(b"bar".to_vec()));
+        assert_eq!(file_system.written_paths(), [PathBuf::from("/foo")]);
+    }
+
+    #[tokio::test]
+    async fn fail_to_write_file_in_missing_directory() {
+        let file_system = InMemoryFileSystem::new();
+
+        assert!(file_system
+            .write_file(Path::new("foo/bar"), b"")
+            .await
+            .is_err());
+
+        file_system
+            .create_directory(Path::new("foo"))
+            .await
+            .unwrap();
+        file_system
+            .write_file(Path::new("foo/bar"), b"")
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn advance_modified_time() {
+        let file_system = InMemoryFileSystem::new().with_file("foo", "");
+
+        file_system.advance_time(Duration::from_secs(1));
+        file_system.write_file(Path::new("bar"), b"").await.unwrap();
+
+        assert!(
+            file_system
+                .metadata(Path::new("foo"))
+                .await
+                .unwrap()
+                .modified_time()
+                < file_system
+                    .metadata(Path::new("bar"))
+                    .await
+                    .unwrap()
+                    .modified_time()
+        );
+    }
+
+    #[tokio::test]
+    async fn remove_file() {
+        let file_system = InMemoryFileSystem::new().with_file("foo", "");
+
+        file_system.remove_file(Path::new("foo")).await.unwrap();
+
+        assert!(file_system.metadata(Path::new("foo")).await.is_err());
+        assert!(file_system.remove_file(Path::new("foo")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn canonicalize_path() {
+        assert_eq!(
+            InMemoryFileSystem::new()
+                .canonicalize_path(Path::new("foo/../bar"))
+                .await
+                .unwrap(),
+            Path::new("/bar")
+        );
+    }
+}
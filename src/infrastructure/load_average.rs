@@ -0,0 +1,26 @@
+use tokio::fs::read_to_string;
+
+const LOAD_AVERAGE_PATH: &str = "/proc/loadavg";
+
+// Reads a 1-minute load average of a system. It returns `None` on platforms
+// where it is not available.
+pub async fn load_average() -> Option<f64> {
+    read_to_string(LOAD_AVERAGE_PATH)
+        .await
+        .ok()?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn read_load_average() {
+        assert!(load_average().await.unwrap() >= 0.0);
+    }
+}
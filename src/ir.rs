@@ -1,11 +1,17 @@
 mod build;
 mod configuration;
+mod dependency_format;
 mod dynamic_build;
 mod dynamic_configuration;
+mod environment;
+mod response_file;
 mod rule;
 
 pub use build::*;
 pub use configuration::*;
+pub use dependency_format::*;
 pub use dynamic_build::*;
 pub use dynamic_configuration::*;
+pub use environment::*;
+pub use response_file::*;
 pub use rule::*;
@@ -1,32 +1,37 @@
 use super::Rule;
-use std::{
-    collections::hash_map::DefaultHasher,
-    hash::{Hash, Hasher},
-    sync::Arc,
-};
+use crate::hasher::Fnv128Hasher;
+use std::{hash::Hash, sync::Arc};
 
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
-pub struct BuildId(u64);
+pub struct BuildId(u128);
 
 impl BuildId {
-    pub fn new(id: u64) -> Self {
+    pub fn new(id: u128) -> Self {
         Self(id)
     }
 
-    pub fn to_bytes(self) -> [u8; 8] {
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        Self(u128::from_le_bytes(bytes))
+    }
+
+    pub fn to_bytes(self) -> [u8; 16] {
         self.0.to_le_bytes()
     }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Build {
     // IDs are persistent across different builds so that they can be used for,
     // for example, caching.
+    #[cfg_attr(feature = "serde", serde(skip))]
     id: BuildId,
     outputs: Vec<Arc<str>>,
     implicit_outputs: Vec<Arc<str>>,
     rule: Option<Rule>,
+    // Implicit inputs follow explicit ones.
     inputs: Vec<Arc<str>>,
+    implicit_input_count: usize,
     order_only_inputs: Vec<Arc<str>>,
     dynamic_module: Option<Arc<str>>,
 }
@@ -46,11 +51,19 @@ impl Build {
             implicit_outputs,
             rule,
             inputs,
+            implicit_input_count: 0,
             order_only_inputs,
             dynamic_module,
         }
     }
 
+    pub fn with_implicit_input_count(self, count: usize) -> Self {
+        Self {
+            implicit_input_count: count,
+            ..self
+        }
+    }
+
     pub fn id(&self) -> BuildId {
         self.id
     }
@@ -71,6 +84,14 @@ impl Build {
         &self.inputs
     }
 
+    pub fn explicit_inputs(&self) -> &[Arc<str>] {
+        &self.inputs[..self.inputs.len() - self.implicit_input_count]
+    }
+
+    pub fn implicit_inputs(&self) -> &[Arc<str>] {
+        &self.inputs[self.inputs.len() - self.implicit_input_count..]
+    }
+
     pub fn order_only_inputs(&self) -> &[Arc<str>] {
         &self.order_only_inputs
     }
@@ -79,12 +100,59 @@ impl Build {
         self.dynamic_module.as_ref()
     }
 
+    // IDs are 128-bit so that different builds never share the same entries in
+    // databases in practice.
     fn calculate_id(outputs: &[Arc<str>], implicit_outputs: &[Arc<str>]) -> BuildId {
-        let mut hasher = DefaultHasher::new();
+        let mut hasher = Fnv128Hasher::new();
 
         outputs.hash(&mut hasher);
         implicit_outputs.hash(&mut hasher);
 
-        BuildId::new(hasher.finish())
+        BuildId::new(hasher.finish_u128())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calculate_stable_id() {
+        assert_eq!(
+            Build::new(vec!["foo".into()], vec![], None, vec![], vec![], None).id(),
+            BuildId::new(141669855849975608356753761540419062385)
+        );
+    }
+
+    #[test]
+    fn calculate_different_ids() {
+        assert_ne!(
+            Build::new(vec!["foo".into()], vec![], None, vec![], vec![], None).id(),
+            Build::new(vec!["bar".into()], vec![], None, vec![], vec![], None).id(),
+        );
+        assert_ne!(
+            Build::new(vec!["foo".into()], vec![], None, vec![], vec![], None).id(),
+            Build::new(vec![], vec!["foo".into()], None, vec![], vec![], None).id(),
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize() {
+        assert_eq!(
+            serde_json::to_value(
+                Build::new(
+                    vec!["foo".into()],
+                    vec![],
+                    Some(Rule::new("cc", None)),
+                    vec!["bar".into()],
+                    vec![],
+                    None,
+                )
+                .with_implicit_input_count(1)
+            )
+            .unwrap()["implicit_input_count"],
+            1
+        );
     }
 }
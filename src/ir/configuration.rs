@@ -5,10 +5,12 @@ use std::{
 };
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Configuration {
     outputs: HashMap<Arc<str>, Arc<Build>>,
     default_outputs: HashSet<Arc<str>>,
     source_map: HashMap<Arc<str>, Arc<str>>,
+    pools: HashMap<Arc<str>, usize>,
     build_directory: Option<Arc<str>>,
 }
 
@@ -17,12 +19,14 @@ impl Configuration {
         outputs: HashMap<Arc<str>, Arc<Build>>,
         default_outputs: HashSet<Arc<str>>,
         source_map: HashMap<Arc<str>, Arc<str>>,
+        pools: HashMap<Arc<str>, usize>,
         build_directory: Option<Arc<str>>,
     ) -> Self {
         Self {
             outputs,
             default_outputs,
             source_map,
+            pools,
             build_directory,
         }
     }
@@ -39,7 +43,43 @@ impl Configuration {
         &self.source_map
     }
 
+    pub fn pools(&self) -> &HashMap<Arc<str>, usize> {
+        &self.pools
+    }
+
     pub fn build_directory(&self) -> Option<&Arc<str>> {
         self.build_directory.as_ref()
     }
 }
+
+#[cfg(test)]
+impl Configuration {
+    // Creates a configuration with builds indexed by all of their outputs.
+    pub fn from_builds(builds: Vec<Build>) -> Self {
+        Self::new(
+            builds
+                .into_iter()
+                .map(Arc::new)
+                .flat_map(|build| {
+                    build
+                        .outputs()
+                        .iter()
+                        .chain(build.implicit_outputs())
+                        .map(|output| (output.clone(), build.clone()))
+                        .collect::<Vec<_>>()
+                })
+                .collect(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            None,
+        )
+    }
+
+    pub fn with_default_outputs(self, outputs: &[&str]) -> Self {
+        Self {
+            default_outputs: outputs.iter().map(|&output| output.into()).collect(),
+            ..self
+        }
+    }
+}
@@ -0,0 +1,12 @@
+pub const DEFAULT_MSVC_DEPENDENCY_PREFIX: &str = "Note: including file:";
+
+// Formats of dependencies discovered by commands, which correspond to values
+// of `deps` variables.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum DependencyFormat {
+    Gcc,
+    // Dependencies are printed to stdout with prefixes by `/showIncludes`
+    // options of MSVC.
+    Msvc(String),
+}
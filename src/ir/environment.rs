@@ -0,0 +1,21 @@
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Environment {
+    variables: Vec<(String, String)>,
+    clear: bool,
+}
+
+impl Environment {
+    pub fn new(variables: Vec<(String, String)>, clear: bool) -> Self {
+        Self { variables, clear }
+    }
+
+    pub fn variables(&self) -> &[(String, String)] {
+        &self.variables
+    }
+
+    // Whether environment variables of a parent process are not inherited
+    pub fn clear(&self) -> bool {
+        self.clear
+    }
+}
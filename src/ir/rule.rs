@@ -1,17 +1,102 @@
+use super::{DependencyFormat, Environment, ResponseFile};
+use std::sync::Arc;
+
+pub const CONSOLE_POOL: &str = "console";
+
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Rule {
+    name: Option<Arc<str>>,
     command: String,
     description: Option<String>,
+    pool: Option<Arc<str>>,
+    depfile: Option<String>,
+    dependency_format: Option<DependencyFormat>,
+    response_file: Option<ResponseFile>,
+    directory: Option<String>,
+    environment: Environment,
+    restat: bool,
+    generator: bool,
 }
 
 impl Rule {
     pub fn new(command: impl Into<String>, description: Option<String>) -> Self {
         Self {
+            name: None,
             command: command.into(),
             description,
+            pool: None,
+            depfile: None,
+            dependency_format: None,
+            response_file: None,
+            directory: None,
+            environment: Default::default(),
+            restat: false,
+            generator: false,
+        }
+    }
+
+    pub fn with_name(self, name: impl Into<Arc<str>>) -> Self {
+        Self {
+            name: Some(name.into()),
+            ..self
+        }
+    }
+
+    pub fn with_pool(self, pool: impl Into<Arc<str>>) -> Self {
+        Self {
+            pool: Some(pool.into()),
+            ..self
+        }
+    }
+
+    pub fn with_depfile(self, depfile: impl Into<String>) -> Self {
+        Self {
+            depfile: Some(depfile.into()),
+            ..self
+        }
+    }
+
+    pub fn with_dependency_format(self, format: DependencyFormat) -> Self {
+        Self {
+            dependency_format: Some(format),
+            ..self
+        }
+    }
+
+    pub fn with_response_file(self, response_file: ResponseFile) -> Self {
+        Self {
+            response_file: Some(response_file),
+            ..self
+        }
+    }
+
+    pub fn with_directory(self, directory: impl Into<String>) -> Self {
+        Self {
+            directory: Some(directory.into()),
+            ..self
+        }
+    }
+
+    pub fn with_environment(self, environment: Environment) -> Self {
+        Self {
+            environment,
+            ..self
         }
     }
 
+    pub fn with_restat(self, restat: bool) -> Self {
+        Self { restat, ..self }
+    }
+
+    pub fn with_generator(self, generator: bool) -> Self {
+        Self { generator, ..self }
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
     pub fn command(&self) -> &str {
         &self.command
     }
@@ -19,4 +104,41 @@ impl Rule {
     pub fn description(&self) -> Option<&str> {
         self.description.as_deref()
     }
+
+    pub fn pool(&self) -> Option<&Arc<str>> {
+        self.pool.as_ref()
+    }
+
+    pub fn depfile(&self) -> Option<&str> {
+        self.depfile.as_deref()
+    }
+
+    pub fn dependency_format(&self) -> Option<&DependencyFormat> {
+        self.dependency_format.as_ref()
+    }
+
+    pub fn response_file(&self) -> Option<&ResponseFile> {
+        self.response_file.as_ref()
+    }
+
+    // A working directory of a command
+    pub fn directory(&self) -> Option<&str> {
+        self.directory.as_deref()
+    }
+
+    pub fn environment(&self) -> &Environment {
+        &self.environment
+    }
+
+    pub fn restat(&self) -> bool {
+        self.restat
+    }
+
+    pub fn generator(&self) -> bool {
+        self.generator
+    }
+
+    pub fn is_console(&self) -> bool {
+        self.pool.as_deref() == Some(CONSOLE_POOL)
+    }
 }
@@ -0,0 +1,45 @@
+use std::fmt::Write;
+
+// Quotes a string as a JSON string literal.
+pub fn quote(string: &str) -> String {
+    let mut quoted = String::with_capacity(string.len() + 2);
+
+    quoted.push('"');
+
+    for character in string.chars() {
+        match character {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            character if character.is_control() => {
+                write!(quoted, "\\u{:04x}", character as u32).unwrap()
+            }
+            character => quoted.push(character),
+        }
+    }
+
+    quoted.push('"');
+
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quote_string() {
+        assert_eq!(quote(""), r#""""#);
+        assert_eq!(quote("foo"), r#""foo""#);
+    }
+
+    #[test]
+    fn quote_special_characters() {
+        assert_eq!(quote(r#"a"b"#), r#""a\"b""#);
+        assert_eq!(quote(r"C:\foo"), r#""C:\\foo""#);
+        assert_eq!(quote("a\nb\tc"), r#""a\nb\tc""#);
+        assert_eq!(quote("\u{1}"), r#""\u0001""#);
+    }
+}
@@ -0,0 +1,47 @@
+mod application;
+mod arguments;
+mod ast;
+mod build_graph;
+mod color;
+mod compile;
+mod configuration_json;
+mod context;
+mod dead_output;
+mod downstream;
+mod error;
+mod error_json;
+mod glob;
+mod hash_algorithm;
+mod hash_type;
+mod hasher;
+mod ignore;
+mod infrastructure;
+mod ir;
+mod json;
+mod module_dependency;
+mod parse;
+mod path;
+mod run;
+mod tool;
+mod verbosity;
+mod warning;
+mod watch;
+
+pub use application::{main, parse_modules};
+pub use ast::Module;
+pub use compile::{compile, CompileError, CompileWarning, Options as CompileOptions};
+pub use context::Context;
+pub use error::ApplicationError;
+pub use hash_algorithm::HashAlgorithm;
+pub use infrastructure::{
+    BufferConsole, CommandRunner, Console, FileSystem, InMemoryFileSystem, Metadata,
+    OsCommandRunner, OsConsole, OsFileSystem,
+};
+pub use ir::{Build, Configuration, Environment, Rule};
+pub use module_dependency::{
+    validate as validate_modules, ModuleDependencyError, ModuleDependencyMap,
+};
+pub use parse::{parse, ParseError};
+pub use run::{run, Options as RunOptions};
+pub use verbosity::Verbosity;
+pub use warning::{WarningConfiguration, WarningKey, WarningLevel};
@@ -1,6 +1,5 @@
-use petgraph::{algo::is_cyclic_directed, Graph};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     error::Error,
     fmt::{self, Display, Formatter},
     path::{Path, PathBuf},
@@ -9,33 +8,64 @@ use std::{
 pub type ModuleDependencyMap = HashMap<PathBuf, HashMap<String, PathBuf>>;
 
 pub fn validate(modules: &ModuleDependencyMap) -> Result<(), ModuleDependencyError> {
-    if is_module_dependency_circular(modules) {
-        return Err(ModuleDependencyError::CircularDependency);
+    if let Some(cycle) = find_cycle(modules) {
+        return Err(ModuleDependencyError::CircularDependency(cycle));
     }
 
     Ok(())
 }
 
-fn is_module_dependency_circular(modules: &ModuleDependencyMap) -> bool {
-    let mut graph = Graph::<&Path, ()>::new();
-    let mut indices = HashMap::<&Path, _>::new();
-
-    for output in modules.keys() {
-        indices.insert(output, graph.add_node(output));
-    }
+// Finds a cycle of module inclusion by depth-first search with an explicit
+// stack. Modules are visited in order for deterministic results.
+fn find_cycle(modules: &ModuleDependencyMap) -> Option<Vec<PathBuf>> {
+    let submodules = |path: &Path| {
+        let mut paths = modules
+            .get(path)
+            .into_iter()
+            .flat_map(|submodules| submodules.values().map(PathBuf::as_path))
+            .collect::<Vec<_>>();
+
+        // Submodules are popped from the last.
+        paths.sort_by(|one, other| other.cmp(one));
+        paths
+    };
+    let mut roots = modules.keys().map(PathBuf::as_path).collect::<Vec<_>>();
+    let mut visited = HashSet::new();
+
+    roots.sort();
+
+    for root in roots {
+        if !visited.insert(root) {
+            continue;
+        }
 
-    for (output, build) in modules {
-        for input in build.values() {
-            graph.add_edge(indices[output.as_path()], indices[input.as_path()], ());
+        let mut stack = vec![(root, submodules(root))];
+
+        while let Some((_, paths)) = stack.last_mut() {
+            let Some(path) = paths.pop() else {
+                stack.pop();
+                continue;
+            };
+
+            if let Some(index) = stack.iter().position(|&(other, _)| other == path) {
+                return Some(
+                    stack[index..]
+                        .iter()
+                        .map(|(path, _)| path.to_path_buf())
+                        .collect(),
+                );
+            } else if visited.insert(path) {
+                stack.push((path, submodules(path)));
+            }
         }
     }
 
-    is_cyclic_directed(&graph)
+    None
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum ModuleDependencyError {
-    CircularDependency,
+    CircularDependency(Vec<PathBuf>),
 }
 
 impl Error for ModuleDependencyError {}
@@ -43,8 +73,17 @@ impl Error for ModuleDependencyError {}
 impl Display for ModuleDependencyError {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         match self {
-            Self::CircularDependency => {
-                write!(formatter, "build file dependency cycle detected")
+            Self::CircularDependency(cycle) => {
+                write!(
+                    formatter,
+                    "build file dependency cycle detected: {}",
+                    cycle
+                        .iter()
+                        .chain(cycle.first())
+                        .map(|path| path.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(" -> ")
+                )
             }
         }
     }
@@ -78,7 +117,9 @@ mod tests {
                 .into_iter()
                 .collect()
             ),
-            Err(ModuleDependencyError::CircularDependency)
+            Err(ModuleDependencyError::CircularDependency(
+                vec!["foo".into()]
+            ))
         );
     }
 
@@ -117,7 +158,46 @@ mod tests {
                 .into_iter()
                 .collect()
             ),
-            Err(ModuleDependencyError::CircularDependency)
+            Err(ModuleDependencyError::CircularDependency(vec![
+                "bar".into(),
+                "foo".into()
+            ]))
+        );
+    }
+
+    #[test]
+    fn validate_circular_modules_with_acyclic_one() {
+        assert_eq!(
+            validate(
+                &[
+                    (
+                        "foo".into(),
+                        [("bar".into(), "bar".into())].into_iter().collect()
+                    ),
+                    (
+                        "bar".into(),
+                        [("baz".into(), "baz".into())].into_iter().collect()
+                    ),
+                    (
+                        "baz".into(),
+                        [("bar".into(), "bar".into())].into_iter().collect()
+                    ),
+                ]
+                .into_iter()
+                .collect()
+            ),
+            Err(ModuleDependencyError::CircularDependency(vec![
+                "bar".into(),
+                "baz".into()
+            ]))
+        );
+    }
+
+    #[test]
+    fn display_circular_dependency() {
+        assert_eq!(
+            ModuleDependencyError::CircularDependency(vec!["foo".into(), "bar".into()]).to_string(),
+            "build file dependency cycle detected: foo -> bar -> foo"
         );
     }
 }
@@ -4,11 +4,105 @@ mod parser;
 pub use self::error::ParseError;
 use self::parser::{dynamic_module, module};
 use crate::ast::{DynamicModule, Module};
+use std::borrow::Cow;
+
+const BYTE_ORDER_MARK: char = '\u{feff}';
 
 pub fn parse(source: &str) -> Result<Module, ParseError> {
-    Ok(module(source).map(|(_, module)| module)?)
+    let source = normalize(source);
+
+    module(&source)
+        .map(|(_, module)| module)
+        .map_err(|error| convert_error(&source, error))
 }
 
 pub fn parse_dynamic(source: &str) -> Result<DynamicModule, ParseError> {
-    Ok(dynamic_module(source).map(|(_, module)| module)?)
+    let source = normalize(source);
+
+    dynamic_module(&source)
+        .map(|(_, module)| module)
+        .map_err(|error| convert_error(&source, error))
+}
+
+// Strips a byte order mark and converts CRLF into LF so that build files
+// written on Windows are parsed in the same way.
+fn normalize(source: &str) -> Cow<'_, str> {
+    let source = source.strip_prefix(BYTE_ORDER_MARK).unwrap_or(source);
+
+    if source.contains("\r\n") {
+        source.replace("\r\n", "\n").into()
+    } else {
+        source.into()
+    }
+}
+
+fn convert_error(source: &str, error: nom::Err<nom::error::Error<&str>>) -> ParseError {
+    let input = match error {
+        nom::Err::Error(error) | nom::Err::Failure(error) => error.input,
+        nom::Err::Incomplete(_) => "",
+    };
+
+    ParseError::new(
+        if let Some(line) = input.lines().next() {
+            format!("unexpected {:?}", line.trim_end())
+        } else {
+            "unexpected end of file".into()
+        },
+        source,
+        source.len() - input.len(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::VariableDefinition;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_crlf() {
+        assert_eq!(
+            parse("rule cc\r\n  command = cc $in\r\n\r\nbuild foo.o: cc foo.c\r\n").unwrap(),
+            parse("rule cc\n  command = cc $in\n\nbuild foo.o: cc foo.c\n").unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_crlf_in_variable_value() {
+        assert_eq!(
+            parse("x = foo\r\n").unwrap(),
+            Module::new(vec![VariableDefinition::new("x", "foo").into()])
+        );
+    }
+
+    #[test]
+    fn parse_byte_order_mark() {
+        assert_eq!(
+            parse("\u{feff}x = foo\n").unwrap(),
+            Module::new(vec![VariableDefinition::new("x", "foo").into()])
+        );
+    }
+
+    #[test]
+    fn parse_dynamic_module_with_crlf() {
+        assert!(parse_dynamic("\u{feff}ninja_dyndep_version = 1\r\nbuild foo: dyndep\r\n").is_ok());
+    }
+
+    #[test]
+    fn report_position_of_malformed_build() {
+        let error = parse("rule cc\n  command = cc\n\nbuild foo.o cc foo.c\n").unwrap_err();
+
+        assert_eq!((error.line(), error.column()), (4, 1));
+        assert_eq!(
+            error.to_string(),
+            "4:1: unexpected \"build foo.o cc foo.c\""
+        );
+    }
+
+    #[test]
+    fn report_position_of_malformed_dynamic_build() {
+        let error = parse_dynamic("ninja_dyndep_version = 1\nbuild foo dyndep\n").unwrap_err();
+
+        assert_eq!((error.line(), error.column()), (2, 1));
+    }
 }
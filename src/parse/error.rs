@@ -1,24 +1,49 @@
 use std::{
     error::Error,
     fmt::{self, Display},
+    path::{Path, PathBuf},
 };
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ParseError {
     message: String,
+    path: Option<PathBuf>,
+    line: usize,
+    column: usize,
 }
 
 impl ParseError {
-    pub fn new(message: impl Into<String>) -> Self {
+    // Lines and columns are 1-based and calculated from a byte offset in a
+    // source.
+    pub fn new(message: impl Into<String>, source: &str, offset: usize) -> Self {
+        let source = &source[..offset];
+        let line_start = source.rfind('\n').map(|index| index + 1).unwrap_or(0);
+
         Self {
             message: message.into(),
+            path: None,
+            line: source.matches('\n').count() + 1,
+            column: source[line_start..].chars().count() + 1,
         }
     }
-}
 
-impl From<nom::Err<nom::error::Error<&str>>> for ParseError {
-    fn from(error: nom::Err<nom::error::Error<&str>>) -> Self {
-        Self::new(error.to_string())
+    pub fn with_path(self, path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: Some(path.into()),
+            ..self
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
     }
 }
 
@@ -26,6 +51,33 @@ impl Error for ParseError {}
 
 impl Display for ParseError {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        write!(formatter, "{}", &self.message)
+        if let Some(path) = &self.path {
+            write!(formatter, "{}:", path.display())?;
+        }
+
+        write!(formatter, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calculate_position() {
+        let error = ParseError::new("", "foo\nbar baz", 8);
+
+        assert_eq!(error.line(), 2);
+        assert_eq!(error.column(), 5);
+    }
+
+    #[test]
+    fn display_with_path() {
+        assert_eq!(
+            ParseError::new("unexpected token", "foo", 1)
+                .with_path("build.ninja")
+                .to_string(),
+            "build.ninja:1:2: unexpected token"
+        );
     }
 }
@@ -1,35 +1,38 @@
 use crate::ast::{
-    Build, DefaultOutput, DynamicBuild, DynamicModule, Include, Module, Rule, Statement, Submodule,
-    VariableDefinition,
+    Build, DefaultOutput, DynamicBuild, DynamicModule, Include, Module, Pool, Rule, Statement,
+    Submodule, VariableDefinition,
 };
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete::{alpha1, alphanumeric1, line_ending, none_of, one_of, space1},
-    combinator::{all_consuming, into, map, not, opt, peek, recognize, value},
-    multi::{many0, many0_count, many1, many1_count},
+    character::complete::{
+        alpha1, alphanumeric1, char, line_ending, none_of, one_of, space0, space1,
+    },
+    combinator::{all_consuming, into, map, not, opt, peek, recognize, value, verify},
+    multi::{fold_many1, many0, many0_count, many1, many1_count},
     sequence::{delimited, preceded, terminated, tuple},
     IResult, Parser,
 };
 
 const OPERATOR_CHARACTERS: &str = "|:";
 const DYNAMIC_MODULE_VERSION_VARIABLE: &str = "ninja_dyndep_version";
+const COMMAND_VARIABLE: &str = "command";
 
 pub fn module(input: &str) -> IResult<&str, Module> {
     map(
-        all_consuming(tuple((opt(line_break), many0(statement), opt(line_break)))),
-        |(_, statements, _)| Module::new(statements),
+        all_consuming(preceded(many0_count(empty_line), many0(statement))),
+        Module::new,
     )(input)
 }
 
 pub fn dynamic_module(input: &str) -> IResult<&str, DynamicModule> {
     map(
         all_consuming(tuple((
-            opt(line_break),
+            many0_count(empty_line),
             dynamic_module_version,
             many0(dynamic_build),
         ))),
-        |(_, _, builds)| DynamicModule::new(builds),
+        |(_, version, builds)| DynamicModule::new(version, builds),
     )(input)
 }
 
@@ -38,6 +41,7 @@ fn statement(input: &str) -> IResult<&str, Statement> {
         into(build),
         into(default),
         into(include),
+        into(pool),
         into(rule),
         into(submodule),
         into(variable_definition),
@@ -51,7 +55,7 @@ fn variable_definition(input: &str) -> IResult<&str, VariableDefinition> {
     )(input)
 }
 
-fn dynamic_module_version(input: &str) -> IResult<&str, &str> {
+fn dynamic_module_version(input: &str) -> IResult<&str, String> {
     map(
         tuple((
             keyword(DYNAMIC_MODULE_VERSION_VARIABLE),
@@ -64,23 +68,37 @@ fn dynamic_module_version(input: &str) -> IResult<&str, &str> {
 }
 
 fn rule(input: &str) -> IResult<&str, Rule> {
+    verify(
+        map(
+            tuple((
+                keyword("rule"),
+                identifier,
+                line_break,
+                many0(preceded(indent, variable_definition)),
+            )),
+            |(_, name, _, variable_definitions)| Rule::new(name, variable_definitions),
+        ),
+        |rule| {
+            rule.variable_definitions()
+                .iter()
+                .any(|definition| definition.name() == COMMAND_VARIABLE)
+        },
+    )(input)
+}
+
+fn pool(input: &str) -> IResult<&str, Pool> {
     map(
         tuple((
-            keyword("rule"),
+            keyword("pool"),
             identifier,
             line_break,
             delimited(
-                tuple((indent, keyword("command"), sign("="))),
+                tuple((indent, keyword("depth"), sign("="))),
                 string_line,
                 line_break,
             ),
-            opt(delimited(
-                tuple((indent, keyword("description"), sign("="))),
-                string_line,
-                line_break,
-            )),
         )),
-        |(_, name, _, command, description)| Rule::new(name, command, description.map(From::from)),
+        |(_, name, _, depth)| Pool::new(name, depth),
     )(input)
 }
 
@@ -142,7 +160,7 @@ pub fn dynamic_build(input: &str) -> IResult<&str, DynamicBuild> {
 fn default(input: &str) -> IResult<&str, DefaultOutput> {
     map(
         tuple((keyword("default"), many1(string_literal), line_break)),
-        |(_, outputs, _)| DefaultOutput::new(outputs.into_iter().map(From::from).collect()),
+        |(_, outputs, _)| DefaultOutput::new(outputs),
     )(input)
 }
 
@@ -160,19 +178,41 @@ fn submodule(input: &str) -> IResult<&str, Submodule> {
     )(input)
 }
 
-fn string_line(input: &str) -> IResult<&str, &str> {
-    map(recognize(many1_count(none_of("\n"))), |string: &str| {
-        string.trim()
-    })(input)
+fn string_line(input: &str) -> IResult<&str, String> {
+    map(
+        fold_many1(
+            alt((
+                recognize(many1_count(none_of("$\n"))),
+                tag("$$"),
+                value("", line_continuation),
+                tag("$"),
+            )),
+            String::new,
+            |mut string, part| {
+                string.push_str(part);
+                string
+            },
+        ),
+        |string| string.trim().into(),
+    )(input)
 }
 
 fn string_literal(input: &str) -> IResult<&str, String> {
-    map(
-        token(recognize(many1_count(none_of(
-            &*(" \t\r\n".to_owned() + OPERATOR_CHARACTERS),
-        )))),
-        |string| string.to_owned(),
-    )(input)
+    token(fold_many1(
+        alt((
+            recognize(many1_count(none_of(
+                &*(" \t\r\n$".to_owned() + OPERATOR_CHARACTERS),
+            ))),
+            preceded(char('$'), recognize(one_of(" :$#"))),
+            // Keep variables as they are.
+            recognize(tuple((char('$'), peek(none_of(" \t\r\n:$#"))))),
+        )),
+        String::new,
+        |mut string, part| {
+            string.push_str(part);
+            string
+        },
+    ))(input)
 }
 
 fn keyword(name: &'static str) -> impl Fn(&str) -> IResult<&str, ()> {
@@ -212,8 +252,21 @@ fn indent(input: &str) -> IResult<&str, ()> {
     value((), space1)(input)
 }
 
+// Comments start only after spaces or at the beginning of lines so that `#`
+// characters can be used in the other places.
 fn blank(input: &str) -> IResult<&str, ()> {
-    value((), many0_count(alt((value((), space1), comment))))(input)
+    value(
+        (),
+        opt(tuple((
+            many1_count(alt((value((), space1), line_continuation))),
+            opt(comment),
+        ))),
+    )(input)
+}
+
+// Joins physical lines ending with `$` skipping leading spaces of next lines.
+fn line_continuation(input: &str) -> IResult<&str, ()> {
+    value((), tuple((char('$'), line_ending, space0)))(input)
 }
 
 fn comment(input: &str) -> IResult<&str, ()> {
@@ -221,7 +274,11 @@ fn comment(input: &str) -> IResult<&str, ()> {
 }
 
 fn line_break(input: &str) -> IResult<&str, ()> {
-    value((), many1_count(tuple((blank, line_ending))))(input)
+    value((), tuple((blank, line_ending, many0_count(empty_line))))(input)
+}
+
+fn empty_line(input: &str) -> IResult<&str, ()> {
+    value((), tuple((alt((comment, blank)), line_ending)))(input)
 }
 
 #[cfg(test)]
@@ -262,15 +319,35 @@ mod tests {
         );
         assert_eq!(
             module("rule foo\n command = bar\n").unwrap().1,
-            Module::new(vec![Rule::new("foo", "bar", None).into()])
+            Module::new(vec![Rule::new(
+                "foo",
+                vec![VariableDefinition::new("command", "bar")]
+            )
+            .into()])
         );
         assert_eq!(
             module("rule foo\n command = bar\nrule baz\n command = blah\n")
                 .unwrap()
                 .1,
             Module::new(vec![
-                Rule::new("foo", "bar", None).into(),
-                Rule::new("baz", "blah", None).into(),
+                Rule::new("foo", vec![VariableDefinition::new("command", "bar")]).into(),
+                Rule::new("baz", vec![VariableDefinition::new("command", "blah")]).into(),
+            ],)
+        );
+        assert_eq!(
+            module("pool foo\n depth = 1\nrule bar\n command = baz\n pool = foo\n")
+                .unwrap()
+                .1,
+            Module::new(vec![
+                Pool::new("foo", "1").into(),
+                Rule::new(
+                    "bar",
+                    vec![
+                        VariableDefinition::new("command", "baz"),
+                        VariableDefinition::new("pool", "foo")
+                    ]
+                )
+                .into(),
             ],)
         );
         assert_eq!(
@@ -279,29 +356,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_include_and_submodule_statements() {
+        assert_eq!(
+            module("include foo.ninja\nsubninja bar.ninja\n").unwrap().1,
+            Module::new(vec![
+                Include::new("foo.ninja").into(),
+                Submodule::new("bar.ninja").into(),
+            ])
+        );
+    }
+
     #[test]
     fn parse_dynamic_module() {
         assert_eq!(
             dynamic_module("ninja_dyndep_version = 1\n").unwrap().1,
-            DynamicModule::new(vec![])
+            DynamicModule::new("1", vec![])
         );
         assert_eq!(
             dynamic_module("ninja_dyndep_version = 1\nbuild foo: dyndep\n")
                 .unwrap()
                 .1,
-            DynamicModule::new(vec![DynamicBuild::new("foo", vec![])])
+            DynamicModule::new("1", vec![DynamicBuild::new("foo", vec![])])
         );
         assert_eq!(
             dynamic_module("ninja_dyndep_version = 1\nbuild foo: dyndep\nbuild bar: dyndep\n")
                 .unwrap()
                 .1,
-            DynamicModule::new(vec![
-                DynamicBuild::new("foo", vec![]),
-                DynamicBuild::new("bar", vec![])
-            ])
+            DynamicModule::new(
+                "1",
+                vec![
+                    DynamicBuild::new("foo", vec![]),
+                    DynamicBuild::new("bar", vec![])
+                ]
+            )
         );
     }
 
+    #[test]
+    fn fail_to_parse_dynamic_module_without_version() {
+        assert!(dynamic_module("build foo: dyndep\n").is_err());
+    }
+
     #[test]
     fn parse_variable_definition() {
         assert_eq!(
@@ -336,14 +432,53 @@ mod tests {
     fn parse_rule() {
         assert_eq!(
             rule("rule foo\n command = bar\n").unwrap().1,
-            Rule::new("foo", "bar", None)
+            Rule::new("foo", vec![VariableDefinition::new("command", "bar")])
         );
         assert_eq!(
             rule("rule foo\n command = bar\n description = baz\n")
                 .unwrap()
                 .1,
-            Rule::new("foo", "bar", Some("baz".into()))
+            Rule::new(
+                "foo",
+                vec![
+                    VariableDefinition::new("command", "bar"),
+                    VariableDefinition::new("description", "baz")
+                ]
+            )
+        );
+        assert_eq!(
+            rule("rule foo\n description = baz\n command = bar\n")
+                .unwrap()
+                .1,
+            Rule::new(
+                "foo",
+                vec![
+                    VariableDefinition::new("description", "baz"),
+                    VariableDefinition::new("command", "bar")
+                ]
+            )
+        );
+        assert_eq!(
+            rule("rule foo\n command = bar\n pool = baz\n").unwrap().1,
+            Rule::new(
+                "foo",
+                vec![
+                    VariableDefinition::new("command", "bar"),
+                    VariableDefinition::new("pool", "baz")
+                ]
+            )
+        );
+        assert!(rule("rule foo\n").is_err());
+        assert!(rule("rule foo\n description = bar\n").is_err());
+    }
+
+    #[test]
+    fn parse_pool() {
+        assert_eq!(
+            pool("pool foo\n depth = 42\n").unwrap().1,
+            Pool::new("foo", "42")
         );
+        assert!(pool("pool foo\n").is_err());
     }
 
     #[test]
@@ -464,6 +599,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_build_with_escaped_paths() {
+        assert_eq!(
+            build("build foo$ bar: baz qux$:quux\n").unwrap().1,
+            explicit_build(
+                vec!["foo bar".into()],
+                "baz",
+                vec!["qux:quux".into()],
+                vec![]
+            )
+        );
+    }
+
     #[test]
     fn parse_dynamic_build() {
         assert_eq!(
@@ -521,6 +669,18 @@ mod tests {
         assert!(string_literal("").is_err());
         assert_eq!(string_literal("foo").unwrap().1, "foo");
         assert_eq!(string_literal("foo bar").unwrap().1, "foo");
+        assert_eq!(string_literal("foo:bar").unwrap().1, "foo");
+    }
+
+    #[test]
+    fn parse_escaped_string_literal() {
+        assert_eq!(string_literal("foo$ bar").unwrap().1, "foo bar");
+        assert_eq!(string_literal("foo$:bar").unwrap().1, "foo:bar");
+        assert_eq!(string_literal("foo$$bar").unwrap().1, "foo$bar");
+        assert_eq!(string_literal("$ foo").unwrap().1, " foo");
+        assert_eq!(string_literal("foo$ ").unwrap().1, "foo ");
+        assert_eq!(string_literal("$foo").unwrap().1, "$foo");
+        assert_eq!(string_literal("foo$bar baz").unwrap().1, "foo$bar");
     }
 
     #[test]
@@ -544,9 +704,10 @@ mod tests {
         assert!(all_consuming(blank)("\t").is_ok());
         assert!(all_consuming(blank)("  ").is_ok());
         assert!(all_consuming(blank)(" \t").is_ok());
-        assert!(all_consuming(blank)("#").is_ok());
-        assert!(all_consuming(blank)("#foo").is_ok());
+        assert!(all_consuming(blank)("#").is_err());
+        assert!(all_consuming(blank)("#foo").is_err());
         assert!(all_consuming(blank)(" #foo").is_ok());
+        assert!(all_consuming(blank)("\t#foo").is_ok());
         assert!(all_consuming(blank)("\n").is_err());
         assert!(all_consuming(blank)(" \n").is_err());
     }
@@ -560,5 +721,142 @@ mod tests {
         assert!(all_consuming(line_break)("  \n").is_ok());
         assert!(all_consuming(line_break)("\n\n").is_ok());
         assert!(all_consuming(line_break)("\n ").is_err());
+        assert!(all_consuming(line_break)(" #foo\n").is_ok());
+        assert!(all_consuming(line_break)("\n#foo\n").is_ok());
+        assert!(all_consuming(line_break)("\n  #foo\n\n").is_ok());
+        assert!(all_consuming(line_break)("#foo\n").is_err());
+    }
+
+    #[test]
+    fn parse_line_continuation() {
+        assert_eq!(
+            variable_definition("command = foo $\n bar\n").unwrap().1,
+            VariableDefinition::new("command", "foo bar")
+        );
+        assert_eq!(
+            variable_definition("command = foo $\r\n    bar $\n  baz\n")
+                .unwrap()
+                .1,
+            VariableDefinition::new("command", "foo bar baz")
+        );
+        assert_eq!(
+            variable_definition("x = foo$$\n").unwrap().1,
+            VariableDefinition::new("x", "foo$$")
+        );
+        assert_eq!(
+            module("rule foo\n  command = bar $\n    baz\n").unwrap().1,
+            Module::new(vec![Rule::new(
+                "foo",
+                vec![VariableDefinition::new("command", "bar baz")]
+            )
+            .into()])
+        );
+        assert_eq!(
+            build("build foo: bar $\n    baz $\n    qux\n").unwrap().1,
+            Build::new(
+                vec!["foo".into()],
+                vec![],
+                "bar",
+                vec!["baz".into(), "qux".into()],
+                vec![],
+                vec![],
+                vec![]
+            )
+        );
+    }
+
+    #[test]
+    fn parse_hash_in_variable_value() {
+        assert_eq!(
+            variable_definition("description = build #1\n").unwrap().1,
+            VariableDefinition::new("description", "build #1")
+        );
+        assert_eq!(
+            module("rule foo\n  command = echo $#\n  description = build #1\n")
+                .unwrap()
+                .1,
+            Module::new(vec![Rule::new(
+                "foo",
+                vec![
+                    VariableDefinition::new("command", "echo $#"),
+                    VariableDefinition::new("description", "build #1")
+                ]
+            )
+            .into()])
+        );
+    }
+
+    #[test]
+    fn parse_hash_in_string_literal() {
+        assert_eq!(string_literal("foo#bar").unwrap().1, "foo#bar");
+        assert_eq!(string_literal("foo$#bar").unwrap().1, "foo#bar");
+        assert_eq!(string_literal("#foo").unwrap().1, "#foo");
+    }
+
+    #[test]
+    fn parse_comment_after_statement() {
+        assert_eq!(
+            module("x = 1\n# foo\ny = 2\n").unwrap().1,
+            Module::new(vec![
+                VariableDefinition::new("x", "1").into(),
+                VariableDefinition::new("y", "2").into(),
+            ])
+        );
+        assert_eq!(
+            module("default foo #bar\n").unwrap().1,
+            Module::new(vec![DefaultOutput::new(vec!["foo".into()]).into()])
+        );
+    }
+
+    #[test]
+    fn parse_comments_and_blank_lines_in_rule() {
+        let expected = Rule::new(
+            "foo",
+            vec![
+                VariableDefinition::new("command", "bar"),
+                VariableDefinition::new("description", "baz"),
+            ],
+        );
+
+        assert_eq!(
+            rule("rule foo\n # foo\n command = bar\n # bar\n description = baz\n")
+                .unwrap()
+                .1,
+            expected
+        );
+        assert_eq!(
+            rule("rule foo\n command = bar\n\n description = baz\n")
+                .unwrap()
+                .1,
+            expected
+        );
+        assert_eq!(
+            rule("rule foo\n command = bar\n# foo\n  \n description = baz\n")
+                .unwrap()
+                .1,
+            expected
+        );
+    }
+
+    #[test]
+    fn parse_comments_and_blank_lines_in_build() {
+        assert_eq!(
+            module("build foo: bar\n # foo\n x = 1\n\n # bar\n y = 2\n")
+                .unwrap()
+                .1,
+            Module::new(vec![Build::new(
+                vec!["foo".into()],
+                vec![],
+                "bar",
+                vec![],
+                vec![],
+                vec![],
+                vec![
+                    VariableDefinition::new("x", "1"),
+                    VariableDefinition::new("y", "2")
+                ]
+            )
+            .into()])
+        );
     }
 }
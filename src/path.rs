@@ -0,0 +1,72 @@
+use std::path::{Component, Path, PathBuf};
+
+// Normalizes a path lexically without touching file systems. Therefore, it
+// does not resolve any symbolic links.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                Some(Component::CurDir | Component::ParentDir) | None => normalized.push(component),
+            },
+            Component::Prefix(_) | Component::RootDir | Component::Normal(_) => {
+                normalized.push(component)
+            }
+        }
+    }
+
+    if normalized.as_os_str().is_empty() {
+        Component::CurDir.as_os_str().into()
+    } else {
+        normalized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_empty_path() {
+        assert_eq!(normalize_path(Path::new("")), Path::new("."));
+        assert_eq!(normalize_path(Path::new(".")), Path::new("."));
+    }
+
+    #[test]
+    fn normalize_relative_path() {
+        assert_eq!(normalize_path(Path::new("foo")), Path::new("foo"));
+        assert_eq!(normalize_path(Path::new("./foo")), Path::new("foo"));
+        assert_eq!(normalize_path(Path::new("foo/./bar")), Path::new("foo/bar"));
+        assert_eq!(normalize_path(Path::new("foo//bar")), Path::new("foo/bar"));
+        assert_eq!(normalize_path(Path::new("foo/../bar")), Path::new("bar"));
+        assert_eq!(normalize_path(Path::new("foo/..")), Path::new("."));
+    }
+
+    #[test]
+    fn normalize_parent_directory() {
+        assert_eq!(normalize_path(Path::new("..")), Path::new(".."));
+        assert_eq!(normalize_path(Path::new("../foo")), Path::new("../foo"));
+        assert_eq!(
+            normalize_path(Path::new("../../foo")),
+            Path::new("../../foo")
+        );
+        assert_eq!(
+            normalize_path(Path::new("foo/../../bar")),
+            Path::new("../bar")
+        );
+    }
+
+    #[test]
+    fn normalize_absolute_path() {
+        assert_eq!(normalize_path(Path::new("/foo")), Path::new("/foo"));
+        assert_eq!(normalize_path(Path::new("/foo/../bar")), Path::new("/bar"));
+        assert_eq!(normalize_path(Path::new("/..")), Path::new("/"));
+        assert_eq!(normalize_path(Path::new("/../foo")), Path::new("/foo"));
+    }
+}
@@ -1,36 +1,74 @@
 mod context;
+mod depfile;
 mod hash;
 mod log;
 mod options;
+mod profile;
+mod show_includes;
+mod status;
 
-use self::context::Context as RunContext;
+use self::{context::Context as RunContext, profile::ProfileEntry};
 use crate::{
     build_graph::{BuildGraph, BuildGraphError},
+    color::{self, Color},
     compile::compile_dynamic,
     context::Context,
     debug,
     error::ApplicationError,
     hash_type::HashType,
-    ir::{Build, Configuration, Rule},
+    infrastructure::{load_average, Console},
+    ir::{Build, Configuration, DependencyFormat, Rule},
+    log,
     parse::parse_dynamic,
+    path::normalize_path,
     profile,
+    verbosity::Verbosity,
 };
 use async_recursion::async_recursion;
-use futures::future::{try_join_all, FutureExt, Shared};
+use futures::future::{join_all, try_join_all, FutureExt, Shared};
 use itertools::Itertools;
 pub use options::Options;
-use std::{future::Future, path::Path, pin::Pin, sync::Arc};
-use tokio::{spawn, time::Instant, try_join};
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashSet},
+    error::Error,
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    select, spawn,
+    sync::SemaphorePermit,
+    time::{sleep, timeout, Instant},
+};
+
+const LOAD_POLLING_INTERVAL: Duration = Duration::from_secs(1);
 
 type RawBuildFuture = Pin<Box<dyn Future<Output = Result<(), ApplicationError>> + Send>>;
 type BuildFuture = Shared<RawBuildFuture>;
 
+// Running builds are interrupted when `interruption` completes. Callers
+// install signal handlers themselves if needed.
 pub async fn run(
     context: &Arc<Context>,
     configuration: Arc<Configuration>,
     outputs: &[String],
     options: Options,
+    interruption: impl Future<Output = ()> + Send,
 ) -> Result<(), ApplicationError> {
+    // Hashes calculated by different algorithms are incompatible.
+    if !options.dry_run {
+        let database = context.database();
+        let algorithm = options.hash_algorithm.name();
+
+        if database.get_hash_algorithm()?.as_deref() != Some(algorithm) {
+            database.clear_hashes()?;
+            database.set_hash_algorithm(algorithm)?;
+        }
+    }
+
     let graph = BuildGraph::new(configuration.outputs());
     let context = Arc::new(RunContext::new(
         context.clone(),
@@ -46,32 +84,308 @@ pub async fn run(
         .validate()
         .map_err(|error| map_build_graph_error(&context, &error))?;
 
-    if outputs.is_empty() {
-        for output in context.configuration().default_outputs() {
-            trigger_build(
-                context.clone(),
+    let builds = if outputs.is_empty() {
+        // Default outputs are sorted as they are in a hash set.
+        context
+            .configuration()
+            .default_outputs()
+            .iter()
+            .sorted()
+            .map(|output| {
                 context
                     .configuration()
                     .outputs()
                     .get(output.as_ref())
-                    .ok_or_else(|| ApplicationError::DefaultOutputNotFound(output.clone()))?,
-            )
-            .await?;
-        }
+                    .cloned()
+                    .ok_or_else(|| ApplicationError::DefaultOutputNotFound(output.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?
     } else {
-        for output in outputs {
-            trigger_build(
-                context.clone(),
+        // Outputs are normalized in the same way as the ones in build files.
+        outputs
+            .iter()
+            .map(|output| {
                 context
                     .configuration()
                     .outputs()
-                    .get(output.as_str())
-                    .ok_or_else(|| ApplicationError::OutputNotFound(output.clone()))?,
+                    .get(normalize_path(output.as_ref()).to_string_lossy().as_ref())
+                    .cloned()
+                    .ok_or_else(|| ApplicationError::OutputNotFound(output.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    let reachable_builds = find_builds(context.configuration(), &builds);
+
+    if context.options().check_inputs {
+        check_inputs(&context, &reachable_builds).await?;
+    }
+
+    if context.options().protect_sources {
+        protect_sources(&context, &reachable_builds).await?;
+    }
+
+    let build_count = reachable_builds
+        .iter()
+        .filter(|build| build.rule().is_some())
+        .count();
+
+    context.add_build_count(build_count);
+
+    for build in &builds {
+        trigger_build(context.clone(), build).await?;
+    }
+
+    let result = select! {
+        result = wait_builds(&context) => result,
+        _ = interruption => {
+            context.interrupt();
+            wait_interrupted_builds(&context).await;
+
+            Err(ApplicationError::Interrupted)
+        }
+    };
+    let result = match result {
+        Ok(()) if context.options().verify_outputs && !context.options().dry_run => {
+            verify_outputs(&context, &reachable_builds).await
+        }
+        result => result,
+    };
+
+    context.application().database().flush().await?;
+
+    if let Some(path) = &context.options().profile_file {
+        context
+            .application()
+            .file_system()
+            .write_file(path, profile::render(&context.profile_entries()).as_bytes())
+            .await?;
+    }
+
+    if let Some(path) = &context.options().trace_file {
+        context
+            .application()
+            .file_system()
+            .write_file(
+                path,
+                profile::render_trace(&context.profile_entries()).as_bytes(),
             )
             .await?;
+    }
+
+    if result.is_ok()
+        && context.options().summary
+        && context.options().verbosity > Verbosity::Silent
+    {
+        write_summary(&context, build_count).await?;
+    }
+
+    result
+}
+
+// Builds are counted only if they run commands. Up-to-date builds are
+// distinguished from the case where no build is required at all.
+async fn write_summary(context: &RunContext, build_count: usize) -> Result<(), ApplicationError> {
+    let count = context.finished_build_count();
+    let message = if build_count == 0 {
+        "nothing to do".into()
+    } else {
+        format!(
+            "built {} target{} in {:.1}s",
+            count,
+            if count == 1 { "" } else { "s" },
+            (Instant::now() - context.start_time()).as_secs_f64()
+        )
+    };
+
+    context
+        .application()
+        .console()
+        .lock()
+        .await
+        .write_stderr(
+            format!(
+                "{}{}\n",
+                context.options().log_prefix.as_deref().unwrap_or_default(),
+                color::paint(&message, Color::Green, context.options().color)
+            )
+            .as_bytes(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+// Finds builds reachable from given ones. Builds discovered via dynamic
+// dependencies are not included.
+fn find_builds(configuration: &Configuration, builds: &[Arc<Build>]) -> Vec<Arc<Build>> {
+    let mut builds = builds.to_vec();
+    let mut visited = HashSet::new();
+    let mut found = vec![];
+
+    while let Some(build) = builds.pop() {
+        if !visited.insert(build.id()) {
+            continue;
+        }
+
+        builds.extend(
+            build
+                .inputs()
+                .iter()
+                .chain(build.order_only_inputs())
+                .filter_map(|input| configuration.outputs().get(input.as_ref()))
+                .cloned(),
+        );
+        found.push(build);
+    }
+
+    found
+}
+
+// Checks existence of all source inputs at once before running any commands.
+async fn check_inputs(context: &RunContext, builds: &[Arc<Build>]) -> Result<(), ApplicationError> {
+    let inputs = builds
+        .iter()
+        .flat_map(|build| build.explicit_inputs())
+        .filter(|input| {
+            !context
+                .configuration()
+                .outputs()
+                .contains_key(input.as_ref())
+        })
+        .collect::<BTreeSet<_>>();
+    let mut missing_inputs = vec![];
+
+    for (input, result) in inputs.iter().zip(
+        join_all(inputs.iter().map(|input| {
+            context
+                .application()
+                .file_system()
+                .metadata(input.as_ref().as_ref())
+        }))
+        .await,
+    ) {
+        if result.is_err() {
+            missing_inputs.push(
+                context
+                    .application()
+                    .database()
+                    .get_source(input)?
+                    .unwrap_or_else(|| input.to_string()),
+            );
+        }
+    }
+
+    if missing_inputs.is_empty() {
+        Ok(())
+    } else {
+        Err(ApplicationError::FilesNotFound(missing_inputs))
+    }
+}
+
+// Finds outputs which exist already but have never been built by Turtle. They
+// are likely to be source files declared as outputs by mistake.
+async fn protect_sources(
+    context: &RunContext,
+    builds: &[Arc<Build>],
+) -> Result<(), ApplicationError> {
+    let built_outputs = context
+        .application()
+        .database()
+        .get_outputs()?
+        .into_iter()
+        .collect::<HashSet<_>>();
+    let outputs = builds
+        .iter()
+        .filter(|build| build.rule().is_some())
+        .flat_map(|build| build.outputs())
+        .filter(|output| !built_outputs.contains(output.as_ref()))
+        .collect::<BTreeSet<_>>();
+    let mut sources = vec![];
+
+    for (output, result) in outputs.iter().zip(
+        join_all(outputs.iter().map(|output| {
+            context
+                .application()
+                .file_system()
+                .metadata(output.as_ref().as_ref())
+        }))
+        .await,
+    ) {
+        if result.is_ok() {
+            sources.push(output.to_string());
+        }
+    }
+
+    if sources.is_empty() {
+        Ok(())
+    } else {
+        Err(ApplicationError::SourcesOverwritten(sources))
+    }
+}
+
+// Checks existence of outputs of all builds run to find rules declaring outputs
+// which their commands do not generate.
+async fn verify_outputs(
+    context: &RunContext,
+    builds: &[Arc<Build>],
+) -> Result<(), ApplicationError> {
+    let outputs = builds
+        .iter()
+        .filter(|build| build.rule().is_some())
+        .flat_map(|build| build.outputs().iter().chain(build.implicit_outputs()))
+        .collect::<BTreeSet<_>>();
+    let mut missing_outputs = vec![];
+
+    for (output, result) in outputs.iter().zip(
+        join_all(outputs.iter().map(|output| {
+            context
+                .application()
+                .file_system()
+                .metadata(output.as_ref().as_ref())
+        }))
+        .await,
+    ) {
+        if result.is_err() {
+            missing_outputs.push(output.to_string());
         }
     }
 
+    if missing_outputs.is_empty() {
+        Ok(())
+    } else {
+        Err(ApplicationError::OutputsMissing(missing_outputs))
+    }
+}
+
+async fn wait_builds(context: &RunContext) -> Result<(), ApplicationError> {
+    let result = wait_build_futures(context).await;
+
+    // Builds do not start any new commands once a failure limit is reached.
+    if context.is_failure_limit_reached() {
+        context.interrupt();
+        wait_interrupted_builds(context).await;
+    }
+
+    // Builds cancelled at a failure limit are not failures by themselves.
+    let result = match result {
+        Err(ApplicationError::Interrupted) if context.is_failure_limit_reached() => Ok(()),
+        result => result,
+    };
+
+    match result {
+        Ok(()) | Err(ApplicationError::Build) => match context.failure_count() {
+            0 => Ok(()),
+            1 => Err(ApplicationError::Build),
+            count => Err(ApplicationError::Builds(count)),
+        },
+        Err(error) => Err(error),
+    }
+}
+
+// Builds stop running their commands immediately on interruption. Builds
+// triggered later are waited for by their dependents.
+async fn wait_interrupted_builds(context: &RunContext) {
     // Do not inline this to avoid borrowing a lock of builds.
     let futures = context
         .build_futures()
@@ -79,13 +393,38 @@ pub async fn run(
         .map(|r#ref| r#ref.value().clone())
         .collect::<Vec<_>>();
 
-    let result = try_join_all(futures).await;
+    join_all(futures).await;
+}
 
-    context.application().database().flush().await?;
+// Failed builds are propagated to their dependents as build errors, which are
+// ignored here until the number of failures reaches a limit.
+async fn wait_build_futures(context: &RunContext) -> Result<(), ApplicationError> {
+    loop {
+        // Do not inline this to avoid borrowing a lock of builds.
+        let futures = context
+            .build_futures()
+            .iter()
+            .map(|r#ref| r#ref.value().clone())
+            .collect::<Vec<_>>();
+        let count = futures.len();
+
+        try_join_all(futures.into_iter().map(|future| async {
+            match future.await {
+                Err(ApplicationError::Build) if !context.is_failure_limit_reached() => Ok(()),
+                result => result,
+            }
+        }))
+        .await?;
 
-    result.map(|_| ())
+        // Wait for builds triggered by the other builds, which might not be waited
+        // for by their dependents on failures of other inputs.
+        if context.build_futures().len() == count {
+            return Ok(());
+        }
+    }
 }
 
+#[allow(clippy::double_must_use)]
 #[async_recursion]
 async fn trigger_build(
     context: Arc<RunContext>,
@@ -94,17 +433,19 @@ async fn trigger_build(
     context
         .build_futures()
         .entry(build.id())
-        .or_insert_with(|| {
-            let future: RawBuildFuture = Box::pin(spawn_build(context.clone(), build.clone()));
-
-            future.shared()
-        });
+        .or_insert_with(|| spawn_build(context.clone(), build.clone()).shared());
 
     Ok(())
 }
 
-async fn spawn_build(context: Arc<RunContext>, build: Arc<Build>) -> Result<(), ApplicationError> {
-    spawn(async move {
+// Builds are spawned on their triggers rather than their first polls so that
+// they start in the order of the triggers.
+fn spawn_build(context: Arc<RunContext>, build: Arc<Build>) -> RawBuildFuture {
+    let handle = spawn(async move {
+        if context.is_interrupted() {
+            return Err(ApplicationError::Interrupted);
+        }
+
         let mut futures = vec![];
 
         for input in build.inputs().iter().chain(build.order_only_inputs()) {
@@ -114,14 +455,22 @@ async fn spawn_build(context: Arc<RunContext>, build: Arc<Build>) -> Result<(),
         try_join_all(futures).await?;
 
         // TODO Consider caching dynamic modules.
-        let dynamic_configuration = if let Some(dynamic_module) = build.dynamic_module() {
+        let dynamic_configuration = if let Some(dynamic_module) = build
+            .dynamic_module()
+            .filter(|module| !is_dry_run_output(&context, module))
+        {
             let mut source = String::new();
             context
                 .application()
                 .file_system()
                 .read_file_to_string(dynamic_module.as_ref().as_ref(), &mut source)
                 .await?;
-            let configuration = compile_dynamic(&parse_dynamic(&source)?)?;
+            let configuration = compile_dynamic(&parse_dynamic(&source).map_err(|error| {
+                ApplicationError::DynamicParse(
+                    build.clone(),
+                    error.with_path(dynamic_module.as_ref()),
+                )
+            })?)?;
 
             context
                 .build_graph()
@@ -154,6 +503,8 @@ async fn spawn_build(context: Arc<RunContext>, build: Arc<Build>) -> Result<(),
 
         try_join_all(futures).await?;
 
+        // Builds are outdated if any of their outputs are missing even when their
+        // hashes match.
         let outputs_exist = try_join_all(
             build
                 .outputs()
@@ -163,7 +514,9 @@ async fn spawn_build(context: Arc<RunContext>, build: Arc<Build>) -> Result<(),
         )
         .await
         .is_ok();
-        let (file_inputs, phony_inputs) = build
+        // Order-only inputs including phony barriers only order builds and never
+        // contribute to their hashes.
+        let (mut file_inputs, phony_inputs) = build
             .inputs()
             .iter()
             .chain(dynamic_inputs)
@@ -175,29 +528,97 @@ async fn spawn_build(context: Arc<RunContext>, build: Arc<Build>) -> Result<(),
                     true
                 }
             });
-        let timestamp_hash =
+
+        let explicit_file_input_count = file_inputs.len();
+        let dependencies = context
+            .application()
+            .database()
+            .get_dependencies(build.id())?
+            .unwrap_or_default();
+        // Builds are outdated if any of their dependencies discovered previously
+        // are missing.
+        let dependencies_exist = try_join_all(
+            dependencies
+                .iter()
+                .map(|path| check_file_existence(&context, path)),
+        )
+        .await
+        .is_ok();
+
+        if dependencies_exist {
+            file_inputs.extend(dependencies.iter().map(String::as_str));
+        }
+
+        if context.options().dry_run
+            && file_inputs
+                .iter()
+                .chain(&phony_inputs)
+                .any(|input| is_dry_run_output(&context, input))
+        {
+            return run_dry(&context, &build).await;
+        }
+
+        // Builds are never up to date if forced although their hashes are still
+        // recorded.
+        let reusable = !context.options().force && outputs_exist && dependencies_exist;
+        let mut timestamp_hash =
             hash::calculate_timestamp_hash(&context, &build, &file_inputs, &phony_inputs).await?;
 
-        if outputs_exist
+        if reusable
             && Some(timestamp_hash)
                 == context
                     .application()
                     .database()
                     .get_hash(HashType::Timestamp, build.id())?
         {
+            skip_build(&context, &build).await?;
+
             return Ok(());
         }
 
-        let content_hash =
+        let mut content_hash =
             hash::calculate_content_hash(&context, &build, &file_inputs, &phony_inputs).await?;
 
-        if outputs_exist
+        if reusable
             && Some(content_hash)
                 == context
                     .application()
                     .database()
                     .get_hash(HashType::Content, build.id())?
         {
+            // Timestamps of inputs regenerated identically by `restat` rules are
+            // recorded so that the build is skipped without reading them next time.
+            if !context.options().dry_run && has_restat_input(&context, &file_inputs) {
+                context.application().database().set_hash(
+                    HashType::Timestamp,
+                    build.id(),
+                    timestamp_hash,
+                )?;
+            }
+
+            skip_build(&context, &build).await?;
+
+            return Ok(());
+        }
+
+        if context.options().explain && build.rule().is_some() {
+            let reason = explain_build(&context, &build, &dependencies, &file_inputs).await?;
+
+            explain(&context, &build, &reason).await?;
+        }
+
+        if context.options().dry_run {
+            // Phony builds are never up to date in terms of their outputs.
+            if build.rule().is_some()
+                || Some(content_hash)
+                    != context
+                        .application()
+                        .database()
+                        .get_hash(HashType::Content, build.id())?
+            {
+                run_dry(&context, &build).await?;
+            }
+
             return Ok(());
         } else if let Some(rule) = build.rule() {
             try_join_all(
@@ -209,7 +630,73 @@ async fn spawn_build(context: Arc<RunContext>, build: Arc<Build>) -> Result<(),
             )
             .await?;
 
-            run_rule(&context, rule).await?;
+            let modified_times = if rule.restat() {
+                Some(read_modified_times(&context, &build).await)
+            } else {
+                None
+            };
+
+            let discovered_dependencies = run_rule(&context, &build, rule).await?;
+
+            // Outputs untouched by commands of `restat` rules keep their timestamps and
+            // therefore do not make their dependents outdated.
+            if modified_times.is_some()
+                && modified_times == Some(read_modified_times(&context, &build).await)
+            {
+                let mut console = context.application().console().lock().await;
+
+                debug!(
+                    context,
+                    console,
+                    "outputs unchanged: {}",
+                    build.outputs()[0]
+                );
+            }
+
+            // Dependencies discovered previously are invalidated when rules stop
+            // generating depfiles.
+            let dependencies = if discovered_dependencies.is_some() {
+                discovered_dependencies
+            } else if let Some(depfile) = rule.depfile() {
+                Some(read_dependencies(&context, depfile).await?)
+            } else if !dependencies.is_empty() {
+                Some(vec![])
+            } else {
+                None
+            };
+
+            if let Some(dependencies) = dependencies {
+                context
+                    .application()
+                    .database()
+                    .set_dependencies(build.id(), &dependencies)?;
+
+                // Missing dependencies make the build outdated on the next run anyway.
+                let existences = join_all(
+                    dependencies
+                        .iter()
+                        .map(|path| check_file_existence(&context, path)),
+                )
+                .await;
+                let file_inputs = file_inputs[..explicit_file_input_count]
+                    .iter()
+                    .copied()
+                    .chain(
+                        dependencies
+                            .iter()
+                            .zip(existences)
+                            .filter(|(_, existence)| existence.is_ok())
+                            .map(|(path, _)| path.as_str()),
+                    )
+                    .collect::<Vec<_>>();
+
+                timestamp_hash =
+                    hash::calculate_timestamp_hash(&context, &build, &file_inputs, &phony_inputs)
+                        .await?;
+                content_hash =
+                    hash::calculate_content_hash(&context, &build, &file_inputs, &phony_inputs)
+                        .await?;
+            }
 
             for output in build.outputs() {
                 context.application().database().set_output(output)?;
@@ -232,10 +719,16 @@ async fn spawn_build(context: Arc<RunContext>, build: Arc<Build>) -> Result<(),
             .application()
             .database()
             .set_hash(HashType::Content, build.id(), content_hash)?;
+        context.application().database().set_hash(
+            HashType::Command,
+            build.id(),
+            hash::calculate_command_hash(&context, &build),
+        )?;
 
         Ok(())
-    })
-    .await?
+    });
+
+    Box::pin(async move { handle.await? })
 }
 
 async fn build_input(
@@ -256,6 +749,110 @@ async fn build_input(
     )
 }
 
+async fn skip_build(context: &RunContext, build: &Build) -> Result<(), ApplicationError> {
+    if build.rule().is_some() {
+        context.skip_build();
+
+        if context.options().explain {
+            explain(context, build, "up to date").await?;
+        }
+    }
+
+    Ok(())
+}
+
+// Reasons are inferred from states of outputs and inputs, and hashes recorded
+// on previous builds.
+async fn explain_build(
+    context: &RunContext,
+    build: &Build,
+    dependencies: &[String],
+    file_inputs: &[&str],
+) -> Result<String, ApplicationError> {
+    if context.options().force {
+        return Ok("forced".into());
+    }
+
+    for output in build.outputs().iter().chain(build.implicit_outputs()) {
+        if check_file_existence(context, output).await.is_err() {
+            return Ok(format!("output {output} missing"));
+        }
+    }
+
+    for dependency in dependencies {
+        if check_file_existence(context, dependency).await.is_err() {
+            return Ok(format!("dependency {dependency} missing"));
+        }
+    }
+
+    match context
+        .application()
+        .database()
+        .get_hash(HashType::Command, build.id())?
+    {
+        None => return Ok("no previous build".into()),
+        Some(hash) if hash != hash::calculate_command_hash(context, build) => {
+            return Ok("command changed".into())
+        }
+        Some(_) => {}
+    }
+
+    let output_time = read_modified_times(context, build)
+        .await
+        .into_iter()
+        .flatten()
+        .min();
+
+    for &input in file_inputs {
+        let time = context
+            .application()
+            .file_system()
+            .metadata(input.as_ref())
+            .await?
+            .modified_time();
+
+        if output_time.is_some_and(|output_time| time > output_time) {
+            return Ok(format!("input {input} newer"));
+        }
+    }
+
+    Ok("content changed".into())
+}
+
+async fn explain(
+    context: &RunContext,
+    build: &Build,
+    reason: &str,
+) -> Result<(), ApplicationError> {
+    let mut console = context.application().console().lock().await;
+
+    log!(console, "explain: {}: {}", build.outputs()[0], reason);
+
+    Ok(())
+}
+
+async fn run_dry(context: &RunContext, build: &Build) -> Result<(), ApplicationError> {
+    if let Some(rule) = build.rule() {
+        let mut console = context.application().console().lock().await;
+
+        console.write_stdout(rule.command().as_bytes()).await?;
+        console.write_stdout(b"\n").await?;
+    }
+
+    context.dry_run_builds().insert(build.id());
+
+    Ok(())
+}
+
+fn is_dry_run_output(context: &RunContext, output: &str) -> bool {
+    context
+        .configuration()
+        .outputs()
+        .get(output)
+        .map(|build| context.dry_run_builds().contains(&build.id()))
+        .unwrap_or_default()
+}
+
 async fn check_file_existence(context: &RunContext, path: &str) -> Result<(), ApplicationError> {
     if context
         .application()
@@ -276,6 +873,56 @@ async fn check_file_existence(context: &RunContext, path: &str) -> Result<(), Ap
     Ok(())
 }
 
+fn has_restat_input(context: &RunContext, inputs: &[&str]) -> bool {
+    inputs.iter().any(|&input| {
+        context
+            .configuration()
+            .outputs()
+            .get(input)
+            .and_then(|build| build.rule())
+            .is_some_and(Rule::restat)
+    })
+}
+
+async fn read_modified_times(context: &RunContext, build: &Build) -> Vec<Option<SystemTime>> {
+    join_all(
+        build
+            .outputs()
+            .iter()
+            .chain(build.implicit_outputs())
+            .map(|path| async {
+                context
+                    .application()
+                    .file_system()
+                    .metadata(path.as_ref().as_ref())
+                    .await
+                    .ok()
+                    .map(|metadata| metadata.modified_time())
+            }),
+    )
+    .await
+}
+
+// Depfiles are not always generated, for example, when builds have no
+// dependency to be discovered.
+async fn read_dependencies(
+    context: &RunContext,
+    path: &str,
+) -> Result<Vec<String>, ApplicationError> {
+    let file_system = context.application().file_system();
+
+    if file_system.metadata(path.as_ref()).await.is_err() {
+        return Ok(vec![]);
+    }
+
+    let mut source = String::new();
+    file_system
+        .read_file_to_string(path.as_ref(), &mut source)
+        .await?;
+
+    Ok(depfile::parse(&source))
+}
+
 async fn prepare_directory(
     context: &RunContext,
     path: impl AsRef<Path>,
@@ -291,36 +938,74 @@ async fn prepare_directory(
     Ok(())
 }
 
-async fn run_rule(context: &RunContext, rule: &Rule) -> Result<(), ApplicationError> {
-    let ((output, duration), mut console) = try_join!(
-        async {
-            let start_time = Instant::now();
-            let output = context
-                .application()
-                .command_runner()
-                .run(rule.command())
-                .await?;
+// Dependencies discovered from outputs of commands are returned.
+async fn run_rule(
+    context: &RunContext,
+    build: &Build,
+    rule: &Rule,
+) -> Result<Option<Vec<String>>, ApplicationError> {
+    if rule.is_console() {
+        run_console_rule(context, build, rule).await?;
 
-            Ok::<_, ApplicationError>((output, Instant::now() - start_time))
-        },
-        async {
-            let mut console = context.application().console().lock().await;
+        return Ok(None);
+    }
 
-            if let Some(description) = rule.description() {
-                console.write_stderr(description.as_bytes()).await?;
-                console.write_stderr(b"\n").await?;
-            }
+    wait_load(context).await;
 
-            debug!(context, console, "command: {}", rule.command());
+    let permit = if let Some(semaphore) = rule.pool().and_then(|pool| context.pool_semaphore(pool))
+    {
+        Some(semaphore.acquire().await?)
+    } else {
+        None
+    };
 
-            Ok(console)
-        }
-    )?;
+    // Response files are written only after job slots are acquired.
+    let _job_permit = acquire_job(context).await?;
+
+    write_response_file(context, rule).await?;
+
+    context.start_build();
+    let directory = command_directory(context, rule);
+    let start_time = Instant::now();
+    // Commands are killed on interruption without recording their builds.
+    let command = context.application().command_runner().run(
+        rule.command(),
+        directory.as_deref(),
+        rule.environment(),
+    );
+    let output = select! {
+        output = run_command(context, rule, command) => output?,
+        _ = context.interrupted() => return Err(ApplicationError::Interrupted),
+    };
+    let duration = Instant::now() - start_time;
+
+    drop(permit);
+
+    record_profile(context, build, rule, start_time, duration);
+
+    // Write a description and outputs of a job under a single console lock so
+    // that they are not interleaved with the ones of the other jobs.
+    let mut console = context.application().console().lock().await;
+
+    write_description(context, &mut **console, rule).await?;
 
     profile!(context, console, "duration: {}ms", duration.as_millis());
 
-    console.write_stdout(&output.stdout).await?;
-    console.write_stderr(&output.stderr).await?;
+    let (stdout, dependencies) =
+        if let Some(DependencyFormat::Msvc(prefix)) = rule.dependency_format() {
+            let (stdout, dependencies) = show_includes::parse(&output.stdout, prefix);
+
+            (stdout.into(), Some(dependencies))
+        } else {
+            (Cow::Borrowed(output.stdout.as_slice()), None)
+        };
+
+    // Outputs of console jobs are not hidden as they are written to a
+    // terminal directly.
+    if context.options().verbosity > Verbosity::Silent {
+        console.write_stdout(&stdout).await?;
+        console.write_stderr(&output.stderr).await?;
+    }
 
     if !output.status.success() {
         debug!(
@@ -334,9 +1019,201 @@ async fn run_rule(context: &RunContext, rule: &Rule) -> Result<(), ApplicationEr
                 .unwrap_or_else(|| "-".into())
         );
 
+        // Failures are recorded in job slots so that no command starts after
+        // a failure limit is reached.
+        context.record_failure();
+
+        return Err(ApplicationError::Build);
+    }
+
+    remove_response_file(context, rule).await?;
+
+    Ok(dependencies)
+}
+
+// Console jobs hold a console lock during their execution so that their
+// outputs are not interleaved with the others.
+async fn run_console_rule(
+    context: &RunContext,
+    build: &Build,
+    rule: &Rule,
+) -> Result<(), ApplicationError> {
+    let _job_permit = acquire_job(context).await?;
+    let mut console = context.application().console().lock().await;
+
+    context.start_build();
+    write_description(context, &mut **console, rule).await?;
+
+    console.flush().await?;
+
+    write_response_file(context, rule).await?;
+
+    let directory = command_directory(context, rule);
+    let start_time = Instant::now();
+    let command = context.application().command_runner().run_console(
+        rule.command(),
+        directory.as_deref(),
+        rule.environment(),
+    );
+    let status = select! {
+        status = run_command(context, rule, command) => status?,
+        _ = context.interrupted() => return Err(ApplicationError::Interrupted),
+    };
+    let duration = Instant::now() - start_time;
+
+    record_profile(context, build, rule, start_time, duration);
+    profile!(context, console, "duration: {}ms", duration.as_millis());
+
+    if !status.success() {
+        debug!(
+            context,
+            console,
+            "exit status: {}",
+            status
+                .code()
+                .map(|code| code.to_string())
+                .unwrap_or_else(|| "-".into())
+        );
+
+        context.record_failure();
+
         return Err(ApplicationError::Build);
     }
 
+    remove_response_file(context, rule).await?;
+
+    Ok(())
+}
+
+fn record_profile(
+    context: &RunContext,
+    build: &Build,
+    rule: &Rule,
+    start_time: Instant,
+    duration: Duration,
+) {
+    if context.options().profile_file.is_some() || context.options().trace_file.is_some() {
+        context.record_profile(ProfileEntry::new(
+            build.outputs()[0].clone(),
+            rule.command(),
+            start_time - context.start_time(),
+            duration,
+        ));
+    }
+}
+
+// Running jobs are not interrupted even if a load average exceeds a limit.
+async fn wait_load(context: &RunContext) {
+    if let Some(limit) = context.options().load_limit {
+        while load_average().await.unwrap_or_default() > limit {
+            sleep(LOAD_POLLING_INTERVAL).await;
+        }
+    }
+}
+
+// Commands never start once builds are interrupted or a failure limit is
+// reached.
+async fn acquire_job(context: &RunContext) -> Result<SemaphorePermit<'_>, ApplicationError> {
+    let permit = context.job_semaphore().acquire().await?;
+
+    if context.is_interrupted() || context.is_failure_limit_reached() {
+        return Err(ApplicationError::Interrupted);
+    }
+
+    Ok(permit)
+}
+
+// Relative directories of commands are resolved from a build directory.
+fn command_directory(context: &RunContext, rule: &Rule) -> Option<PathBuf> {
+    rule.directory()
+        .map(|directory| context.options().build_directory.join(directory))
+}
+
+async fn run_command<T>(
+    context: &RunContext,
+    rule: &Rule,
+    future: impl Future<Output = Result<T, Box<dyn Error>>>,
+) -> Result<T, ApplicationError> {
+    Ok(if let Some(duration) = context.options().timeout {
+        timeout(duration, future)
+            .await
+            .map_err(|_| ApplicationError::CommandTimeout(rule.command().into()))?
+    } else {
+        future.await
+    }?)
+}
+
+async fn write_description(
+    context: &RunContext,
+    console: &mut (dyn Console + Send + Sync),
+    rule: &Rule,
+) -> Result<(), ApplicationError> {
+    // Progress is counted while a console is locked so that numbers in status
+    // lines are neither skipped nor duplicated.
+    let count = context.finish_build();
+
+    if let Some(description) = rule
+        .description()
+        .filter(|_| context.options().verbosity == Verbosity::Normal)
+    {
+        console
+            .write_stderr(
+                format!(
+                    "{}{}\n",
+                    status::format(
+                        &context.options().status_format,
+                        &status::Progress {
+                            started: context.started_build_count(),
+                            finished: count,
+                            total: context.build_count().max(count),
+                            elapsed: Instant::now() - context.start_time(),
+                        }
+                    ),
+                    description
+                )
+                .as_bytes(),
+            )
+            .await?;
+    }
+
+    if context.options().verbose && context.options().verbosity > Verbosity::Silent {
+        if let Some(prefix) = &context.options().log_prefix {
+            console.write_stderr(prefix.as_bytes()).await?;
+        }
+
+        console.write_stderr(rule.command().as_bytes()).await?;
+        console.write_stderr(b"\n").await?;
+    }
+
+    debug!(context, console, "command: {}", rule.command());
+
+    Ok(())
+}
+
+async fn write_response_file(context: &RunContext, rule: &Rule) -> Result<(), ApplicationError> {
+    if let Some(file) = rule.response_file() {
+        prepare_directory(context, file.path()).await?;
+
+        context
+            .application()
+            .file_system()
+            .write_file(file.path().as_ref(), file.content().as_bytes())
+            .await?;
+    }
+
+    Ok(())
+}
+
+// Response files are kept on failures or in debug mode for investigation.
+async fn remove_response_file(context: &RunContext, rule: &Rule) -> Result<(), ApplicationError> {
+    if let Some(file) = rule.response_file().filter(|_| !context.options().debug) {
+        context
+            .application()
+            .file_system()
+            .remove_file(file.path().as_ref())
+            .await?;
+    }
+
     Ok(())
 }
 
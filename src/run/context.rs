@@ -1,18 +1,39 @@
-use super::{options::Options, BuildFuture};
+use super::{options::Options, profile::ProfileEntry, BuildFuture};
 use crate::{
     build_graph::BuildGraph,
     context::Context as ApplicationContext,
     ir::{BuildId, Configuration},
 };
-use dashmap::DashMap;
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use dashmap::{DashMap, DashSet};
+use std::{
+    collections::HashMap,
+    sync::{
+        self,
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use tokio::{
+    sync::{watch, Mutex, Semaphore},
+    time::Instant,
+};
 
 pub struct Context {
     application: Arc<ApplicationContext>,
     configuration: Arc<Configuration>,
     build_futures: DashMap<BuildId, BuildFuture>,
     build_graph: Mutex<BuildGraph>,
+    dry_run_builds: DashSet<BuildId>,
+    build_count: AtomicUsize,
+    skipped_build_count: AtomicUsize,
+    started_build_count: AtomicUsize,
+    finished_build_count: AtomicUsize,
+    failure_count: AtomicUsize,
+    interruption: watch::Sender<bool>,
+    job_semaphore: Semaphore,
+    pool_semaphores: HashMap<Arc<str>, Semaphore>,
+    start_time: Instant,
+    profile_entries: sync::Mutex<Vec<ProfileEntry>>,
     options: Options,
 }
 
@@ -26,8 +47,25 @@ impl Context {
         Self {
             application,
             build_graph: build_graph.into(),
+            dry_run_builds: DashSet::new(),
+            build_count: Default::default(),
+            skipped_build_count: Default::default(),
+            started_build_count: Default::default(),
+            finished_build_count: Default::default(),
+            failure_count: Default::default(),
+            interruption: watch::Sender::new(false),
+            job_semaphore: Semaphore::new(options.job_limit),
+            // Pools of zero depth have no limit.
+            pool_semaphores: configuration
+                .pools()
+                .iter()
+                .filter(|(_, &depth)| depth > 0)
+                .map(|(name, &depth)| (name.clone(), Semaphore::new(depth)))
+                .collect(),
             configuration,
             build_futures: DashMap::new(),
+            start_time: Instant::now(),
+            profile_entries: Default::default(),
             options,
         }
     }
@@ -48,6 +86,91 @@ impl Context {
         &self.build_graph
     }
 
+    // Builds considered to be run in a dry-run mode.
+    pub fn dry_run_builds(&self) -> &DashSet<BuildId> {
+        &self.dry_run_builds
+    }
+
+    // The number of builds with rules to be run except up-to-date ones.
+    pub fn build_count(&self) -> usize {
+        self.build_count
+            .load(Ordering::SeqCst)
+            .saturating_sub(self.skipped_build_count.load(Ordering::SeqCst))
+    }
+
+    pub fn add_build_count(&self, count: usize) {
+        self.build_count.fetch_add(count, Ordering::SeqCst);
+    }
+
+    // Records an up-to-date build.
+    pub fn skip_build(&self) {
+        self.skipped_build_count.fetch_add(1, Ordering::SeqCst);
+    }
+
+    // Records a build whose command is started.
+    pub fn start_build(&self) {
+        self.started_build_count.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn started_build_count(&self) -> usize {
+        self.started_build_count.load(Ordering::SeqCst)
+    }
+
+    // Records a finished build and returns the number of finished builds.
+    pub fn finish_build(&self) -> usize {
+        self.finished_build_count.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    pub fn finished_build_count(&self) -> usize {
+        self.finished_build_count.load(Ordering::SeqCst)
+    }
+
+    pub fn failure_count(&self) -> usize {
+        self.failure_count.load(Ordering::SeqCst)
+    }
+
+    pub fn record_failure(&self) {
+        self.failure_count.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn is_failure_limit_reached(&self) -> bool {
+        self.options.keep_going != 0 && self.failure_count() >= self.options.keep_going
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        *self.interruption.borrow()
+    }
+
+    pub fn interrupt(&self) {
+        self.interruption.send_replace(true);
+    }
+
+    // Waits for interruption of builds.
+    pub async fn interrupted(&self) {
+        // The sender is never dropped while the receiver is alive.
+        let _ = self.interruption.subscribe().wait_for(|&value| value).await;
+    }
+
+    pub fn start_time(&self) -> Instant {
+        self.start_time
+    }
+
+    pub fn profile_entries(&self) -> Vec<ProfileEntry> {
+        self.profile_entries.lock().unwrap().clone()
+    }
+
+    pub fn record_profile(&self, entry: ProfileEntry) {
+        self.profile_entries.lock().unwrap().push(entry);
+    }
+
+    pub fn job_semaphore(&self) -> &Semaphore {
+        &self.job_semaphore
+    }
+
+    pub fn pool_semaphore(&self, pool: &str) -> Option<&Semaphore> {
+        self.pool_semaphores.get(pool)
+    }
+
     pub fn options(&self) -> &Options {
         &self.options
     }
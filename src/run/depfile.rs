@@ -0,0 +1,114 @@
+// Parses dependencies in a Makefile format generated by compilers like
+// `gcc -MD`. Targets are ignored as they are always outputs of a build.
+pub fn parse(source: &str) -> Vec<String> {
+    let mut dependencies = vec![];
+    let mut path = String::new();
+    let mut target = true;
+    let mut characters = source.chars().peekable();
+
+    let mut push = |path: &mut String, target: bool| {
+        if !target && !path.is_empty() && !dependencies.contains(path) {
+            dependencies.push(path.clone());
+        }
+
+        path.clear();
+    };
+
+    while let Some(character) = characters.next() {
+        match character {
+            '\\' => match characters.peek() {
+                Some('\n') => {
+                    characters.next();
+                    push(&mut path, target);
+                }
+                Some('\r') => {
+                    characters.next();
+                    characters.next_if_eq(&'\n');
+                    push(&mut path, target);
+                }
+                Some(&character @ (' ' | '#')) => {
+                    characters.next();
+                    path.push(character);
+                }
+                _ => path.push(character),
+            },
+            '$' if characters.next_if_eq(&'$').is_some() => path.push('$'),
+            ':' if target
+                && characters
+                    .peek()
+                    .map(|character| character.is_whitespace())
+                    .unwrap_or(true) =>
+            {
+                push(&mut path, target);
+                target = false;
+            }
+            '\n' | '\r' => {
+                push(&mut path, target);
+                target = true;
+            }
+            ' ' | '\t' => push(&mut path, target),
+            _ => path.push(character),
+        }
+    }
+
+    push(&mut path, target);
+
+    dependencies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_empty() {
+        assert_eq!(parse(""), Vec::<String>::new());
+    }
+
+    #[test]
+    fn parse_dependency() {
+        assert_eq!(parse("foo.o: foo.c"), vec!["foo.c"]);
+        assert_eq!(parse("foo.o: foo.c\n"), vec!["foo.c"]);
+        assert_eq!(parse("foo.o : foo.c"), vec!["foo.c"]);
+    }
+
+    #[test]
+    fn parse_dependencies() {
+        assert_eq!(parse("foo.o: foo.c foo.h"), vec!["foo.c", "foo.h"]);
+    }
+
+    #[test]
+    fn parse_multiple_targets() {
+        assert_eq!(parse("foo.o foo.d: foo.c"), vec!["foo.c"]);
+    }
+
+    #[test]
+    fn parse_multiple_rules() {
+        assert_eq!(
+            parse("foo.o: foo.c foo.h\nfoo.h:\n"),
+            vec!["foo.c", "foo.h"]
+        );
+    }
+
+    #[test]
+    fn parse_line_continuation() {
+        assert_eq!(
+            parse("foo.o: foo.c \\\n  foo.h \\\r\n  bar.h\n"),
+            vec!["foo.c", "foo.h", "bar.h"]
+        );
+    }
+
+    #[test]
+    fn parse_escaped_characters() {
+        assert_eq!(
+            parse("foo.o: foo\\ bar.c foo\\#.h foo$$.h foo\\bar.h"),
+            vec!["foo bar.c", "foo#.h", "foo$.h", "foo\\bar.h"]
+        );
+    }
+
+    #[test]
+    fn parse_colon_in_path() {
+        assert_eq!(parse("foo.o: c:\\foo.c"), vec!["c:\\foo.c"]);
+    }
+}
@@ -1,15 +1,19 @@
 use super::context::Context;
 use crate::{
     error::ApplicationError,
+    hash_algorithm::HashAlgorithm,
     hash_type::HashType,
+    infrastructure::FileSystem,
     ir::{Build, Rule},
 };
+use futures::{stream, StreamExt, TryStreamExt};
 use std::{
-    collections::hash_map::DefaultHasher,
     hash::{Hash, Hasher},
+    path::Path,
 };
 
 const BUFFER_CAPACITY: usize = 2 << 10;
+const METADATA_CONCURRENCY: usize = 16;
 
 pub async fn calculate_timestamp_hash(
     context: &Context,
@@ -17,22 +21,39 @@ pub async fn calculate_timestamp_hash(
     file_inputs: &[&str],
     phony_inputs: &[&str],
 ) -> Result<u64, ApplicationError> {
-    if let Some(hash) = calculate_phony_hash(build, file_inputs, phony_inputs) {
+    if let Some(hash) = calculate_phony_hash(
+        context.options().hash_algorithm,
+        build,
+        file_inputs,
+        phony_inputs,
+    ) {
         return Ok(hash);
     }
 
-    let mut hasher = DefaultHasher::new();
+    let mut hasher = context.options().hash_algorithm.hasher();
 
     hash_command(build, &mut hasher);
 
-    for input in file_inputs {
-        context
-            .application()
-            .file_system()
-            .metadata(input.as_ref())
-            .await?
-            .modified_time()
-            .hash(&mut hasher);
+    // Inputs are stated concurrently but hashed in order.
+    let mut times = stream::iter(
+        file_inputs
+            .iter()
+            .map(|input| async move {
+                Ok::<_, ApplicationError>(
+                    context
+                        .application()
+                        .file_system()
+                        .metadata(input.as_ref())
+                        .await?
+                        .modified_time(),
+                )
+            })
+            .collect::<Vec<_>>(),
+    )
+    .buffered(METADATA_CONCURRENCY);
+
+    while let Some(time) = times.try_next().await? {
+        time.hash(&mut hasher);
     }
 
     for &input in phony_inputs {
@@ -42,30 +63,43 @@ pub async fn calculate_timestamp_hash(
     Ok(hasher.finish())
 }
 
+pub fn calculate_command_hash(context: &Context, build: &Build) -> u64 {
+    let mut hasher = context.options().hash_algorithm.hasher();
+
+    hash_command(build, &mut hasher);
+
+    hasher.finish()
+}
+
 pub async fn calculate_content_hash(
     context: &Context,
     build: &Build,
     file_inputs: &[&str],
     phony_inputs: &[&str],
 ) -> Result<u64, ApplicationError> {
-    if let Some(hash) = calculate_phony_hash(build, file_inputs, phony_inputs) {
+    if let Some(hash) = calculate_phony_hash(
+        context.options().hash_algorithm,
+        build,
+        file_inputs,
+        phony_inputs,
+    ) {
         return Ok(hash);
     }
 
-    let mut hasher = DefaultHasher::new();
+    let mut hasher = context.options().hash_algorithm.hasher();
 
     hash_command(build, &mut hasher);
 
-    let mut buffer = Vec::with_capacity(BUFFER_CAPACITY);
+    let mut buffer = vec![0; BUFFER_CAPACITY];
 
     for input in file_inputs {
-        context
-            .application()
-            .file_system()
-            .read_file(input.as_ref(), &mut buffer)
-            .await?;
-        buffer.hash(&mut hasher);
-        buffer.clear();
+        hash_file(
+            context.application().file_system(),
+            input.as_ref(),
+            &mut buffer,
+            &mut hasher,
+        )
+        .await?;
     }
 
     for &input in phony_inputs {
@@ -75,6 +109,22 @@ pub async fn calculate_content_hash(
     Ok(hasher.finish())
 }
 
+// Files are hashed in the same way as `Vec<u8>` so that hashes are identical to
+// the ones of whole file contents.
+async fn hash_file(
+    file_system: &(dyn FileSystem + Send + Sync),
+    path: &Path,
+    buffer: &mut [u8],
+    hasher: &mut (impl Hasher + Send),
+) -> Result<(), ApplicationError> {
+    hasher.write_usize(file_system.metadata(path).await?.size() as usize);
+    file_system
+        .read_file_chunks(path, buffer, &mut |chunk| hasher.write(chunk))
+        .await?;
+
+    Ok(())
+}
+
 fn get_build_hash(
     context: &Context,
     r#type: HashType,
@@ -95,14 +145,135 @@ fn get_build_hash(
         .ok_or_else(|| ApplicationError::InputNotBuilt(input.into()))
 }
 
-fn calculate_phony_hash(build: &Build, file_inputs: &[&str], phony_inputs: &[&str]) -> Option<u64> {
+// Phony builds without any input are pure aliases always satisfied and hashed
+// by their outputs so that they do not make their dependents outdated on every
+// run. Phony builds with inputs are hashed by their inputs instead.
+fn calculate_phony_hash(
+    algorithm: HashAlgorithm,
+    build: &Build,
+    file_inputs: &[&str],
+    phony_inputs: &[&str],
+) -> Option<u64> {
     if build.rule().is_none() && file_inputs.is_empty() && phony_inputs.is_empty() {
-        Some(rand::random())
+        let mut hasher = algorithm.hasher();
+
+        build.outputs().hash(&mut hasher);
+
+        Some(hasher.finish())
     } else {
         None
     }
 }
 
+// Changes of commands in generator rules do not make their builds outdated.
 fn hash_command(build: &Build, hasher: &mut impl Hasher) {
-    build.rule().map(Rule::command).hash(hasher);
+    build
+        .rule()
+        .filter(|rule| !rule.generator())
+        .map(Rule::command)
+        .hash(hasher);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hasher::FnvHasher, infrastructure::OsFileSystem};
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    fn phony_build(outputs: Vec<Arc<str>>) -> Build {
+        Build::new(outputs, vec![], None, vec![], vec![], None)
+    }
+
+    #[test]
+    fn calculate_stable_phony_hash() {
+        let build = phony_build(vec!["foo".into()]);
+
+        assert_eq!(
+            calculate_phony_hash(HashAlgorithm::Fnv, &build, &[], &[]),
+            calculate_phony_hash(HashAlgorithm::Fnv, &build, &[], &[])
+        );
+        assert_ne!(
+            calculate_phony_hash(HashAlgorithm::Fnv, &build, &[], &[]),
+            calculate_phony_hash(
+                HashAlgorithm::Fnv,
+                &phony_build(vec!["bar".into()]),
+                &[],
+                &[]
+            )
+        );
+    }
+
+    #[test]
+    fn calculate_no_phony_hash_with_inputs() {
+        assert_eq!(
+            calculate_phony_hash(
+                HashAlgorithm::Fnv,
+                &phony_build(vec!["foo".into()]),
+                &["bar"],
+                &[]
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn calculate_no_phony_hash_with_phony_inputs() {
+        assert_eq!(
+            calculate_phony_hash(
+                HashAlgorithm::Fnv,
+                &phony_build(vec!["foo".into()]),
+                &[],
+                &["bar"]
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn calculate_no_phony_hash_for_rule() {
+        assert_eq!(
+            calculate_phony_hash(
+                HashAlgorithm::Fnv,
+                &Build::new(
+                    vec!["foo".into()],
+                    vec![],
+                    Some(Rule::new("", None)),
+                    vec![],
+                    vec![],
+                    None
+                ),
+                &[],
+                &[]
+            ),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn hash_file_in_chunks() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("foo");
+        let content = (0..BUFFER_CAPACITY * 3 + 1)
+            .map(|index| index as u8)
+            .collect::<Vec<_>>();
+        let file_system = OsFileSystem::new(1, true);
+
+        file_system.write_file(&path, &content).await.unwrap();
+
+        let mut hasher = FnvHasher::new();
+        hash_file(
+            &file_system,
+            &path,
+            &mut vec![0; BUFFER_CAPACITY],
+            &mut hasher,
+        )
+        .await
+        .unwrap();
+
+        let mut expected_hasher = FnvHasher::new();
+        content.hash(&mut expected_hasher);
+
+        assert_eq!(hasher.finish(), expected_hasher.finish());
+    }
 }
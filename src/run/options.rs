@@ -1,5 +1,74 @@
-#[derive(Clone, Debug, Eq, PartialEq)]
+use crate::{hash_algorithm::HashAlgorithm, verbosity::Verbosity};
+use std::{path::PathBuf, time::Duration};
+
+const DEFAULT_STATUS_FORMAT: &str = "[%f/%t] ";
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Options {
     pub debug: bool,
     pub profile: bool,
+    // A file to write timings of commands into in JSON.
+    pub profile_file: Option<PathBuf>,
+    // A file to write timings of commands into in Chrome's trace event format.
+    pub trace_file: Option<PathBuf>,
+    pub dry_run: bool,
+    // Whether to run builds even if they are up to date.
+    pub force: bool,
+    // Whether to show reasons why builds are run or skipped.
+    pub explain: bool,
+    // The number of failed builds to stop at. Zero means no limit.
+    pub keep_going: usize,
+    // The maximum number of commands running at once
+    pub job_limit: usize,
+    pub timeout: Option<Duration>,
+    // The maximum load average of a system to start new jobs.
+    pub load_limit: Option<f64>,
+    pub hash_algorithm: HashAlgorithm,
+    // Whether to check existence of source inputs before running builds.
+    pub check_inputs: bool,
+    // Whether to check existence of outputs after running builds.
+    pub verify_outputs: bool,
+    // Whether to refuse to overwrite existing files never built before.
+    pub protect_sources: bool,
+    pub verbosity: Verbosity,
+    // Whether to show a summary of builds on their completion.
+    pub summary: bool,
+    // A format of status lines in Ninja's `NINJA_STATUS` syntax.
+    pub status_format: String,
+    pub verbose: bool,
+    // Whether to color diagnostics.
+    pub color: bool,
+    pub log_prefix: Option<String>,
+    // A directory where relative working directories of commands are resolved
+    // from.
+    pub build_directory: PathBuf,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            debug: false,
+            profile: false,
+            profile_file: None,
+            trace_file: None,
+            dry_run: false,
+            force: false,
+            explain: false,
+            keep_going: 1,
+            job_limit: num_cpus::get(),
+            timeout: None,
+            load_limit: None,
+            hash_algorithm: Default::default(),
+            check_inputs: false,
+            verify_outputs: false,
+            protect_sources: false,
+            verbosity: Default::default(),
+            summary: true,
+            status_format: DEFAULT_STATUS_FORMAT.into(),
+            verbose: false,
+            color: false,
+            log_prefix: None,
+            build_directory: Default::default(),
+        }
+    }
 }
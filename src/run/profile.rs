@@ -0,0 +1,175 @@
+use crate::json;
+use std::{fmt::Write, sync::Arc, time::Duration};
+
+// A timing of a command run for a build.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProfileEntry {
+    output: Arc<str>,
+    command: String,
+    start_offset: Duration,
+    duration: Duration,
+}
+
+impl ProfileEntry {
+    pub fn new(
+        output: Arc<str>,
+        command: impl Into<String>,
+        start_offset: Duration,
+        duration: Duration,
+    ) -> Self {
+        Self {
+            output,
+            command: command.into(),
+            start_offset,
+            duration,
+        }
+    }
+}
+
+// Renders profile entries in JSON in order of their start times.
+pub fn render(entries: &[ProfileEntry]) -> String {
+    let mut entries = entries.iter().collect::<Vec<_>>();
+
+    entries.sort_by_key(|entry| entry.start_offset);
+
+    let mut string = String::new();
+
+    writeln!(string, "[").unwrap();
+
+    for (index, entry) in entries.iter().enumerate() {
+        writeln!(
+            string,
+            "  {{\"output\": {}, \"command\": {}, \"duration_ms\": {}, \"start_offset_ms\": {}}}{}",
+            json::quote(&entry.output),
+            json::quote(&entry.command),
+            entry.duration.as_millis(),
+            entry.start_offset.as_millis(),
+            if index + 1 < entries.len() { "," } else { "" }
+        )
+        .unwrap();
+    }
+
+    writeln!(string, "]").unwrap();
+
+    string
+}
+
+// Renders profile entries in Chrome's trace event format. Each entry is put on
+// the first lane free at its start time so that lanes correspond to job slots.
+pub fn render_trace(entries: &[ProfileEntry]) -> String {
+    let mut entries = entries.iter().collect::<Vec<_>>();
+
+    entries.sort_by_key(|entry| entry.start_offset);
+
+    let mut lane_end_offsets = vec![];
+    let mut string = String::new();
+
+    writeln!(string, "{{\"traceEvents\": [").unwrap();
+
+    for (index, entry) in entries.iter().enumerate() {
+        let lane = lane_end_offsets
+            .iter()
+            .position(|&offset| offset <= entry.start_offset)
+            .unwrap_or_else(|| {
+                lane_end_offsets.push(Duration::default());
+                lane_end_offsets.len() - 1
+            });
+
+        lane_end_offsets[lane] = entry.start_offset + entry.duration;
+
+        writeln!(
+            string,
+            "  {{\"name\": {}, \"cat\": \"build\", \"ph\": \"X\", \"ts\": {}, \"dur\": {}, \"pid\": 0, \"tid\": {}, \"args\": {{\"command\": {}}}}}{}",
+            json::quote(&entry.output),
+            entry.start_offset.as_micros(),
+            entry.duration.as_micros(),
+            lane,
+            json::quote(&entry.command),
+            if index + 1 < entries.len() { "," } else { "" }
+        )
+        .unwrap();
+    }
+
+    writeln!(string, "]}}").unwrap();
+
+    string
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn render_empty() {
+        assert_eq!(render(&[]), "[\n]\n");
+    }
+
+    #[test]
+    fn render_entries() {
+        assert_eq!(
+            render(&[
+                ProfileEntry::new(
+                    "bar".into(),
+                    "cc \"bar.c\"",
+                    Duration::from_millis(5),
+                    Duration::from_millis(20),
+                ),
+                ProfileEntry::new(
+                    "foo".into(),
+                    "cc foo.c",
+                    Duration::from_millis(1),
+                    Duration::from_millis(10),
+                ),
+            ]),
+            [
+                "[",
+                r#"  {"output": "foo", "command": "cc foo.c", "duration_ms": 10, "start_offset_ms": 1},"#,
+                r#"  {"output": "bar", "command": "cc \"bar.c\"", "duration_ms": 20, "start_offset_ms": 5}"#,
+                "]",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn render_empty_trace() {
+        assert_eq!(render_trace(&[]), "{\"traceEvents\": [\n]}\n");
+    }
+
+    #[test]
+    fn render_trace_entries() {
+        assert_eq!(
+            render_trace(&[
+                ProfileEntry::new(
+                    "bar".into(),
+                    "cc bar.c",
+                    Duration::from_millis(5),
+                    Duration::from_millis(20),
+                ),
+                ProfileEntry::new(
+                    "foo".into(),
+                    "cc foo.c",
+                    Duration::from_millis(1),
+                    Duration::from_millis(10),
+                ),
+                ProfileEntry::new(
+                    "baz".into(),
+                    "cc baz.c",
+                    Duration::from_millis(11),
+                    Duration::from_millis(1),
+                ),
+            ]),
+            [
+                r#"{"traceEvents": ["#,
+                r#"  {"name": "foo", "cat": "build", "ph": "X", "ts": 1000, "dur": 10000, "pid": 0, "tid": 0, "args": {"command": "cc foo.c"}},"#,
+                r#"  {"name": "bar", "cat": "build", "ph": "X", "ts": 5000, "dur": 20000, "pid": 0, "tid": 1, "args": {"command": "cc bar.c"}},"#,
+                r#"  {"name": "baz", "cat": "build", "ph": "X", "ts": 11000, "dur": 1000, "pid": 0, "tid": 0, "args": {"command": "cc baz.c"}}"#,
+                "]}",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -0,0 +1,77 @@
+// Parses dependencies in outputs of MSVC with `/showIncludes` options. It
+// returns the outputs without lines of the dependencies and the dependencies.
+pub fn parse(output: &[u8], prefix: &str) -> (Vec<u8>, Vec<String>) {
+    let mut filtered_output = vec![];
+    let mut dependencies = Vec::<String>::new();
+
+    for line in output.split_inclusive(|&byte| byte == b'\n') {
+        if let Some(path) = line.strip_prefix(prefix.as_bytes()) {
+            let path = String::from_utf8_lossy(path);
+            let path = path.trim();
+
+            if !path.is_empty() && !dependencies.iter().any(|other| other == path) {
+                dependencies.push(path.into());
+            }
+        } else {
+            filtered_output.extend_from_slice(line);
+        }
+    }
+
+    (filtered_output, dependencies)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::DEFAULT_MSVC_DEPENDENCY_PREFIX;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_empty() {
+        assert_eq!(parse(b"", DEFAULT_MSVC_DEPENDENCY_PREFIX), (vec![], vec![]));
+    }
+
+    #[test]
+    fn parse_output_without_dependency() {
+        assert_eq!(
+            parse(b"foo.c\n", DEFAULT_MSVC_DEPENDENCY_PREFIX),
+            (b"foo.c\n".to_vec(), vec![])
+        );
+    }
+
+    #[test]
+    fn parse_dependencies() {
+        assert_eq!(
+            parse(
+                b"foo.c\r\nNote: including file: foo.h\r\nNote: including file:  C:\\bar baz.h\r\nwarning\r\n",
+                DEFAULT_MSVC_DEPENDENCY_PREFIX
+            ),
+            (
+                b"foo.c\r\nwarning\r\n".to_vec(),
+                vec!["foo.h".into(), "C:\\bar baz.h".into()]
+            )
+        );
+    }
+
+    #[test]
+    fn parse_duplicate_dependencies() {
+        assert_eq!(
+            parse(
+                b"Note: including file: foo.h\nNote: including file: foo.h",
+                DEFAULT_MSVC_DEPENDENCY_PREFIX
+            ),
+            (vec![], vec!["foo.h".into()])
+        );
+    }
+
+    #[test]
+    fn parse_dependencies_with_custom_prefix() {
+        assert_eq!(
+            parse(
+                b"Remarque : inclusion du fichier : foo.h\n",
+                "Remarque : inclusion du fichier :"
+            ),
+            (vec![], vec!["foo.h".into()])
+        );
+    }
+}
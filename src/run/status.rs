@@ -0,0 +1,94 @@
+use std::{fmt::Write, time::Duration};
+
+// Counts of builds and elapsed time shown in status lines.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Progress {
+    pub started: usize,
+    pub finished: usize,
+    pub total: usize,
+    pub elapsed: Duration,
+}
+
+// Formats a status line prefix with placeholders compatible with Ninja's
+// `NINJA_STATUS`. Unknown placeholders are kept as they are.
+pub fn format(format: &str, progress: &Progress) -> String {
+    let mut string = String::with_capacity(format.len());
+    let mut characters = format.chars();
+
+    while let Some(character) = characters.next() {
+        if character != '%' {
+            string.push(character);
+            continue;
+        }
+
+        match characters.next() {
+            Some('s') => write!(string, "{}", progress.started).unwrap(),
+            Some('f') => write!(string, "{}", progress.finished).unwrap(),
+            Some('t') => write!(string, "{}", progress.total).unwrap(),
+            Some('p') => write!(
+                string,
+                "{:3}%",
+                (100 * progress.finished)
+                    .checked_div(progress.total)
+                    .unwrap_or(100)
+            )
+            .unwrap(),
+            Some('e') => write!(string, "{:.3}", progress.elapsed.as_secs_f64()).unwrap(),
+            Some('%') => string.push('%'),
+            Some(character) => {
+                string.push('%');
+                string.push(character);
+            }
+            None => string.push('%'),
+        }
+    }
+
+    string
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const PROGRESS: Progress = Progress {
+        started: 3,
+        finished: 2,
+        total: 8,
+        elapsed: Duration::from_millis(1500),
+    };
+
+    #[test]
+    fn format_default() {
+        assert_eq!(format("[%f/%t] ", &PROGRESS), "[2/8] ");
+    }
+
+    #[test]
+    fn format_placeholders() {
+        assert_eq!(format("%s %f %t %p %e", &PROGRESS), "3 2 8  25% 1.500");
+    }
+
+    #[test]
+    fn format_percent_of_no_build() {
+        assert_eq!(
+            format(
+                "%p",
+                &Progress {
+                    total: 0,
+                    ..PROGRESS
+                }
+            ),
+            "100%"
+        );
+    }
+
+    #[test]
+    fn format_escaped_percent() {
+        assert_eq!(format("%%f", &PROGRESS), "%f");
+    }
+
+    #[test]
+    fn keep_unknown_placeholders() {
+        assert_eq!(format("%x %", &PROGRESS), "%x %");
+    }
+}
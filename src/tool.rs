@@ -1,3 +1,34 @@
+mod clean;
 mod clean_dead;
+mod commands;
+mod compilation_database;
+mod deps;
+mod graph;
+mod list;
+mod path;
+mod query;
+mod rules;
+mod targets;
 
+pub use clean::*;
 pub use clean_dead::*;
+pub use commands::*;
+pub use compilation_database::*;
+pub use deps::*;
+pub use graph::*;
+pub use list::*;
+pub use path::*;
+pub use query::*;
+pub use rules::*;
+pub use targets::*;
+
+use crate::ir::Build;
+
+const PHONY_RULE: &str = "phony";
+
+fn rule_name(build: &Build) -> &str {
+    build
+        .rule()
+        .map(|rule| rule.name().unwrap_or_default())
+        .unwrap_or(PHONY_RULE)
+}
@@ -0,0 +1,146 @@
+use crate::{context::Context, error::ApplicationError, ignore::IgnoreList, ir::Configuration};
+use futures::future::try_join_all;
+
+// Removes outputs of all builds except ones of generator rules and ignored
+// ones.
+pub async fn clean(
+    context: &Context,
+    configuration: &Configuration,
+    ignore_list: &IgnoreList,
+    dry_run: bool,
+) -> Result<(), ApplicationError> {
+    let count = try_join_all(
+        list(configuration)
+            .into_iter()
+            .map(|path| remove_file(context, ignore_list, path, dry_run)),
+    )
+    .await?
+    .into_iter()
+    .filter(|&removed| removed)
+    .count();
+
+    context
+        .console()
+        .lock()
+        .await
+        .write_stdout(
+            format!(
+                "{} {} {}\n",
+                count,
+                if count == 1 { "file" } else { "files" },
+                if dry_run { "to be removed" } else { "removed" }
+            )
+            .as_bytes(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+fn list(configuration: &Configuration) -> Vec<&str> {
+    let mut paths = configuration
+        .outputs()
+        .values()
+        .filter_map(|build| Some((build, build.rule()?)))
+        .filter(|(_, rule)| !rule.generator())
+        .flat_map(|(build, rule)| {
+            build
+                .outputs()
+                .iter()
+                .chain(build.implicit_outputs())
+                .map(AsRef::as_ref)
+                .chain(rule.depfile())
+                .chain(rule.response_file().map(|file| file.path()))
+        })
+        .collect::<Vec<_>>();
+
+    paths.sort();
+    paths.dedup();
+
+    paths
+}
+
+async fn remove_file(
+    context: &Context,
+    ignore_list: &IgnoreList,
+    path: &str,
+    dry_run: bool,
+) -> Result<bool, ApplicationError> {
+    match context.file_system().metadata(path.as_ref()).await {
+        Ok(metadata) if metadata.is_file() => {}
+        _ => return Ok(false),
+    }
+
+    if ignore_list.is_ignored(path) {
+        report_ignored_file(context, path).await?;
+
+        return Ok(false);
+    } else if dry_run {
+        let mut console = context.console().lock().await;
+
+        console.write_stdout(path.as_bytes()).await?;
+        console.write_stdout(b"\n").await?;
+    } else {
+        context.file_system().remove_file(path.as_ref()).await?;
+    }
+
+    Ok(true)
+}
+
+pub(super) async fn report_ignored_file(
+    context: &Context,
+    path: &str,
+) -> Result<(), ApplicationError> {
+    context
+        .console()
+        .lock()
+        .await
+        .write_stdout(format!("{path} skipped (ignored)\n").as_bytes())
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Build, ResponseFile, Rule};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn list_outputs() {
+        assert_eq!(
+            list(&Configuration::from_builds(vec![
+                Build::new(
+                    vec!["foo".into()],
+                    vec!["bar".into()],
+                    Some(
+                        Rule::new("", None)
+                            .with_depfile("foo.d")
+                            .with_response_file(ResponseFile::new("foo.rsp", ""))
+                    ),
+                    vec![],
+                    vec![],
+                    None
+                ),
+                Build::new(vec!["baz".into()], vec![], None, vec![], vec![], None),
+            ])),
+            vec!["bar", "foo", "foo.d", "foo.rsp"]
+        );
+    }
+
+    #[test]
+    fn skip_outputs_of_generator_rules() {
+        assert_eq!(
+            list(&Configuration::from_builds(vec![Build::new(
+                vec!["build.ninja".into()],
+                vec![],
+                Some(Rule::new("", None).with_generator(true)),
+                vec![],
+                vec![],
+                None
+            )])),
+            Vec::<&str>::new()
+        );
+    }
+}
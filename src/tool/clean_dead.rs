@@ -1,36 +1,70 @@
-use crate::{context::Context, ir::Configuration};
+use super::clean::report_ignored_file;
+use crate::{context::Context, ignore::IgnoreList, ir::Configuration};
 use futures::future::try_join_all;
-use std::error::Error;
-use tokio::fs::remove_file;
+use std::{collections::HashSet, error::Error};
 
+// Removes outputs recorded in a database but not in a current configuration.
+// Ignored outputs are kept in the database so that they can be removed later.
+// Records of builds not in the configuration are pruned as well.
 pub async fn clean_dead(
     context: &Context,
     configuration: &Configuration,
+    ignore_list: &IgnoreList,
 ) -> Result<(), Box<dyn Error>> {
+    // Never remove files which became sources.
+    let inputs = configuration
+        .outputs()
+        .values()
+        .flat_map(|build| build.inputs().iter().chain(build.order_only_inputs()))
+        .map(AsRef::as_ref)
+        .collect::<HashSet<&str>>();
+
     try_join_all(
         context
             .database()
             .get_outputs()?
             .iter()
-            .map(|output| remove_output(context, configuration, output)),
+            .filter(|output| {
+                !configuration.outputs().contains_key(output.as_str())
+                    && !inputs.contains(output.as_str())
+            })
+            .map(|output| remove_output(context, ignore_list, output)),
     )
     .await?;
 
+    let ids = configuration
+        .outputs()
+        .values()
+        .map(|build| build.id())
+        .collect::<HashSet<_>>();
+
+    for id in context.database().get_build_ids()? {
+        if !ids.contains(&id) {
+            context.database().remove_build(id)?;
+        }
+    }
+
     Ok(())
 }
 
 async fn remove_output(
     context: &Context,
-    configuration: &Configuration,
+    ignore_list: &IgnoreList,
     output: &str,
 ) -> Result<(), Box<dyn Error>> {
-    if configuration.outputs().contains_key(output) {
-        return Ok(());
-    } else if let Ok(metadata) = context.file_system().metadata(output.as_ref()).await {
+    if let Ok(metadata) = context.file_system().metadata(output.as_ref()).await {
         if metadata.is_file() {
-            remove_file(output).await?;
+            if ignore_list.is_ignored(output) {
+                report_ignored_file(context, output).await?;
+
+                return Ok(());
+            }
+
+            context.file_system().remove_file(output.as_ref()).await?;
         }
     }
 
+    context.database().remove_output(output)?;
+
     Ok(())
 }
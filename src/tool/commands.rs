@@ -0,0 +1,174 @@
+use crate::{
+    build_graph::BuildGraph,
+    context::Context,
+    error::ApplicationError,
+    ir::{Build, BuildId, Configuration},
+};
+use std::{collections::HashSet, sync::Arc};
+
+// Prints commands of builds in an order in which they can run.
+pub async fn commands(
+    context: &Context,
+    configuration: &Configuration,
+    outputs: &[String],
+) -> Result<(), ApplicationError> {
+    let mut console = context.console().lock().await;
+
+    for command in list(configuration, outputs)? {
+        console.write_stdout(command.as_bytes()).await?;
+        console.write_stdout(b"\n").await?;
+    }
+
+    Ok(())
+}
+
+fn list<'a>(
+    configuration: &'a Configuration,
+    outputs: &[String],
+) -> Result<Vec<&'a str>, ApplicationError> {
+    BuildGraph::new(configuration.outputs()).validate()?;
+
+    let mut commands = vec![];
+    let mut visited = HashSet::new();
+
+    for build in find_builds(configuration, outputs)? {
+        list_build(configuration, build, &mut visited, &mut commands);
+    }
+
+    Ok(commands)
+}
+
+fn find_builds<'a>(
+    configuration: &'a Configuration,
+    outputs: &[String],
+) -> Result<Vec<&'a Arc<Build>>, ApplicationError> {
+    if outputs.is_empty() {
+        let mut outputs = configuration.default_outputs().iter().collect::<Vec<_>>();
+        outputs.sort();
+
+        outputs
+            .into_iter()
+            .map(|output| {
+                configuration
+                    .outputs()
+                    .get(output)
+                    .ok_or_else(|| ApplicationError::DefaultOutputNotFound(output.clone()))
+            })
+            .collect()
+    } else {
+        outputs
+            .iter()
+            .map(|output| {
+                configuration
+                    .outputs()
+                    .get(output.as_str())
+                    .ok_or_else(|| ApplicationError::OutputNotFound(output.clone()))
+            })
+            .collect()
+    }
+}
+
+fn list_build<'a>(
+    configuration: &'a Configuration,
+    build: &'a Build,
+    visited: &mut HashSet<BuildId>,
+    commands: &mut Vec<&'a str>,
+) {
+    if !visited.insert(build.id()) {
+        return;
+    }
+
+    for input in build.inputs().iter().chain(build.order_only_inputs()) {
+        if let Some(build) = configuration.outputs().get(input) {
+            list_build(configuration, build, visited, commands);
+        }
+    }
+
+    if let Some(rule) = build.rule() {
+        commands.push(rule.command());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_graph::BuildGraphError, ir::Rule};
+    use pretty_assertions::assert_eq;
+
+    fn create_build(output: &str, command: Option<&str>, inputs: &[&str]) -> Build {
+        Build::new(
+            vec![output.into()],
+            vec![],
+            command.map(|command| Rule::new(command, None)),
+            inputs.iter().map(|&input| input.into()).collect(),
+            vec![],
+            None,
+        )
+    }
+
+    #[test]
+    fn list_commands_in_order() {
+        assert_eq!(
+            list(
+                &Configuration::from_builds(vec![
+                    create_build("foo", Some("cc foo"), &["bar", "baz"]),
+                    create_build("bar", Some("cc bar"), &["baz"]),
+                    create_build("baz", Some("cc baz"), &[]),
+                ])
+                .with_default_outputs(&["foo"]),
+                &[]
+            )
+            .unwrap(),
+            vec!["cc baz", "cc bar", "cc foo"]
+        );
+    }
+
+    #[test]
+    fn list_commands_of_outputs() {
+        assert_eq!(
+            list(
+                &Configuration::from_builds(vec![
+                    create_build("foo", Some("cc foo"), &["bar"]),
+                    create_build("bar", Some("cc bar"), &[]),
+                ])
+                .with_default_outputs(&["foo"]),
+                &["bar".into()]
+            )
+            .unwrap(),
+            vec!["cc bar"]
+        );
+    }
+
+    #[test]
+    fn skip_phony_builds() {
+        assert_eq!(
+            list(
+                &Configuration::from_builds(vec![
+                    create_build("foo", None, &["bar"]),
+                    create_build("bar", Some("cc bar"), &[]),
+                ])
+                .with_default_outputs(&["foo"]),
+                &[]
+            )
+            .unwrap(),
+            vec!["cc bar"]
+        );
+    }
+
+    #[test]
+    fn fail_to_list_commands_with_circular_dependency() {
+        assert!(matches!(
+            list(
+                &Configuration::from_builds(vec![
+                    create_build("foo", Some("cc foo"), &["bar"]),
+                    create_build("bar", Some("cc bar"), &["foo"]),
+                ])
+                .with_default_outputs(&["foo"]),
+                &[]
+            ),
+            Err(ApplicationError::BuildGraph(
+                BuildGraphError::CircularDependency(_)
+            ))
+        ));
+    }
+}
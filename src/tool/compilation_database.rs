@@ -0,0 +1,160 @@
+use crate::{
+    context::Context,
+    error::ApplicationError,
+    ir::{Build, Configuration},
+    json,
+};
+use std::{env::current_dir, fmt::Write, path::Path};
+
+// Prints a compilation database in JSON for Clang tools. If no rule name is
+// given, all rules are included.
+pub async fn compilation_database(
+    context: &Context,
+    configuration: &Configuration,
+    rules: &[String],
+) -> Result<(), ApplicationError> {
+    context
+        .console()
+        .lock()
+        .await
+        .write_stdout(render(configuration, rules, &current_dir()?).as_bytes())
+        .await?;
+
+    Ok(())
+}
+
+fn render(configuration: &Configuration, rules: &[String], directory: &Path) -> String {
+    let mut builds = configuration
+        .outputs()
+        .values()
+        .filter(|build| !build.inputs().is_empty())
+        .filter(|build| {
+            build.rule().is_some_and(|rule| {
+                rules.is_empty() || rules.iter().any(|name| Some(name.as_str()) == rule.name())
+            })
+        })
+        .collect::<Vec<_>>();
+
+    builds.sort_by(|one, other| one.outputs().cmp(other.outputs()));
+    builds.dedup_by_key(|build| build.id());
+
+    let mut string = String::new();
+
+    writeln!(string, "[").unwrap();
+
+    for (index, build) in builds.iter().enumerate() {
+        render_build(&mut string, build, directory);
+
+        writeln!(
+            string,
+            "{}",
+            if index + 1 < builds.len() { "," } else { "" }
+        )
+        .unwrap();
+    }
+
+    writeln!(string, "]").unwrap();
+
+    string
+}
+
+fn render_build(string: &mut String, build: &Build, directory: &Path) {
+    let fields = [
+        ("directory", directory.display().to_string()),
+        ("command", build.rule().unwrap().command().into()),
+        ("file", build.inputs()[0].to_string()),
+        ("output", build.outputs()[0].to_string()),
+    ];
+
+    writeln!(string, "  {{").unwrap();
+
+    for (index, (key, value)) in fields.iter().enumerate() {
+        writeln!(
+            string,
+            "    {}: {}{}",
+            json::quote(key),
+            json::quote(value),
+            if index + 1 < fields.len() { "," } else { "" }
+        )
+        .unwrap();
+    }
+
+    write!(string, "  }}").unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::Rule;
+    use pretty_assertions::assert_eq;
+
+    fn create_build(output: &str, rule: Option<(&str, &str)>, inputs: &[&str]) -> Build {
+        Build::new(
+            vec![output.into()],
+            vec![],
+            rule.map(|(name, command)| Rule::new(command, None).with_name(name)),
+            inputs.iter().map(|&input| input.into()).collect(),
+            vec![],
+            None,
+        )
+    }
+
+    #[test]
+    fn render_empty() {
+        assert_eq!(
+            render(&Configuration::from_builds(vec![]), &[], Path::new("/foo")),
+            "[\n]\n"
+        );
+    }
+
+    #[test]
+    fn render_builds() {
+        assert_eq!(
+            render(
+                &Configuration::from_builds(vec![
+                    create_build("foo.o", Some(("cc", "cc -c \"foo.c\"")), &["foo.c"]),
+                    create_build("bar.o", Some(("cc", "cc -c bar.c")), &["bar.c"]),
+                    create_build("foo", Some(("link", "ld foo.o")), &["foo.o"]),
+                    create_build("all", None, &["foo"]),
+                ]),
+                &["cc".into()],
+                Path::new("/foo")
+            ),
+            [
+                "[",
+                "  {",
+                r#"    "directory": "/foo","#,
+                r#"    "command": "cc -c bar.c","#,
+                r#"    "file": "bar.c","#,
+                r#"    "output": "bar.o""#,
+                "  },",
+                "  {",
+                r#"    "directory": "/foo","#,
+                r#"    "command": "cc -c \"foo.c\"","#,
+                r#"    "file": "foo.c","#,
+                r#"    "output": "foo.o""#,
+                "  }",
+                "]",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn render_all_rules() {
+        assert_eq!(
+            render(
+                &Configuration::from_builds(vec![
+                    create_build("foo.o", Some(("cc", "cc")), &["foo.c"]),
+                    create_build("foo", Some(("link", "ld")), &["foo.o"]),
+                ]),
+                &[],
+                Path::new("/foo")
+            )
+            .matches("\"directory\"")
+            .count(),
+            2
+        );
+    }
+}
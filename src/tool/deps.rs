@@ -0,0 +1,120 @@
+use crate::{
+    context::Context,
+    error::ApplicationError,
+    ir::{Build, Configuration},
+};
+use std::{collections::HashSet, fmt::Write, sync::Arc};
+
+// Prints dependencies discovered via depfiles or outputs of commands.
+pub async fn deps(
+    context: &Context,
+    configuration: &Configuration,
+    outputs: &[String],
+) -> Result<(), ApplicationError> {
+    let builds = if outputs.is_empty() {
+        let mut ids = HashSet::new();
+        let mut builds = configuration
+            .outputs()
+            .values()
+            .filter(|build| {
+                build.rule().is_some_and(|rule| {
+                    rule.depfile().is_some() || rule.dependency_format().is_some()
+                })
+            })
+            .filter(|build| ids.insert(build.id()))
+            .collect::<Vec<_>>();
+
+        builds.sort_by_key(|build| build.outputs()[0].clone());
+
+        builds
+    } else {
+        outputs
+            .iter()
+            .map(|output| {
+                configuration
+                    .outputs()
+                    .get(output.as_str())
+                    .ok_or_else(|| ApplicationError::OutputNotFound(output.clone()))
+            })
+            .collect::<Result<_, _>>()?
+    };
+
+    let entries = builds
+        .into_iter()
+        .map(|build| Ok((build, context.database().get_dependencies(build.id())?)))
+        .collect::<Result<Vec<_>, ApplicationError>>()?;
+
+    context
+        .console()
+        .lock()
+        .await
+        .write_stdout(render(&entries).as_bytes())
+        .await?;
+
+    Ok(())
+}
+
+fn render(entries: &[(&Arc<Build>, Option<Vec<String>>)]) -> String {
+    let mut string = String::new();
+
+    for (build, dependencies) in entries {
+        let output = &build.outputs()[0];
+
+        if let Some(dependencies) = dependencies {
+            writeln!(string, "{output}: #deps {}", dependencies.len()).unwrap();
+
+            for dependency in dependencies {
+                writeln!(string, "    {dependency}").unwrap();
+            }
+        } else {
+            writeln!(string, "{output}: deps not found").unwrap();
+        }
+
+        writeln!(string).unwrap();
+    }
+
+    string
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn build(output: &str) -> Arc<Build> {
+        Build::new(vec![output.into()], vec![], None, vec![], vec![], None).into()
+    }
+
+    #[test]
+    fn render_nothing() {
+        assert_eq!(render(&[]), "");
+    }
+
+    #[test]
+    fn render_dependencies() {
+        assert_eq!(
+            render(&[
+                (&build("foo.o"), Some(vec!["foo.c".into(), "foo.h".into()])),
+                (&build("bar.o"), Some(vec![])),
+            ]),
+            [
+                "foo.o: #deps 2",
+                "    foo.c",
+                "    foo.h",
+                "",
+                "bar.o: #deps 0",
+                "",
+                ""
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn render_missing_dependencies() {
+        assert_eq!(
+            render(&[(&build("foo.o"), None)]),
+            "foo.o: deps not found\n\n"
+        );
+    }
+}
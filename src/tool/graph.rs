@@ -0,0 +1,201 @@
+use crate::{
+    context::Context,
+    error::ApplicationError,
+    ir::{Build, Configuration},
+};
+use std::{collections::HashSet, fmt::Write, sync::Arc};
+
+// Prints a dependency graph in the DOT language of Graphviz.
+pub async fn graph(
+    context: &Context,
+    configuration: &Configuration,
+    outputs: &[String],
+) -> Result<(), ApplicationError> {
+    context
+        .console()
+        .lock()
+        .await
+        .write_stdout(render(configuration, outputs)?.as_bytes())
+        .await?;
+
+    Ok(())
+}
+
+fn render(configuration: &Configuration, outputs: &[String]) -> Result<String, ApplicationError> {
+    let mut builds = if outputs.is_empty() {
+        configuration.outputs().values().cloned().collect()
+    } else {
+        find_reachable_builds(configuration, outputs)?
+    };
+
+    builds.sort_by(|one, other| one.outputs().cmp(other.outputs()));
+    builds.dedup_by_key(|build| build.id());
+
+    let mut string = String::new();
+
+    writeln!(string, "digraph turtle {{").unwrap();
+    writeln!(string, "  rankdir=\"LR\"").unwrap();
+    writeln!(string, "  node [fontsize=10, shape=box, height=0.25]").unwrap();
+    writeln!(string, "  edge [fontsize=10]").unwrap();
+
+    for build in &builds {
+        for output in build.outputs().iter().chain(build.implicit_outputs()) {
+            if build.rule().is_some() {
+                writeln!(string, "  {}", quote(output)).unwrap();
+            } else {
+                writeln!(string, "  {} [shape=ellipse]", quote(output)).unwrap();
+            }
+        }
+
+        for output in build.outputs() {
+            for input in build.inputs() {
+                writeln!(string, "  {} -> {}", quote(input), quote(output)).unwrap();
+            }
+
+            for input in build.order_only_inputs() {
+                writeln!(
+                    string,
+                    "  {} -> {} [style=dotted]",
+                    quote(input),
+                    quote(output)
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    writeln!(string, "}}").unwrap();
+
+    Ok(string)
+}
+
+fn find_reachable_builds(
+    configuration: &Configuration,
+    outputs: &[String],
+) -> Result<Vec<Arc<Build>>, ApplicationError> {
+    let mut builds = outputs
+        .iter()
+        .map(|output| {
+            configuration
+                .outputs()
+                .get(output.as_str())
+                .cloned()
+                .ok_or_else(|| ApplicationError::OutputNotFound(output.clone()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut ids = builds
+        .iter()
+        .map(|build| build.id())
+        .collect::<HashSet<_>>();
+    let mut index = 0;
+
+    while let Some(build) = builds.get(index).cloned() {
+        for input in build.inputs().iter().chain(build.order_only_inputs()) {
+            if let Some(build) = configuration.outputs().get(input) {
+                if ids.insert(build.id()) {
+                    builds.push(build.clone());
+                }
+            }
+        }
+
+        index += 1;
+    }
+
+    Ok(builds)
+}
+
+fn quote(string: &str) -> String {
+    format!("\"{}\"", string.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::Rule;
+    use pretty_assertions::assert_eq;
+
+    fn create_build(output: &str, rule: Option<Rule>, inputs: Vec<Arc<str>>) -> Build {
+        Build::new(vec![output.into()], vec![], rule, inputs, vec![], None)
+    }
+
+    fn render_lines(lines: &[&str]) -> String {
+        [
+            "digraph turtle {",
+            "  rankdir=\"LR\"",
+            "  node [fontsize=10, shape=box, height=0.25]",
+            "  edge [fontsize=10]",
+        ]
+        .iter()
+        .chain(lines)
+        .chain(&["}"])
+        .map(|line| line.to_string() + "\n")
+        .collect()
+    }
+
+    #[test]
+    fn render_empty_graph() {
+        assert_eq!(
+            render(&Configuration::from_builds(vec![]), &[]).unwrap(),
+            render_lines(&[])
+        );
+    }
+
+    #[test]
+    fn render_graph() {
+        assert_eq!(
+            render(
+                &Configuration::from_builds(vec![
+                    create_build("foo", Some(Rule::new("", None)), vec!["bar".into()]),
+                    create_build("baz", None, vec!["foo".into()]),
+                    Build::new(
+                        vec!["qux".into()],
+                        vec![],
+                        Some(Rule::new("", None)),
+                        vec![],
+                        vec!["foo".into()],
+                        None
+                    ),
+                ]),
+                &[]
+            )
+            .unwrap(),
+            render_lines(&[
+                "  \"baz\" [shape=ellipse]",
+                "  \"foo\" -> \"baz\"",
+                "  \"foo\"",
+                "  \"bar\" -> \"foo\"",
+                "  \"qux\"",
+                "  \"foo\" -> \"qux\" [style=dotted]",
+            ])
+        );
+    }
+
+    #[test]
+    fn render_reachable_graph() {
+        assert_eq!(
+            render(
+                &Configuration::from_builds(vec![
+                    create_build("foo", Some(Rule::new("", None)), vec!["bar".into()]),
+                    create_build("bar", Some(Rule::new("", None)), vec![]),
+                    create_build("baz", Some(Rule::new("", None)), vec![]),
+                ]),
+                &["foo".into()]
+            )
+            .unwrap(),
+            render_lines(&["  \"bar\"", "  \"foo\"", "  \"bar\" -> \"foo\""])
+        );
+    }
+
+    #[test]
+    fn fail_to_render_graph_of_unknown_output() {
+        assert!(matches!(
+            render(&Configuration::from_builds(vec![]), &["foo".into()]),
+            Err(ApplicationError::OutputNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn quote_special_characters() {
+        assert_eq!(quote("foo\"bar\\baz"), "\"foo\\\"bar\\\\baz\"");
+    }
+}
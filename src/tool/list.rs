@@ -0,0 +1,61 @@
+use crate::{arguments::Tool, context::Context, error::ApplicationError};
+use clap::ValueEnum;
+use std::fmt::Write;
+
+// Lists tools with their descriptions.
+pub async fn list(context: &Context) -> Result<(), ApplicationError> {
+    context
+        .console()
+        .lock()
+        .await
+        .write_stdout(render().as_bytes())
+        .await?;
+
+    Ok(())
+}
+
+fn render() -> String {
+    let tools = Tool::value_variants()
+        .iter()
+        .filter_map(Tool::to_possible_value)
+        .collect::<Vec<_>>();
+    let width = tools
+        .iter()
+        .map(|tool| tool.get_name().len())
+        .max()
+        .unwrap_or_default();
+    let mut string = String::new();
+
+    for tool in tools {
+        writeln!(
+            string,
+            "{:width$}  {}",
+            tool.get_name(),
+            tool.get_help().map(ToString::to_string).unwrap_or_default()
+        )
+        .unwrap();
+    }
+
+    string
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_tools() {
+        let string = render();
+
+        assert!(string.starts_with("clean      Remove built files\n"));
+        assert!(string.contains("list       List available tools\n"));
+        assert_eq!(string.lines().count(), Tool::value_variants().len());
+    }
+
+    #[test]
+    fn describe_all_tools() {
+        for tool in Tool::value_variants() {
+            assert!(tool.to_possible_value().unwrap().get_help().is_some());
+        }
+    }
+}
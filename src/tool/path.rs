@@ -0,0 +1,181 @@
+use crate::{context::Context, error::ApplicationError, ir::Configuration};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Write,
+};
+
+// Prints the shortest dependency path from an output to one of its inputs.
+// Order-only dependencies are prefixed with `||`.
+pub async fn path(
+    context: &Context,
+    configuration: &Configuration,
+    arguments: &[String],
+) -> Result<(), ApplicationError> {
+    let [from, to] = arguments else {
+        return Err(ApplicationError::InvalidToolArgument(arguments.join(" ")));
+    };
+
+    context
+        .console()
+        .lock()
+        .await
+        .write_stdout(render(configuration, from, to)?.as_bytes())
+        .await?;
+
+    Ok(())
+}
+
+fn render(configuration: &Configuration, from: &str, to: &str) -> Result<String, ApplicationError> {
+    if !configuration.outputs().contains_key(from) {
+        return Err(ApplicationError::OutputNotFound(from.into()));
+    }
+
+    // Parents of paths with flags of order-only dependencies
+    let mut parents = HashMap::<&str, (&str, bool)>::new();
+    let mut queue = VecDeque::from([from]);
+
+    while let Some(output) = queue.pop_front() {
+        if output == to {
+            break;
+        }
+
+        let Some(build) = configuration.outputs().get(output) else {
+            continue;
+        };
+
+        for (input, order_only) in build
+            .inputs()
+            .iter()
+            .map(|input| (input, false))
+            .chain(build.order_only_inputs().iter().map(|input| (input, true)))
+        {
+            if input.as_ref() != from && !parents.contains_key(input.as_ref()) {
+                parents.insert(input, (output, order_only));
+                queue.push_back(input);
+            }
+        }
+    }
+
+    if from != to && !parents.contains_key(to) {
+        return Err(ApplicationError::DependencyPathNotFound(
+            from.into(),
+            to.into(),
+        ));
+    }
+
+    let mut paths = vec![];
+    let mut path = to;
+
+    while let Some(&(parent, order_only)) = parents.get(path) {
+        paths.push((path, order_only));
+        path = parent;
+    }
+
+    let mut string = String::new();
+
+    writeln!(string, "{from}").unwrap();
+
+    for (path, order_only) in paths.into_iter().rev() {
+        writeln!(string, "{}{}", if order_only { "|| " } else { "" }, path).unwrap();
+    }
+
+    Ok(string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Build, Rule};
+    use pretty_assertions::assert_eq;
+
+    fn create_build(output: &str, inputs: &[&str], order_only_inputs: &[&str]) -> Build {
+        Build::new(
+            vec![output.into()],
+            vec![],
+            Some(Rule::new("", None)),
+            inputs.iter().map(|&input| input.into()).collect(),
+            order_only_inputs
+                .iter()
+                .map(|&input| input.into())
+                .collect(),
+            None,
+        )
+    }
+
+    #[test]
+    fn render_path() {
+        assert_eq!(
+            render(
+                &Configuration::from_builds(vec![
+                    create_build("foo", &["bar", "baz"], &[]),
+                    create_build("bar", &["qux"], &[]),
+                    create_build("baz", &[], &["qux"]),
+                ]),
+                "foo",
+                "qux"
+            )
+            .unwrap(),
+            "foo\nbar\nqux\n"
+        );
+    }
+
+    #[test]
+    fn render_shortest_path() {
+        assert_eq!(
+            render(
+                &Configuration::from_builds(vec![
+                    create_build("foo", &["bar", "qux"], &[]),
+                    create_build("bar", &["qux"], &[]),
+                ]),
+                "foo",
+                "qux"
+            )
+            .unwrap(),
+            "foo\nqux\n"
+        );
+    }
+
+    #[test]
+    fn render_order_only_dependency() {
+        assert_eq!(
+            render(
+                &Configuration::from_builds(vec![create_build("foo", &[], &["bar"])]),
+                "foo",
+                "bar"
+            )
+            .unwrap(),
+            "foo\n|| bar\n"
+        );
+    }
+
+    #[test]
+    fn render_path_to_itself() {
+        assert_eq!(
+            render(
+                &Configuration::from_builds(vec![create_build("foo", &[], &[])]),
+                "foo",
+                "foo"
+            )
+            .unwrap(),
+            "foo\n"
+        );
+    }
+
+    #[test]
+    fn fail_to_render_missing_path() {
+        assert_eq!(
+            render(
+                &Configuration::from_builds(vec![
+                    create_build("foo", &[], &[]),
+                    create_build("bar", &["foo"], &[]),
+                ]),
+                "foo",
+                "bar"
+            ),
+            Err(ApplicationError::DependencyPathNotFound(
+                "foo".into(),
+                "bar".into()
+            ))
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use super::rule_name;
+use crate::{context::Context, error::ApplicationError, ir::Configuration};
+use std::fmt::Write;
+
+// Prints inputs and dependents of outputs.
+pub async fn query(
+    context: &Context,
+    configuration: &Configuration,
+    outputs: &[String],
+) -> Result<(), ApplicationError> {
+    context
+        .console()
+        .lock()
+        .await
+        .write_stdout(render(configuration, outputs)?.as_bytes())
+        .await?;
+
+    Ok(())
+}
+
+fn render(configuration: &Configuration, outputs: &[String]) -> Result<String, ApplicationError> {
+    let mut string = String::new();
+
+    for output in outputs {
+        let build = configuration
+            .outputs()
+            .get(output.as_str())
+            .ok_or_else(|| ApplicationError::OutputNotFound(output.clone()))?;
+
+        writeln!(string, "{output}:").unwrap();
+        writeln!(string, "  input: {}", rule_name(build)).unwrap();
+
+        for (prefix, inputs) in [
+            ("", build.explicit_inputs()),
+            ("| ", build.implicit_inputs()),
+            ("|| ", build.order_only_inputs()),
+        ] {
+            for input in inputs {
+                writeln!(string, "    {prefix}{input}").unwrap();
+            }
+        }
+
+        let mut dependents = configuration
+            .outputs()
+            .values()
+            .filter(|build| {
+                build
+                    .inputs()
+                    .iter()
+                    .chain(build.order_only_inputs())
+                    .any(|input| input.as_ref() == output)
+            })
+            .flat_map(|build| build.outputs())
+            .collect::<Vec<_>>();
+
+        dependents.sort();
+        dependents.dedup();
+
+        writeln!(string, "  outputs:").unwrap();
+
+        for dependent in dependents {
+            writeln!(string, "    {dependent}").unwrap();
+        }
+    }
+
+    Ok(string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Build, Rule};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn render_output() {
+        assert_eq!(
+            render(
+                &Configuration::from_builds(vec![
+                    Build::new(
+                        vec!["foo".into()],
+                        vec![],
+                        Some(Rule::new("", None).with_name("cc")),
+                        vec!["bar".into(), "baz".into()],
+                        vec!["qux".into()],
+                        None
+                    )
+                    .with_implicit_input_count(1),
+                    Build::new(
+                        vec!["quux".into()],
+                        vec![],
+                        None,
+                        vec!["foo".into()],
+                        vec![],
+                        None
+                    ),
+                ]),
+                &["foo".into()]
+            )
+            .unwrap(),
+            [
+                "foo:",
+                "  input: cc",
+                "    bar",
+                "    | baz",
+                "    || qux",
+                "  outputs:",
+                "    quux",
+                ""
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn fail_to_render_unknown_output() {
+        assert_eq!(
+            render(&Configuration::from_builds(vec![]), &["foo".into()]),
+            Err(ApplicationError::OutputNotFound("foo".into()))
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use crate::{
+    ast::{Module, Statement},
+    context::Context,
+    error::ApplicationError,
+};
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+const COMMAND_VARIABLE: &str = "command";
+
+// Lists rules defined in build files. Rules in modules other than a root one
+// are prefixed with their module paths. Their command templates are also
+// printed with a `-a` option.
+pub async fn rules(
+    context: &Context,
+    modules: &HashMap<PathBuf, Module>,
+    root_module_path: &Path,
+    arguments: &[String],
+) -> Result<(), ApplicationError> {
+    context
+        .console()
+        .lock()
+        .await
+        .write_stdout(render(modules, root_module_path, arguments)?.as_bytes())
+        .await?;
+
+    Ok(())
+}
+
+fn render(
+    modules: &HashMap<PathBuf, Module>,
+    root_module_path: &Path,
+    arguments: &[String],
+) -> Result<String, ApplicationError> {
+    let all = match arguments {
+        [] => false,
+        [argument] if argument == "-a" => true,
+        [argument, ..] => return Err(ApplicationError::InvalidToolArgument(argument.clone())),
+    };
+    let directory = root_module_path.parent().unwrap_or(root_module_path);
+
+    let mut rules = modules
+        .iter()
+        .flat_map(|(path, module)| {
+            let prefix = if path == root_module_path {
+                String::new()
+            } else {
+                format!(
+                    "{}:",
+                    path.strip_prefix(directory).unwrap_or(path).display()
+                )
+            };
+
+            module
+                .statements()
+                .iter()
+                .filter_map(|statement| match statement {
+                    Statement::Rule(rule) => Some(rule),
+                    _ => None,
+                })
+                .map(move |rule| (prefix.clone(), rule))
+        })
+        .collect::<Vec<_>>();
+
+    rules.sort_by(|(one_prefix, one), (other_prefix, other)| {
+        (one_prefix, one.name()).cmp(&(other_prefix, other.name()))
+    });
+
+    let mut string = String::new();
+
+    for (prefix, rule) in rules {
+        write!(string, "{}{}", prefix, rule.name()).unwrap();
+
+        if all {
+            if let Some(definition) = rule
+                .variable_definitions()
+                .iter()
+                .rev()
+                .find(|definition| definition.name() == COMMAND_VARIABLE)
+            {
+                write!(string, ": {}", definition.value()).unwrap();
+            }
+        }
+
+        writeln!(string).unwrap();
+    }
+
+    Ok(string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::{Rule, VariableDefinition};
+    use pretty_assertions::assert_eq;
+
+    fn create_rule(name: &str, command: &str) -> Statement {
+        Rule::new(name, vec![VariableDefinition::new("command", command)]).into()
+    }
+
+    fn create_modules() -> HashMap<PathBuf, Module> {
+        [
+            (
+                PathBuf::from("/foo/build.ninja"),
+                Module::new(vec![
+                    create_rule("link", "ld $in"),
+                    create_rule("cc", "cc $in"),
+                ]),
+            ),
+            (
+                PathBuf::from("/foo/bar/build.ninja"),
+                Module::new(vec![create_rule("cc", "clang $in")]),
+            ),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    #[test]
+    fn render_rules() {
+        assert_eq!(
+            render(&create_modules(), Path::new("/foo/build.ninja"), &[]).unwrap(),
+            "cc\nlink\nbar/build.ninja:cc\n"
+        );
+    }
+
+    #[test]
+    fn render_rules_with_commands() {
+        assert_eq!(
+            render(
+                &create_modules(),
+                Path::new("/foo/build.ninja"),
+                &["-a".into()]
+            )
+            .unwrap(),
+            "cc: cc $in\nlink: ld $in\nbar/build.ninja:cc: clang $in\n"
+        );
+    }
+
+    #[test]
+    fn fail_to_render_with_invalid_argument() {
+        assert_eq!(
+            render(
+                &create_modules(),
+                Path::new("/foo/build.ninja"),
+                &["foo".into()]
+            ),
+            Err(ApplicationError::InvalidToolArgument("foo".into()))
+        );
+    }
+}
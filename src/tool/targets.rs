@@ -0,0 +1,203 @@
+use super::rule_name;
+use crate::{context::Context, error::ApplicationError, ir::Configuration};
+use std::{collections::HashSet, fmt::Write};
+
+const DEFAULT_DEPTH: usize = 1;
+
+// Lists outputs in one of the following modes:
+//
+// - `all`: all outputs with their rules
+// - `default`: default outputs
+// - `depth [N]`: outputs reachable from default outputs in a depth
+// - `rule NAME`: outputs of a rule
+pub async fn targets(
+    context: &Context,
+    configuration: &Configuration,
+    arguments: &[String],
+) -> Result<(), ApplicationError> {
+    context
+        .console()
+        .lock()
+        .await
+        .write_stdout(render(configuration, arguments)?.as_bytes())
+        .await?;
+
+    Ok(())
+}
+
+fn render(configuration: &Configuration, arguments: &[String]) -> Result<String, ApplicationError> {
+    let mut string = String::new();
+
+    match arguments
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [] | ["all"] => {
+            for output in sort(configuration.outputs().keys().map(AsRef::as_ref)) {
+                writeln!(
+                    string,
+                    "{}: {}",
+                    output,
+                    rule_name(&configuration.outputs()[output])
+                )
+                .unwrap();
+            }
+        }
+        ["default"] => {
+            for output in sort(configuration.default_outputs().iter().map(AsRef::as_ref)) {
+                writeln!(string, "{output}").unwrap();
+            }
+        }
+        ["depth"] => render_depth(&mut string, configuration, DEFAULT_DEPTH),
+        ["depth", depth] => render_depth(
+            &mut string,
+            configuration,
+            depth
+                .parse()
+                .map_err(|_| ApplicationError::InvalidToolArgument(depth.to_string()))?,
+        ),
+        ["rule", name] => {
+            for output in sort(
+                configuration
+                    .outputs()
+                    .iter()
+                    .filter(|(_, build)| rule_name(build) == *name)
+                    .map(|(output, _)| output.as_ref()),
+            ) {
+                writeln!(string, "{output}").unwrap();
+            }
+        }
+        [argument, ..] => return Err(ApplicationError::InvalidToolArgument(argument.to_string())),
+    }
+
+    Ok(string)
+}
+
+// Zero depth means no limit.
+fn render_depth(string: &mut String, configuration: &Configuration, depth: usize) {
+    let mut outputs = sort(configuration.default_outputs().iter().map(AsRef::as_ref))
+        .into_iter()
+        .map(|output| (output, 0))
+        .collect::<Vec<_>>();
+    let mut visited = HashSet::new();
+
+    outputs.reverse();
+
+    while let Some((output, level)) = outputs.pop() {
+        let Some(build) = configuration.outputs().get(output) else {
+            continue;
+        };
+
+        writeln!(
+            string,
+            "{}{}: {}",
+            "  ".repeat(level),
+            output,
+            rule_name(build)
+        )
+        .unwrap();
+
+        if (depth == 0 || level + 1 < depth) && visited.insert(build.id()) {
+            outputs.extend(
+                build
+                    .inputs()
+                    .iter()
+                    .rev()
+                    .map(|input| (input.as_ref(), level + 1)),
+            );
+        }
+    }
+}
+
+fn sort<'a>(outputs: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut outputs = outputs.into_iter().collect::<Vec<_>>();
+    outputs.sort();
+    outputs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Build, Rule};
+    use pretty_assertions::assert_eq;
+
+    fn create_build(output: &str, rule: Option<&str>, inputs: &[&str]) -> Build {
+        Build::new(
+            vec![output.into()],
+            vec![],
+            rule.map(|name| Rule::new("", None).with_name(name)),
+            inputs.iter().map(|&input| input.into()).collect(),
+            vec![],
+            None,
+        )
+    }
+
+    fn create_default_configuration() -> Configuration {
+        Configuration::from_builds(vec![
+            create_build("foo", Some("cc"), &["bar"]),
+            create_build("bar", Some("cp"), &["baz"]),
+            create_build("all", None, &["foo"]),
+        ])
+        .with_default_outputs(&["all"])
+    }
+
+    fn render_arguments(arguments: &[&str]) -> Result<String, ApplicationError> {
+        render(
+            &create_default_configuration(),
+            &arguments
+                .iter()
+                .map(|&argument| argument.into())
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn render_all() {
+        assert_eq!(
+            render_arguments(&["all"]).unwrap(),
+            "all: phony\nbar: cp\nfoo: cc\n"
+        );
+        assert_eq!(
+            render_arguments(&[]).unwrap(),
+            render_arguments(&["all"]).unwrap()
+        );
+    }
+
+    #[test]
+    fn render_default() {
+        assert_eq!(render_arguments(&["default"]).unwrap(), "all\n");
+    }
+
+    #[test]
+    fn render_depth() {
+        assert_eq!(render_arguments(&["depth"]).unwrap(), "all: phony\n");
+        assert_eq!(
+            render_arguments(&["depth", "2"]).unwrap(),
+            "all: phony\n  foo: cc\n"
+        );
+        assert_eq!(
+            render_arguments(&["depth", "0"]).unwrap(),
+            "all: phony\n  foo: cc\n    bar: cp\n"
+        );
+    }
+
+    #[test]
+    fn render_rule() {
+        assert_eq!(render_arguments(&["rule", "cc"]).unwrap(), "foo\n");
+        assert_eq!(render_arguments(&["rule", "phony"]).unwrap(), "all\n");
+    }
+
+    #[test]
+    fn fail_to_render_with_invalid_argument() {
+        assert_eq!(
+            render_arguments(&["foo"]),
+            Err(ApplicationError::InvalidToolArgument("foo".into()))
+        );
+        assert_eq!(
+            render_arguments(&["depth", "foo"]),
+            Err(ApplicationError::InvalidToolArgument("foo".into()))
+        );
+    }
+}
@@ -0,0 +1,11 @@
+// Levels of messages from Turtle in an ascending order
+#[derive(Clone, Copy, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Verbosity {
+    // Nothing is shown but exit codes.
+    Silent,
+    // Progress status of build jobs is hidden while errors and summaries are
+    // still shown.
+    Quiet,
+    #[default]
+    Normal,
+}
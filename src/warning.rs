@@ -0,0 +1,167 @@
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WarningKey {
+    DeadOutput,
+    DuplicateBuild,
+    EmptyCommand,
+    UndefinedVariable,
+}
+
+impl WarningKey {
+    pub const ALL: &'static [Self] = &[
+        Self::DeadOutput,
+        Self::DuplicateBuild,
+        Self::EmptyCommand,
+        Self::UndefinedVariable,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::DeadOutput => "dead-output",
+            Self::DuplicateBuild => "dupbuild",
+            Self::EmptyCommand => "emptycmd",
+            Self::UndefinedVariable => "undefvar",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WarningLevel {
+    Off,
+    Warn,
+    Err,
+}
+
+impl FromStr for WarningLevel {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, String> {
+        match string {
+            "off" => Ok(Self::Off),
+            "warn" => Ok(Self::Warn),
+            "err" => Ok(Self::Err),
+            _ => Err(format!(
+                "unknown warning level \"{string}\" (valid levels: warn, err, off)"
+            )),
+        }
+    }
+}
+
+// Levels of warnings applied to build files.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WarningConfiguration {
+    pub dead_output: WarningLevel,
+    pub duplicate_build: WarningLevel,
+    pub empty_command: WarningLevel,
+    pub undefined_variable: WarningLevel,
+}
+
+impl WarningConfiguration {
+    pub fn level(&self, key: WarningKey) -> WarningLevel {
+        match key {
+            WarningKey::DeadOutput => self.dead_output,
+            WarningKey::DuplicateBuild => self.duplicate_build,
+            WarningKey::EmptyCommand => self.empty_command,
+            WarningKey::UndefinedVariable => self.undefined_variable,
+        }
+    }
+
+    pub fn set_level(&mut self, key: WarningKey, level: WarningLevel) {
+        *match key {
+            WarningKey::DeadOutput => &mut self.dead_output,
+            WarningKey::DuplicateBuild => &mut self.duplicate_build,
+            WarningKey::EmptyCommand => &mut self.empty_command,
+            WarningKey::UndefinedVariable => &mut self.undefined_variable,
+        } = level;
+    }
+}
+
+// Defaults follow Ninja's ones.
+impl Default for WarningConfiguration {
+    fn default() -> Self {
+        Self {
+            dead_output: WarningLevel::Off,
+            duplicate_build: WarningLevel::Err,
+            empty_command: WarningLevel::Warn,
+            undefined_variable: WarningLevel::Warn,
+        }
+    }
+}
+
+// Parses a warning setting of `KEY=LEVEL`. A key alone enables a warning.
+pub fn parse_setting(string: &str) -> Result<(WarningKey, WarningLevel), String> {
+    let (name, level) = string.split_once('=').unwrap_or((string, "warn"));
+    let key = WarningKey::ALL
+        .iter()
+        .copied()
+        .find(|key| key.name() == name)
+        .ok_or_else(|| {
+            format!(
+                "unknown warning \"{}\" (valid warnings: {})",
+                name,
+                WarningKey::ALL
+                    .iter()
+                    .map(|key| key.name())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })?;
+
+    Ok((key, level.parse()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_setting_with_level() {
+        assert_eq!(
+            parse_setting("dupbuild=warn"),
+            Ok((WarningKey::DuplicateBuild, WarningLevel::Warn))
+        );
+        assert_eq!(
+            parse_setting("undefvar=err"),
+            Ok((WarningKey::UndefinedVariable, WarningLevel::Err))
+        );
+        assert_eq!(
+            parse_setting("dead-output=off"),
+            Ok((WarningKey::DeadOutput, WarningLevel::Off))
+        );
+    }
+
+    #[test]
+    fn parse_setting_without_level() {
+        assert_eq!(
+            parse_setting("dead-output"),
+            Ok((WarningKey::DeadOutput, WarningLevel::Warn))
+        );
+    }
+
+    #[test]
+    fn fail_to_parse_unknown_key() {
+        assert_eq!(
+            parse_setting("foo=warn"),
+            Err("unknown warning \"foo\" (valid warnings: dead-output, dupbuild, emptycmd, undefvar)".into())
+        );
+    }
+
+    #[test]
+    fn fail_to_parse_unknown_level() {
+        assert!(parse_setting("dupbuild=foo").is_err());
+    }
+
+    #[test]
+    fn set_level() {
+        let mut configuration = WarningConfiguration::default();
+
+        configuration.set_level(WarningKey::DuplicateBuild, WarningLevel::Off);
+
+        assert_eq!(
+            configuration.level(WarningKey::DuplicateBuild),
+            WarningLevel::Off
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use crate::error::ApplicationError;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    collections::BTreeSet,
+    io,
+    path::{self, Path, PathBuf},
+    time::Duration,
+};
+use tokio::{
+    sync::mpsc::{unbounded_channel, UnboundedReceiver},
+    time::timeout,
+};
+
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(100);
+
+// Waits for modification of any of files notified by an operating system.
+// Successive modifications are debounced until files stay unmodified for a
+// debounce interval.
+pub async fn wait_changes(paths: &[PathBuf]) -> Result<(), ApplicationError> {
+    let paths = paths
+        .iter()
+        .map(|path| resolve_path(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let (sender, mut receiver) = unbounded_channel();
+    let mut watcher = RecommendedWatcher::new(
+        move |event| {
+            // A receiver is dropped only after a watcher.
+            let _ = sender.send(event);
+        },
+        Default::default(),
+    )?;
+
+    // Parent directories are watched instead of files as files can be created
+    // or replaced by renaming later.
+    for directory in paths
+        .iter()
+        .filter_map(|path| path.parent())
+        .map(existing_ancestor)
+        .collect::<BTreeSet<_>>()
+    {
+        watcher.watch(directory, RecursiveMode::NonRecursive)?;
+    }
+
+    wait_change(&paths, &mut receiver).await?;
+
+    while let Ok(result) = timeout(DEBOUNCE_INTERVAL, wait_change(&paths, &mut receiver)).await {
+        result?;
+    }
+
+    Ok(())
+}
+
+async fn wait_change(
+    paths: &[PathBuf],
+    receiver: &mut UnboundedReceiver<notify::Result<Event>>,
+) -> Result<(), ApplicationError> {
+    while let Some(event) = receiver.recv().await {
+        let event = event?;
+
+        // Creation of missing directories is a change of files in them.
+        if !event.kind.is_access()
+            && event
+                .paths
+                .iter()
+                .any(|changed| paths.iter().any(|path| path.starts_with(changed)))
+        {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+// Paths are resolved in the same way as the ones notified by an operating
+// system, which can resolve symbolic links of existing directories.
+fn resolve_path(path: &Path) -> Result<PathBuf, io::Error> {
+    let path = path::absolute(path)?;
+    let ancestor = existing_ancestor(&path);
+
+    Ok(ancestor
+        .canonicalize()?
+        .join(path.strip_prefix(ancestor).unwrap_or(&path)))
+}
+
+fn existing_ancestor(path: &Path) -> &Path {
+    path.ancestors().find(|path| path.exists()).unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn wait_modification() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("foo");
+
+        tokio::fs::write(&path, "foo").await.unwrap();
+
+        let paths = [path.clone()];
+        let future = wait_changes(&paths);
+
+        tokio::pin!(future);
+
+        assert!(timeout(DEBOUNCE_INTERVAL * 2, &mut future).await.is_err());
+
+        tokio::fs::write(&path, "bar").await.unwrap();
+
+        timeout(DEBOUNCE_INTERVAL * 10, future)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn wait_creation() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("foo");
+        let paths = [path.clone()];
+        let future = wait_changes(&paths);
+
+        tokio::pin!(future);
+
+        assert!(timeout(DEBOUNCE_INTERVAL * 2, &mut future).await.is_err());
+
+        tokio::fs::write(&path, "foo").await.unwrap();
+
+        timeout(DEBOUNCE_INTERVAL * 10, future)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn wait_creation_in_missing_directory() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("foo/bar");
+        let paths = [path.clone()];
+        let future = wait_changes(&paths);
+
+        tokio::pin!(future);
+
+        assert!(timeout(DEBOUNCE_INTERVAL * 2, &mut future).await.is_err());
+
+        tokio::fs::create_dir(path.parent().unwrap()).await.unwrap();
+
+        timeout(DEBOUNCE_INTERVAL * 10, future)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn ignore_other_files() {
+        let directory = tempdir().unwrap();
+        let paths = [directory.path().join("foo")];
+        let future = wait_changes(&paths);
+
+        tokio::pin!(future);
+
+        assert!(timeout(DEBOUNCE_INTERVAL * 2, &mut future).await.is_err());
+
+        tokio::fs::write(directory.path().join("bar"), "")
+            .await
+            .unwrap();
+
+        assert!(timeout(DEBOUNCE_INTERVAL * 2, future).await.is_err());
+    }
+}
@@ -0,0 +1,96 @@
+use async_trait::async_trait;
+use std::{
+    error::Error,
+    future::pending,
+    path::Path,
+    process::{ExitStatus, Output},
+    sync::{Arc, Mutex},
+};
+use turtle_build::{
+    compile, parse_modules, run, validate_modules, BufferConsole, CommandRunner, CompileOptions,
+    Context, Environment, InMemoryFileSystem, RunOptions,
+};
+
+#[derive(Clone, Default)]
+struct RecordingCommandRunner {
+    commands: Arc<Mutex<Vec<String>>>,
+    environments: Arc<Mutex<Vec<Environment>>>,
+}
+
+#[async_trait]
+impl CommandRunner for RecordingCommandRunner {
+    async fn run(
+        &self,
+        command: &str,
+        _directory: Option<&Path>,
+        environment: &Environment,
+    ) -> Result<Output, Box<dyn Error>> {
+        self.commands.lock().unwrap().push(command.into());
+        self.environments.lock().unwrap().push(environment.clone());
+
+        Ok(Output {
+            status: ExitStatus::default(),
+            stdout: vec![],
+            stderr: vec![],
+        })
+    }
+
+    async fn run_console(
+        &self,
+        _command: &str,
+        _directory: Option<&Path>,
+        _environment: &Environment,
+    ) -> Result<ExitStatus, Box<dyn Error>> {
+        unreachable!()
+    }
+}
+
+#[tokio::test]
+async fn run_builds_with_custom_command_runner() {
+    let runner = RecordingCommandRunner::default();
+    let context = Arc::new(
+        Context::new(
+            BufferConsole::new(),
+            InMemoryFileSystem::new()
+                .with_file(
+                    "build.ninja",
+                    "rule cp\n  command = cp $in $out\n  environment = FOO=foo\nbuild bar: cp foo\n",
+                )
+                .with_file("foo", ""),
+        )
+        .with_command_runner(runner.clone())
+        .with_temporary_database()
+        .unwrap(),
+    );
+    let root_paths = [context
+        .file_system()
+        .canonicalize_path(Path::new("build.ninja"))
+        .await
+        .unwrap()];
+
+    let (modules, dependencies) = parse_modules(&context, &root_paths).await.unwrap();
+    validate_modules(&dependencies).unwrap();
+    let (configuration, _) = compile(
+        &modules,
+        &dependencies,
+        &root_paths,
+        &CompileOptions::default(),
+    )
+    .unwrap();
+
+    run(
+        &context,
+        configuration.into(),
+        &[],
+        RunOptions::default(),
+        pending(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(*runner.commands.lock().unwrap(), ["cp foo bar"]);
+    assert_eq!(
+        runner.environments.lock().unwrap()[0].variables(),
+        [("FOO".into(), "foo".into())]
+    );
+}